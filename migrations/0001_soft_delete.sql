@@ -0,0 +1,10 @@
+-- migrations/0001_soft_delete.sql
+--
+-- Exclusão lógica (soft delete) de produtos e categorias e perfil de acesso dos usuários.
+
+-- Momento da exclusão lógica. NULL indica que o registro está ativo.
+ALTER TABLE produtos ADD COLUMN deleted_at TIMESTAMPTZ NULL;
+ALTER TABLE categorias ADD COLUMN deleted_at TIMESTAMPTZ NULL;
+
+-- Perfil de acesso do usuário: 'cliente' (padrão) ou 'admin'.
+ALTER TABLE usuarios ADD COLUMN perfil VARCHAR(20) NOT NULL DEFAULT 'cliente';
@@ -0,0 +1,41 @@
+-- migrations/0002_auditoria.sql
+--
+-- Pedidos persistidos e trilha de auditoria das alterações administrativas.
+
+-- Pedidos (vendas) realizados pelos clientes.
+CREATE TABLE vendas (
+    id SERIAL PRIMARY KEY,
+    usuario_id INTEGER NOT NULL REFERENCES usuarios(id),
+    total NUMERIC(12, 2) NOT NULL,
+    status VARCHAR(30) NOT NULL DEFAULT 'confirmado',
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Itens de cada pedido, com o preço praticado no momento da venda.
+CREATE TABLE vendas_itens (
+    id SERIAL PRIMARY KEY,
+    venda_id INTEGER NOT NULL REFERENCES vendas(id),
+    produto_id INTEGER NOT NULL REFERENCES produtos(id),
+    quantidade INTEGER NOT NULL,
+    preco_unitario NUMERIC(12, 2) NOT NULL
+);
+
+CREATE INDEX idx_vendas_usuario ON vendas (usuario_id);
+CREATE INDEX idx_vendas_itens_venda ON vendas_itens (venda_id);
+
+-- Registro de quem alterou o quê, com snapshots da linha antes e depois da alteração.
+-- Não há chave estrangeira para usuarios: o histórico deve sobreviver ao autor.
+CREATE TABLE auditoria (
+    id SERIAL PRIMARY KEY,
+    usuario_id INTEGER NOT NULL,
+    usuario_email VARCHAR(255) NOT NULL,
+    entidade VARCHAR(50) NOT NULL,
+    entidade_id INTEGER NOT NULL,
+    acao VARCHAR(30) NOT NULL,
+    antes JSONB NULL,
+    depois JSONB NULL,
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_auditoria_entidade ON auditoria (entidade, entidade_id);
+CREATE INDEX idx_auditoria_criado_em ON auditoria (criado_em);
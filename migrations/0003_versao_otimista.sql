@@ -0,0 +1,7 @@
+-- migrations/0003_versao_otimista.sql
+--
+-- Controle de concorrência otimista: cada alteração incrementa a versão do registro,
+-- exposta como ETag e exigida em If-Match nas atualizações.
+
+ALTER TABLE produtos ADD COLUMN versao INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE categorias ADD COLUMN versao INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,11 @@
+-- migrations/0004_perfil_usuario.sql
+--
+-- Dados de perfil do cliente e anonimização de contas excluídas (LGPD).
+
+ALTER TABLE usuarios ADD COLUMN telefone VARCHAR(11) NULL;
+ALTER TABLE usuarios ADD COLUMN cpf VARCHAR(11) NULL UNIQUE;
+ALTER TABLE usuarios ADD COLUMN data_nascimento DATE NULL;
+
+-- Momento em que a conta foi excluída e seus dados pessoais anonimizados.
+-- A linha é mantida para preservar os pedidos que a referenciam.
+ALTER TABLE usuarios ADD COLUMN anonimizado_em TIMESTAMPTZ NULL;
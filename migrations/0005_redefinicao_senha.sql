@@ -0,0 +1,14 @@
+-- migrations/0005_redefinicao_senha.sql
+--
+-- Tokens de uso único para redefinição de senha. Apenas o hash SHA-256 do token é armazenado.
+
+CREATE TABLE tokens_redefinicao_senha (
+    id SERIAL PRIMARY KEY,
+    usuario_id INTEGER NOT NULL REFERENCES usuarios(id),
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    expira_em TIMESTAMPTZ NOT NULL,
+    usado_em TIMESTAMPTZ NULL,
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tokens_redefinicao_usuario ON tokens_redefinicao_senha (usuario_id);
@@ -0,0 +1,8 @@
+-- migrations/0006_verificacao_email.sql
+--
+-- Verificação de e-mail no cadastro. NULL indica conta ainda não verificada.
+
+ALTER TABLE usuarios ADD COLUMN email_verificado_em TIMESTAMPTZ NULL;
+
+-- Contas existentes antes da verificação são consideradas verificadas.
+UPDATE usuarios SET email_verificado_em = NOW() WHERE email_verificado_em IS NULL;
@@ -0,0 +1,23 @@
+-- migrations/0007_protecao_login.sql
+--
+-- Proteção contra força bruta no login: contadores de falhas com bloqueio temporário
+-- por e-mail e por IP, e registro dos eventos de login para revisão.
+
+CREATE TABLE bloqueios_login (
+    chave VARCHAR(320) PRIMARY KEY, -- 'email:<email>' ou 'ip:<endereço>'
+    falhas INTEGER NOT NULL DEFAULT 0,
+    ultima_falha TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    bloqueado_ate TIMESTAMPTZ NULL
+);
+
+CREATE TABLE eventos_login (
+    id SERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    ip VARCHAR(45) NOT NULL,
+    usuario_id INTEGER NULL,
+    resultado VARCHAR(20) NOT NULL, -- 'sucesso', 'falha' ou 'bloqueado'
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_eventos_login_email ON eventos_login (email, criado_em);
+CREATE INDEX idx_eventos_login_ip ON eventos_login (ip, criado_em);
@@ -0,0 +1,19 @@
+-- migrations/0008_dois_fatores.sql
+--
+-- Autenticação em dois fatores (TOTP) opcional.
+
+-- Segredo TOTP em Base32. Preenchido na inscrição; só passa a valer após a ativação.
+ALTER TABLE usuarios ADD COLUMN totp_segredo VARCHAR(64) NULL;
+ALTER TABLE usuarios ADD COLUMN totp_ativado_em TIMESTAMPTZ NULL;
+-- Último passo de tempo aceito, para impedir a reutilização de um mesmo código.
+ALTER TABLE usuarios ADD COLUMN totp_ultimo_passo BIGINT NULL;
+
+-- Códigos de recuperação de uso único. Apenas o hash SHA-256 é armazenado.
+CREATE TABLE codigos_recuperacao_2fa (
+    id SERIAL PRIMARY KEY,
+    usuario_id INTEGER NOT NULL REFERENCES usuarios(id),
+    codigo_hash CHAR(64) NOT NULL,
+    usado_em TIMESTAMPTZ NULL
+);
+
+CREATE INDEX idx_codigos_recuperacao_usuario ON codigos_recuperacao_2fa (usuario_id);
@@ -0,0 +1,25 @@
+-- migrations/0009_enderecos.sql
+--
+-- Catálogo de endereços do cliente e endereço de entrega dos pedidos.
+
+CREATE TABLE enderecos (
+    id SERIAL PRIMARY KEY,
+    usuario_id INTEGER NOT NULL REFERENCES usuarios(id),
+    cep CHAR(8) NOT NULL,
+    logradouro VARCHAR(255) NOT NULL,
+    numero VARCHAR(20) NOT NULL,
+    complemento VARCHAR(255) NULL,
+    bairro VARCHAR(255) NOT NULL,
+    cidade VARCHAR(255) NOT NULL,
+    uf CHAR(2) NOT NULL,
+    padrao BOOLEAN NOT NULL DEFAULT FALSE,
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_enderecos_usuario ON enderecos (usuario_id);
+-- No máximo um endereço padrão por cliente.
+CREATE UNIQUE INDEX idx_enderecos_padrao ON enderecos (usuario_id) WHERE padrao;
+
+-- Cópia do endereço no momento da compra. Alterar ou excluir o endereço depois
+-- não muda o pedido. Pedidos anteriores a esta migração ficam sem endereço.
+ALTER TABLE vendas ADD COLUMN endereco_entrega JSONB NULL;
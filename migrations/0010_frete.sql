@@ -0,0 +1,18 @@
+-- migrations/0010_frete.sql
+--
+-- Peso e dimensões dos produtos para o cálculo de frete, e frete escolhido nos pedidos.
+
+-- Os produtos existentes recebem medidas provisórias; revise-as antes de vender.
+ALTER TABLE produtos ADD COLUMN peso_gramas INTEGER NOT NULL DEFAULT 500 CHECK (peso_gramas > 0);
+ALTER TABLE produtos ADD COLUMN altura_cm INTEGER NOT NULL DEFAULT 10 CHECK (altura_cm > 0);
+ALTER TABLE produtos ADD COLUMN largura_cm INTEGER NOT NULL DEFAULT 15 CHECK (largura_cm > 0);
+ALTER TABLE produtos ADD COLUMN comprimento_cm INTEGER NOT NULL DEFAULT 20 CHECK (comprimento_cm > 0);
+ALTER TABLE produtos ALTER COLUMN peso_gramas DROP DEFAULT;
+ALTER TABLE produtos ALTER COLUMN altura_cm DROP DEFAULT;
+ALTER TABLE produtos ALTER COLUMN largura_cm DROP DEFAULT;
+ALTER TABLE produtos ALTER COLUMN comprimento_cm DROP DEFAULT;
+
+-- O total do pedido passa a incluir o frete.
+ALTER TABLE vendas ADD COLUMN frete_modalidade VARCHAR(50) NULL;
+ALTER TABLE vendas ADD COLUMN frete_valor NUMERIC(12, 2) NOT NULL DEFAULT 0;
+ALTER TABLE vendas ADD COLUMN frete_prazo_dias INTEGER NULL;
@@ -0,0 +1,27 @@
+-- migrations/0011_pagamentos.sql
+--
+-- Pagamento dos pedidos e reserva de estoque até a confirmação.
+
+-- Novos pedidos nascem como 'pendente'. O estoque fica reservado até
+-- reserva_expira_em; sem pagamento, o pedido é cancelado e o estoque devolvido.
+ALTER TABLE vendas ADD COLUMN metodo_pagamento VARCHAR(20) NULL;
+ALTER TABLE vendas ADD COLUMN reserva_expira_em TIMESTAMPTZ NULL;
+ALTER TABLE vendas ALTER COLUMN status SET DEFAULT 'pendente';
+
+CREATE INDEX idx_vendas_reserva_pendente ON vendas (reserva_expira_em) WHERE status = 'pendente';
+
+-- Cobranças criadas no gateway de pagamento.
+CREATE TABLE pagamentos (
+    id SERIAL PRIMARY KEY,
+    venda_id INTEGER NOT NULL REFERENCES vendas(id),
+    gateway VARCHAR(50) NOT NULL,
+    referencia VARCHAR(100) NOT NULL UNIQUE, -- Identificador da cobrança no gateway
+    metodo VARCHAR(20) NOT NULL,
+    valor NUMERIC(12, 2) NOT NULL,
+    situacao VARCHAR(20) NOT NULL,
+    instrucoes JSONB NULL, -- Código Pix, linha digitável do boleto etc.
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    atualizado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_pagamentos_venda ON pagamentos (venda_id);
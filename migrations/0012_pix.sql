@@ -0,0 +1,6 @@
+-- migrations/0012_pix.sql
+--
+-- Identificador da transação Pix recebida.
+
+-- endToEndId informado pelo banco no webhook do Pix; usado na conciliação e em devoluções.
+ALTER TABLE pagamentos ADD COLUMN end_to_end_id VARCHAR(32) NULL;
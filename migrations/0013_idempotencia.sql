@@ -0,0 +1,19 @@
+-- migrations/0013_idempotencia.sql
+--
+-- Chaves de idempotência (cabeçalho Idempotency-Key) das rotas POST.
+
+-- Cada chave vale por rota e por usuário (dono = 0 nas rotas sem login).
+-- status_http NULL indica que a primeira requisição ainda está em andamento.
+CREATE TABLE idempotencia (
+    rota VARCHAR(200) NOT NULL,
+    dono INTEGER NOT NULL,
+    chave VARCHAR(255) NOT NULL,
+    hash_requisicao CHAR(64) NOT NULL, -- SHA-256 do método e do corpo da requisição
+    status_http SMALLINT NULL,
+    corpo_resposta BYTEA NULL,
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    concluido_em TIMESTAMPTZ NULL,
+    PRIMARY KEY (rota, dono, chave)
+);
+
+CREATE INDEX idx_idempotencia_criado_em ON idempotencia (criado_em);
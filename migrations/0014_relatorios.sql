@@ -0,0 +1,14 @@
+-- migrations/0014_relatorios.sql
+--
+-- Dados para os relatórios de vendas.
+
+-- Sacolas iniciadas (primeiro item adicionado) e o pedido em que cada uma foi finalizada.
+-- Base da taxa de conversão de sacola para compra.
+CREATE TABLE sacolas (
+    id SERIAL PRIMARY KEY,
+    criada_em TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    venda_id INTEGER NULL REFERENCES vendas(id)
+);
+
+CREATE INDEX idx_sacolas_criada_em ON sacolas (criada_em);
+CREATE INDEX idx_vendas_criado_em ON vendas (criado_em);
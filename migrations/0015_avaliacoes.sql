@@ -0,0 +1,20 @@
+-- migrations/0015_avaliacoes.sql
+--
+-- Avaliações de produtos feitas pelos clientes, com moderação administrativa.
+
+-- Cada cliente tem no máximo uma avaliação por produto; enviar de novo substitui a anterior.
+-- Só avaliações 'aprovada' aparecem na loja e entram na média do produto.
+CREATE TABLE avaliacoes (
+    id SERIAL PRIMARY KEY,
+    produto_id INTEGER NOT NULL REFERENCES produtos(id),
+    usuario_id INTEGER NOT NULL REFERENCES usuarios(id),
+    nota SMALLINT NOT NULL CHECK (nota BETWEEN 1 AND 5),
+    texto TEXT NOT NULL,
+    situacao VARCHAR(20) NOT NULL DEFAULT 'pendente' CHECK (situacao IN ('pendente', 'aprovada', 'rejeitada')),
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    moderado_em TIMESTAMPTZ NULL,
+    UNIQUE (produto_id, usuario_id)
+);
+
+CREATE INDEX idx_avaliacoes_aprovadas ON avaliacoes (produto_id) WHERE situacao = 'aprovada';
+CREATE INDEX idx_avaliacoes_pendentes ON avaliacoes (criado_em) WHERE situacao = 'pendente';
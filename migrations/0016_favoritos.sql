@@ -0,0 +1,24 @@
+-- migrations/0016_favoritos.sql
+--
+-- Lista de favoritos dos clientes e registro de reduções de preço dos produtos favoritados.
+
+CREATE TABLE favoritos (
+    usuario_id INTEGER NOT NULL REFERENCES usuarios(id),
+    produto_id INTEGER NOT NULL REFERENCES produtos(id),
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (usuario_id, produto_id)
+);
+
+CREATE INDEX idx_favoritos_produto ON favoritos (produto_id);
+
+-- Reduções de preço feitas pela atualização de produto, registradas apenas para produtos
+-- que estão nos favoritos de alguém. Base para avisar os clientes interessados.
+CREATE TABLE quedas_preco (
+    id SERIAL PRIMARY KEY,
+    produto_id INTEGER NOT NULL REFERENCES produtos(id),
+    preco_anterior NUMERIC(12, 2) NOT NULL,
+    preco_novo NUMERIC(12, 2) NOT NULL,
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_quedas_preco_produto ON quedas_preco (produto_id, criado_em);
@@ -0,0 +1,21 @@
+-- migrations/0017_avise_me.sql
+--
+-- Inscrições "avise-me quando chegar" em produtos sem estoque.
+
+-- Ciclo de uma inscrição: criada com o produto esgotado, enfileirada quando o estoque volta
+-- a ficar positivo e notificada pelo envio periódico. Inscrições não enfileiradas expiram.
+CREATE TABLE inscricoes_estoque (
+    id SERIAL PRIMARY KEY,
+    usuario_id INTEGER NOT NULL REFERENCES usuarios(id),
+    produto_id INTEGER NOT NULL REFERENCES produtos(id),
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expira_em TIMESTAMPTZ NOT NULL,
+    enfileirada_em TIMESTAMPTZ NULL,
+    notificado_em TIMESTAMPTZ NULL,
+    tentativas INTEGER NOT NULL DEFAULT 0 -- Falhas de envio; após o limite, o aviso é abandonado
+);
+
+-- No máximo uma inscrição ainda não notificada por cliente e produto.
+CREATE UNIQUE INDEX idx_inscricoes_estoque_ativa ON inscricoes_estoque (usuario_id, produto_id) WHERE notificado_em IS NULL;
+CREATE INDEX idx_inscricoes_estoque_produto ON inscricoes_estoque (produto_id) WHERE enfileirada_em IS NULL;
+CREATE INDEX idx_inscricoes_estoque_fila ON inscricoes_estoque (enfileirada_em) WHERE enfileirada_em IS NOT NULL AND notificado_em IS NULL;
@@ -0,0 +1,62 @@
+-- migrations/0018_atributos.sql
+--
+-- Atributos estruturados dos produtos (tipo de pele, vegano, volume...), definidos por categoria
+-- e usados nos filtros da listagem.
+
+-- Definição de cada atributo. `codigo` é o nome usado na API e na query string dos filtros.
+-- Atributos `opcao` e `multiplas_opcoes` só aceitam valores de `opcoes`.
+CREATE TABLE atributos (
+    id SERIAL PRIMARY KEY,
+    codigo VARCHAR(50) NOT NULL UNIQUE,
+    nome VARCHAR(100) NOT NULL,
+    tipo VARCHAR(20) NOT NULL CHECK (tipo IN ('texto', 'opcao', 'multiplas_opcoes', 'booleano', 'numero')),
+    unidade VARCHAR(20) NULL, -- Só para atributos numéricos, ex.: "ml"
+    opcoes TEXT[] NOT NULL DEFAULT '{}',
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Atributos que se aplicam a cada categoria. Subcategorias herdam os atributos das categorias acima.
+CREATE TABLE categorias_atributos (
+    categoria_id INTEGER NOT NULL REFERENCES categorias(id),
+    atributo_id INTEGER NOT NULL REFERENCES atributos(id),
+    obrigatorio BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (categoria_id, atributo_id)
+);
+
+-- Valores dos produtos: uma linha por valor (atributos de múltiplas opções têm várias).
+-- `valor` guarda o texto normalizado; `valor_numero` repete o valor dos atributos numéricos para filtros por faixa.
+CREATE TABLE produtos_atributos (
+    produto_id INTEGER NOT NULL REFERENCES produtos(id),
+    atributo_id INTEGER NOT NULL REFERENCES atributos(id),
+    valor TEXT NOT NULL,
+    valor_numero NUMERIC NULL,
+    PRIMARY KEY (produto_id, atributo_id, valor)
+);
+
+CREATE INDEX idx_produtos_atributos_valor ON produtos_atributos (atributo_id, valor);
+
+-- Atributos de cada produto como objeto JSON ({"codigo": valor}), no tipo de cada atributo.
+CREATE VIEW produtos_atributos_json AS
+SELECT produto_id, jsonb_object_agg(codigo, valor) AS atributos
+FROM (
+    SELECT pa.produto_id, a.codigo,
+           CASE a.tipo
+               WHEN 'multiplas_opcoes' THEN jsonb_agg(pa.valor ORDER BY pa.valor)
+               WHEN 'booleano' THEN to_jsonb(bool_or(pa.valor = 'true'))
+               WHEN 'numero' THEN to_jsonb(MAX(pa.valor_numero))
+               ELSE to_jsonb(MAX(pa.valor))
+           END AS valor
+    FROM produtos_atributos pa
+    JOIN atributos a ON a.id = pa.atributo_id
+    GROUP BY pa.produto_id, a.id, a.codigo, a.tipo
+) valores
+GROUP BY produto_id;
+
+-- Atributos usuais de produtos de beleza. Cada loja liga às suas categorias os que fizerem sentido.
+INSERT INTO atributos (codigo, nome, tipo, unidade, opcoes) VALUES
+    ('tipo_pele', 'Tipo de pele', 'multiplas_opcoes', NULL, ARRAY['normal', 'seca', 'oleosa', 'mista', 'sensivel']),
+    ('tipo_cabelo', 'Tipo de cabelo', 'multiplas_opcoes', NULL, ARRAY['normal', 'seco', 'oleoso', 'misto', 'cacheado', 'crespo', 'danificado']),
+    ('vegano', 'Vegano', 'booleano', NULL, '{}'),
+    ('cruelty_free', 'Cruelty-free', 'booleano', NULL, '{}'),
+    ('sem_fragrancia', 'Sem fragrância', 'booleano', NULL, '{}'),
+    ('volume', 'Volume', 'numero', 'ml', '{}');
@@ -0,0 +1,38 @@
+-- migrations/0019_marcas.sql
+--
+-- Marcas dos produtos, com página própria no catálogo.
+
+CREATE TABLE marcas (
+    id SERIAL PRIMARY KEY,
+    nome VARCHAR(100) NOT NULL,
+    descricao TEXT NULL,
+    versao INTEGER NOT NULL DEFAULT 1,
+    deleted_at TIMESTAMPTZ NULL,
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Não pode haver duas marcas ativas com o mesmo nome, sem diferenciar maiúsculas.
+CREATE UNIQUE INDEX idx_marcas_nome ON marcas (LOWER(nome)) WHERE deleted_at IS NULL;
+
+-- Marca do produto. Opcional: nem todo produto tem marca cadastrada.
+ALTER TABLE produtos ADD COLUMN marca_id INTEGER NULL REFERENCES marcas(id);
+
+CREATE INDEX idx_produtos_marca ON produtos (marca_id) WHERE deleted_at IS NULL;
+
+-- A marca deixa de ser um atributo de texto: os valores já gravados viram marcas.
+INSERT INTO marcas (nome)
+SELECT DISTINCT ON (LOWER(pa.valor)) pa.valor
+FROM produtos_atributos pa
+JOIN atributos a ON a.id = pa.atributo_id
+WHERE a.codigo = 'marca'
+ORDER BY LOWER(pa.valor), pa.valor;
+
+UPDATE produtos p SET marca_id = m.id
+FROM produtos_atributos pa
+JOIN atributos a ON a.id = pa.atributo_id
+JOIN marcas m ON LOWER(m.nome) = LOWER(pa.valor)
+WHERE a.codigo = 'marca' AND pa.produto_id = p.id;
+
+DELETE FROM produtos_atributos WHERE atributo_id IN (SELECT id FROM atributos WHERE codigo = 'marca');
+DELETE FROM categorias_atributos WHERE atributo_id IN (SELECT id FROM atributos WHERE codigo = 'marca');
+DELETE FROM atributos WHERE codigo = 'marca';
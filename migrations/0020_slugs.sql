@@ -0,0 +1,93 @@
+-- migrations/0020_slugs.sql
+--
+-- Endereços amigáveis (slugs) de produtos e categorias, gerados a partir do nome.
+-- Todo slug já usado por um registro continua reservado para ele, para que os endereços
+-- antigos sigam funcionando depois de uma troca de nome.
+
+-- Histórico de slugs. `entidade` separa os espaços de nomes ('produto' ou 'categoria',
+-- que inclui as sessões); o slug atual de cada registro fica na coluna `slug` da própria tabela.
+CREATE TABLE slugs (
+    id SERIAL PRIMARY KEY,
+    entidade VARCHAR(20) NOT NULL,
+    entidade_id INTEGER NOT NULL,
+    slug VARCHAR(200) NOT NULL,
+    criado_em TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (entidade, slug)
+);
+
+-- Converte um nome em slug: sem acentos, minúsculo, com hífens no lugar de espaços e pontuação.
+-- Ex.: 'Sérum Facial' -> 'serum-facial'.
+CREATE FUNCTION gerar_slug(nome TEXT) RETURNS TEXT AS $$
+    SELECT LEFT(TRIM(BOTH '-' FROM REGEXP_REPLACE(
+        TRANSLATE(
+            LOWER(nome),
+            'áàâãäåāéèêëēíìîïīóòôõöøōúùûüūçñýÿ',
+            'aaaaaaaeeeeeiiiiiooooooouuuuucnyy'
+        ),
+        '[^a-z0-9]+', '-', 'g'
+    )), 180)
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- Reserva para o registro o primeiro slug livre a partir do nome ('serum-facial', 'serum-facial-2', ...).
+-- Um slug que já pertence ao próprio registro é reaproveitado. A unicidade de `slugs` resolve
+-- cadastros simultâneos com o mesmo nome: quem perde a disputa passa ao próximo sufixo.
+CREATE FUNCTION reservar_slug(p_entidade TEXT, p_entidade_id INTEGER, p_nome TEXT) RETURNS TEXT AS $$
+DECLARE
+    base TEXT := gerar_slug(p_nome);
+    candidato TEXT;
+    dono INTEGER;
+    sufixo INTEGER := 1;
+BEGIN
+    IF base = '' THEN
+        base := p_entidade;
+    END IF;
+    LOOP
+        candidato := CASE WHEN sufixo = 1 THEN base ELSE base || '-' || sufixo END;
+        INSERT INTO slugs (entidade, entidade_id, slug) VALUES (p_entidade, p_entidade_id, candidato)
+        ON CONFLICT (entidade, slug) DO NOTHING;
+        SELECT entidade_id INTO dono FROM slugs WHERE entidade = p_entidade AND slug = candidato;
+        IF dono = p_entidade_id THEN
+            RETURN candidato;
+        END IF;
+        sufixo := sufixo + 1;
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Gera o slug no cadastro e a cada troca de nome, em qualquer rota que grave a tabela.
+-- O argumento do gatilho é a entidade do histórico.
+CREATE FUNCTION atualizar_slug() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR NEW.nome IS DISTINCT FROM OLD.nome THEN
+        NEW.slug := reservar_slug(TG_ARGV[0], NEW.id, NEW.nome);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE produtos ADD COLUMN slug VARCHAR(200) NULL;
+ALTER TABLE categorias ADD COLUMN slug VARCHAR(200) NULL;
+
+-- Preenche os registros existentes, na ordem de cadastro (o mais antigo fica com o slug sem sufixo).
+DO $$
+DECLARE
+    r RECORD;
+BEGIN
+    FOR r IN SELECT id, nome FROM produtos ORDER BY id LOOP
+        UPDATE produtos SET slug = reservar_slug('produto', r.id, r.nome) WHERE id = r.id;
+    END LOOP;
+    FOR r IN SELECT id, nome FROM categorias ORDER BY id LOOP
+        UPDATE categorias SET slug = reservar_slug('categoria', r.id, r.nome) WHERE id = r.id;
+    END LOOP;
+END;
+$$;
+
+ALTER TABLE produtos ALTER COLUMN slug SET NOT NULL;
+ALTER TABLE categorias ALTER COLUMN slug SET NOT NULL;
+CREATE UNIQUE INDEX idx_produtos_slug ON produtos (slug);
+CREATE UNIQUE INDEX idx_categorias_slug ON categorias (slug);
+
+CREATE TRIGGER produtos_slug BEFORE INSERT OR UPDATE OF nome ON produtos
+    FOR EACH ROW EXECUTE FUNCTION atualizar_slug('produto');
+CREATE TRIGGER categorias_slug BEFORE INSERT OR UPDATE OF nome ON categorias
+    FOR EACH ROW EXECUTE FUNCTION atualizar_slug('categoria');
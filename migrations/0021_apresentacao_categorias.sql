@@ -0,0 +1,23 @@
+-- migrations/0021_apresentacao_categorias.sql
+--
+-- Apresentação das sessões e categorias no menu da loja: ordem de exibição, visibilidade,
+-- descrição, imagens e metadados para buscadores.
+
+-- Posição entre as categorias com o mesmo pai (menor primeiro). Reordenar não mexe nos IDs.
+ALTER TABLE categorias ADD COLUMN posicao INTEGER NOT NULL DEFAULT 0;
+-- Categorias ocultas continuam existindo (e com seus produtos), mas saem do menu.
+ALTER TABLE categorias ADD COLUMN visivel BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE categorias ADD COLUMN descricao TEXT NULL;
+ALTER TABLE categorias ADD COLUMN icone_url TEXT NULL;
+ALTER TABLE categorias ADD COLUMN banner_url TEXT NULL;
+ALTER TABLE categorias ADD COLUMN seo_titulo VARCHAR(70) NULL;
+ALTER TABLE categorias ADD COLUMN seo_descricao VARCHAR(160) NULL;
+
+-- Mantém a ordem atual (por ID) como ponto de partida.
+UPDATE categorias c SET posicao = o.posicao
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY parent_id ORDER BY id) AS posicao FROM categorias
+) o
+WHERE c.id = o.id;
+
+CREATE INDEX idx_categorias_pai_posicao ON categorias (parent_id, posicao) WHERE deleted_at IS NULL;
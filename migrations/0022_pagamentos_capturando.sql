@@ -0,0 +1,6 @@
+-- migrations/0022_pagamentos_capturando.sql
+--
+-- Cartões autorizados passam a ser gravados como 'capturando' antes da captura, e a liberação
+-- das reservas expiradas deixa esses pedidos de fora. Um pagamento que ficou 'autorizado' não teve
+-- a captura concluída nem anulada: pode ter sido cobrado e precisa da mesma conferência.
+UPDATE pagamentos SET situacao = 'capturando', atualizado_em = NOW() WHERE situacao = 'autorizado';
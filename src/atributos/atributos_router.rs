@@ -0,0 +1,490 @@
+// src/atributos/atributos_router.rs
+
+use actix_web::{get, post, put, web, HttpResponse};
+use sqlx::{query, query_as, query_scalar};
+use std::collections::HashSet;
+
+// Importa as structs de atributos
+use super::atributos_structs::{
+    Atributo,
+    AtributoCategoria,
+    AtualizacaoAtributo,
+    NovoAtributo,
+    TipoAtributo,
+    ValoresAtributos,
+};
+// Importa a validação dos atributos e dos valores
+use super::atributos_service::{atributos_da_categoria, codigo_valido, normalizar_valores, validar_definicao, PARAMETROS_RESERVADOS};
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+// Importa o extrator de autorização para as rotas administrativas
+use crate::usuarios::auth_middleware::AdminUser;
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::{executar_com_auditoria, registrar_auditoria};
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
+
+/// Código de erro do PostgreSQL para violação de chave única.
+const VIOLACAO_UNICIDADE: &str = "23505";
+
+fn requisicao_invalida(mensagem: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(GenericResponse::<()>{
+        status: "error".to_string(),
+        message: mensagem.to_string(),
+        body: None,
+    })
+}
+
+/// Rota para listar as definições de todos os atributos, com as opções aceitas.
+#[get("/atributos")]
+pub async fn buscar_atributos(data: web::Data<AppState>) -> HttpResponse {
+    let result = query_as::<_, Atributo>("SELECT id, codigo, nome, tipo, unidade, opcoes FROM atributos ORDER BY nome, id")
+        .fetch_all(&data.db_pool)
+        .await;
+
+    match result {
+        Ok(atributos) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Atributos listados com sucesso!".to_string(),
+            body: Some(atributos),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar atributos: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar atributos.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para cadastrar um atributo. A criação é registrada na auditoria.
+#[post("/atributos")]
+pub async fn cadastrar_atributo(
+    data: web::Data<AppState>,
+    item: web::Json<NovoAtributo>,
+    admin: AdminUser,
+) -> HttpResponse {
+    if !codigo_valido(&item.codigo) {
+        return requisicao_invalida(&format!(
+            "Código inválido: use letras minúsculas, dígitos e '_', começando por letra (reservados: {}).",
+            PARAMETROS_RESERVADOS.join(", ")
+        ));
+    }
+    if item.nome.trim().is_empty() {
+        return requisicao_invalida("O nome do atributo é obrigatório.");
+    }
+    if let Err(mensagem) = validar_definicao(item.tipo, &item.unidade, &item.opcoes) {
+        return requisicao_invalida(&mensagem);
+    }
+
+    let comando = query(
+        r#"
+        INSERT INTO atributos (codigo, nome, tipo, unidade, opcoes)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, NULL::jsonb AS antes, to_jsonb(atributos) AS depois
+        "#
+    )
+    .bind(&item.codigo)
+    .bind(item.nome.trim())
+    .bind(item.tipo.as_str())
+    .bind(&item.unidade)
+    .bind(&item.opcoes);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "atributo", AcaoAuditoria::Criacao, comando).await {
+        Ok(Some(id)) => HttpResponse::Created().json(GenericResponse {
+            status: "success".to_string(),
+            message: format!("Atributo cadastrado com sucesso! ID: {}", id),
+            body: Some(serde_json::json!({ "id": id })),
+        }),
+        Ok(None) => {
+            eprintln!("Erro ao obter id do novo atributo: nenhuma linha retornada");
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao processar resposta do cadastro".to_string(),
+                body: None,
+            })
+        }
+        Err(e) if e.as_database_error().and_then(|d| d.code()).as_deref() == Some(VIOLACAO_UNICIDADE) => {
+            HttpResponse::Conflict().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Já existe um atributo com o código '{}'.", item.codigo),
+                body: None,
+            })
+        }
+        Err(e) => {
+            eprintln!("Erro ao cadastrar atributo: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao cadastrar atributo.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para alterar nome, unidade e opções de um atributo.
+/// Opções que ainda estão em uso por algum produto não podem ser removidas.
+#[put("/atributos/{id}")]
+pub async fn atualizar_atributo(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<AtualizacaoAtributo>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    if item.nome.trim().is_empty() {
+        return requisicao_invalida("O nome do atributo é obrigatório.");
+    }
+
+    let tipo = query_scalar::<_, String>("SELECT tipo FROM atributos WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&data.db_pool)
+        .await;
+    let tipo = match tipo {
+        Ok(Some(tipo)) => match TipoAtributo::da_coluna(&tipo) {
+            Some(tipo) => tipo,
+            None => {
+                eprintln!("Tipo desconhecido '{}' no atributo {}", tipo, id);
+                return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "Erro ao atualizar atributo.".to_string(),
+                    body: None,
+                });
+            }
+        },
+        Ok(None) => {
+            return HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Atributo com ID {} não encontrado.", id),
+                body: None,
+            });
+        }
+        Err(e) => {
+            eprintln!("Erro ao buscar atributo {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao atualizar atributo.".to_string(),
+                body: None,
+            });
+        }
+    };
+    if let Err(mensagem) = validar_definicao(tipo, &item.unidade, &item.opcoes) {
+        return requisicao_invalida(&mensagem);
+    }
+
+    if tipo.usa_opcoes() {
+        let em_uso = query_scalar::<_, String>(
+            "SELECT DISTINCT valor FROM produtos_atributos WHERE atributo_id = $1 AND NOT (valor = ANY($2)) ORDER BY valor"
+        )
+        .bind(id)
+        .bind(&item.opcoes)
+        .fetch_all(&data.db_pool)
+        .await;
+
+        match em_uso {
+            Ok(em_uso) if em_uso.is_empty() => {},
+            Ok(em_uso) => {
+                return HttpResponse::Conflict().json(GenericResponse {
+                    status: "error".to_string(),
+                    message: "Há produtos usando opções que seriam removidas.".to_string(),
+                    body: Some(em_uso),
+                });
+            }
+            Err(e) => {
+                eprintln!("Erro ao verificar opções em uso do atributo {}: {:?}", id, e);
+                return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "Erro ao atualizar atributo.".to_string(),
+                    body: None,
+                });
+            }
+        }
+    }
+
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT a.id, to_jsonb(a) AS antes FROM atributos a WHERE a.id = $4 FOR UPDATE)
+        UPDATE atributos SET nome = $1, unidade = $2, opcoes = $3
+        FROM anterior
+        WHERE atributos.id = anterior.id
+        RETURNING atributos.id, anterior.antes, to_jsonb(atributos) AS depois
+        "#
+    )
+    .bind(item.nome.trim())
+    .bind(&item.unidade)
+    .bind(&item.opcoes)
+    .bind(id);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "atributo", AcaoAuditoria::Atualizacao, comando).await {
+        Ok(Some(_)) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Atributo com ID {} atualizado com sucesso.", id),
+            body: None,
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Atributo com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao atualizar atributo {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao atualizar atributo.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para listar os atributos que valem para uma categoria, incluindo os herdados
+/// das categorias acima (`categoria_id` indica onde cada um foi ligado).
+#[get("/categorias/{id}/atributos")]
+pub async fn buscar_atributos_categoria(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let result: Result<Option<_>, sqlx::Error> = async {
+        let mut conn = data.db_pool.acquire().await?;
+        let existe = query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM categorias WHERE id = $1 AND deleted_at IS NULL)")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+        if !existe {
+            return Ok(None);
+        }
+        Ok(Some(atributos_da_categoria(&mut conn, id).await?))
+    }
+    .await;
+
+    match result {
+        Ok(Some(atributos)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Atributos da categoria listados com sucesso!".to_string(),
+            body: Some(atributos),
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Categoria com ID {} não encontrada.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar atributos da categoria {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar atributos da categoria.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Resultado da troca dos atributos de uma categoria ou produto.
+enum ResultadoDefinicao<E> {
+    Definido,
+    NaoEncontrado,
+    Invalido(E),
+}
+
+/// Rota administrativa para definir os atributos ligados diretamente a uma categoria,
+/// substituindo a lista anterior. Subcategorias passam a herdá-los.
+/// Valores já gravados nos produtos são mantidos. A troca é registrada na auditoria.
+#[put("/categorias/{id}/atributos")]
+pub async fn definir_atributos_categoria(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<Vec<AtributoCategoria>>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let atributo_ids: Vec<i32> = item.iter().map(|a| a.atributo_id).collect();
+    let obrigatorios: Vec<bool> = item.iter().map(|a| a.obrigatorio).collect();
+    if atributo_ids.iter().collect::<HashSet<_>>().len() != atributo_ids.len() {
+        return requisicao_invalida("Cada atributo só pode aparecer uma vez.");
+    }
+
+    let result: Result<ResultadoDefinicao<Vec<i32>>, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let existe = query_scalar::<_, i32>("SELECT id FROM categorias WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        if existe.is_none() {
+            return Ok(ResultadoDefinicao::NaoEncontrado);
+        }
+
+        let inexistentes = query_scalar::<_, i32>(
+            "SELECT u.id FROM UNNEST($1::int[]) AS u(id) WHERE NOT EXISTS (SELECT 1 FROM atributos a WHERE a.id = u.id) ORDER BY u.id"
+        )
+        .bind(&atributo_ids)
+        .fetch_all(&mut *transaction)
+        .await?;
+        if !inexistentes.is_empty() {
+            return Ok(ResultadoDefinicao::Invalido(inexistentes));
+        }
+
+        let consulta_ligacoes = r#"
+            SELECT jsonb_agg(jsonb_build_object('atributo_id', atributo_id, 'obrigatorio', obrigatorio) ORDER BY atributo_id)
+            FROM categorias_atributos WHERE categoria_id = $1
+        "#;
+        let antes = query_scalar::<_, Option<serde_json::Value>>(consulta_ligacoes)
+            .bind(id)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        query("DELETE FROM categorias_atributos WHERE categoria_id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+        query(
+            r#"
+            INSERT INTO categorias_atributos (categoria_id, atributo_id, obrigatorio)
+            SELECT $1, l.atributo_id, l.obrigatorio FROM UNNEST($2::int[], $3::bool[]) AS l(atributo_id, obrigatorio)
+            "#
+        )
+        .bind(id)
+        .bind(&atributo_ids)
+        .bind(&obrigatorios)
+        .execute(&mut *transaction)
+        .await?;
+
+        let depois = query_scalar::<_, Option<serde_json::Value>>(consulta_ligacoes)
+            .bind(id)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        registrar_auditoria(&mut transaction, &admin.0, "categoria_atributos", id, AcaoAuditoria::Atualizacao, antes, depois).await?;
+        transaction.commit().await?;
+        Ok(ResultadoDefinicao::Definido)
+    }
+    .await;
+
+    match result {
+        Ok(ResultadoDefinicao::Definido) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Atributos da categoria {} definidos com sucesso.", id),
+            body: None,
+        }),
+        Ok(ResultadoDefinicao::NaoEncontrado) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Categoria com ID {} não encontrada.", id),
+            body: None,
+        }),
+        Ok(ResultadoDefinicao::Invalido(inexistentes)) => HttpResponse::BadRequest().json(GenericResponse {
+            status: "error".to_string(),
+            message: "Atributos não encontrados.".to_string(),
+            body: Some(inexistentes),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao definir atributos da categoria {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao definir atributos da categoria.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para definir os valores dos atributos de um produto, substituindo os anteriores.
+/// Recebe um objeto `{"codigo": valor}` com os atributos da categoria do produto; os obrigatórios
+/// devem estar presentes. Os problemas de todos os valores são devolvidos juntos.
+/// A troca incrementa a versão do produto e é registrada na auditoria.
+#[put("/produtos/{id}/atributos")]
+pub async fn definir_atributos_produto(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<ValoresAtributos>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+
+    let result = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let categoria_id = query_scalar::<_, i32>("SELECT categoria_id FROM produtos WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        let categoria_id = match categoria_id {
+            Some(categoria_id) => categoria_id,
+            None => return Ok(ResultadoDefinicao::NaoEncontrado),
+        };
+
+        let atributos = atributos_da_categoria(&mut transaction, categoria_id).await?;
+        let valores = match normalizar_valores(&atributos, &item) {
+            Ok(valores) => valores,
+            Err(erros) => return Ok(ResultadoDefinicao::Invalido(erros)),
+        };
+
+        let consulta_atributos = "SELECT atributos FROM produtos_atributos_json WHERE produto_id = $1";
+        let antes = query_scalar::<_, serde_json::Value>(consulta_atributos)
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        query("DELETE FROM produtos_atributos WHERE produto_id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+        query(
+            r#"
+            INSERT INTO produtos_atributos (produto_id, atributo_id, valor, valor_numero)
+            SELECT $1, v.atributo_id, v.valor, NULLIF(v.numero, '')::numeric
+            FROM UNNEST($2::int[], $3::text[], $4::text[]) AS v(atributo_id, valor, numero)
+            "#
+        )
+        .bind(id)
+        .bind(valores.iter().map(|v| v.atributo_id).collect::<Vec<i32>>())
+        .bind(valores.iter().map(|v| v.valor.clone()).collect::<Vec<String>>())
+        .bind(valores.iter().map(|v| v.valor_numero.as_ref().map(|n| n.to_string()).unwrap_or_default()).collect::<Vec<String>>())
+        .execute(&mut *transaction)
+        .await?;
+
+        query("UPDATE produtos SET versao = versao + 1 WHERE id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        let depois = query_scalar::<_, serde_json::Value>(consulta_atributos)
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        registrar_auditoria(&mut transaction, &admin.0, "produto_atributos", id, AcaoAuditoria::Atualizacao, antes, depois).await?;
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>(ResultadoDefinicao::Definido)
+    }
+    .await;
+
+    match result {
+        Ok(ResultadoDefinicao::Definido) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Atributos do produto {} definidos com sucesso.", id),
+            body: None,
+        }),
+        Ok(ResultadoDefinicao::NaoEncontrado) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Produto com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Ok(ResultadoDefinicao::Invalido(erros)) => HttpResponse::BadRequest().json(GenericResponse {
+            status: "error".to_string(),
+            message: "Há atributos inválidos.".to_string(),
+            body: Some(erros),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao definir atributos do produto {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao definir atributos do produto.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
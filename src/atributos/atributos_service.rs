@@ -0,0 +1,423 @@
+// src/atributos/atributos_service.rs
+
+use bigdecimal::BigDecimal;
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::{query_as, PgConnection, Pool, Postgres};
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+use super::atributos_structs::{
+    Atributo, AtributoDaCategoria, ErroAtributo, Faceta, FiltroAtributo, LinhaFaceta, LinhaFacetaMarca, TipoAtributo,
+    ValorFaceta, ValoresAtributos,
+};
+
+/// Parâmetros da listagem de produtos que não são filtros de atributo.
+/// Nenhum atributo pode usar esses códigos; "marca" fica reservado porque a marca é uma
+/// entidade própria (`marcas`), filtrada e contada por `produtos.marca_id`.
+pub const PARAMETROS_RESERVADOS: &[&str] = &["ordenar", "categoria_id", "marca_id", "marca"];
+
+/// Tamanho máximo de um valor de atributo de texto.
+const TAMANHO_MAXIMO_VALOR: usize = 100;
+
+/// Valida o código de um atributo: letras minúsculas, dígitos e "_", começando por letra.
+pub fn codigo_valido(codigo: &str) -> bool {
+    codigo.len() <= 50
+        && codigo.starts_with(|c: char| c.is_ascii_lowercase())
+        && codigo.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !PARAMETROS_RESERVADOS.contains(&codigo)
+}
+
+/// Confere se unidade e opções combinam com o tipo do atributo.
+pub fn validar_definicao(tipo: TipoAtributo, unidade: &Option<String>, opcoes: &[String]) -> Result<(), String> {
+    if tipo.usa_opcoes() {
+        if opcoes.is_empty() || opcoes.iter().any(|opcao| opcao.trim().is_empty()) {
+            return Err("Informe as opções do atributo, sem valores vazios.".to_string());
+        }
+        if opcoes.iter().collect::<HashSet<_>>().len() != opcoes.len() {
+            return Err("As opções do atributo não podem se repetir.".to_string());
+        }
+    } else if !opcoes.is_empty() {
+        return Err("Só atributos 'opcao' e 'multiplas_opcoes' têm opções.".to_string());
+    }
+    if unidade.is_some() && tipo != TipoAtributo::Numero {
+        return Err("Só atributos numéricos têm unidade.".to_string());
+    }
+    Ok(())
+}
+
+/// Atributos que valem para a categoria: os ligados a ela e às categorias acima.
+/// Se o mesmo atributo estiver ligado em mais de um nível, vale a ligação mais próxima.
+pub async fn atributos_da_categoria(
+    conn: &mut PgConnection,
+    categoria_id: i32,
+) -> Result<Vec<AtributoDaCategoria>, sqlx::Error> {
+    query_as::<_, AtributoDaCategoria>(
+        r#"
+        WITH RECURSIVE acima AS (
+            SELECT id, parent_id, 0 AS nivel FROM categorias WHERE id = $1
+            UNION ALL
+            SELECT c.id, c.parent_id, a.nivel + 1 FROM categorias c JOIN acima a ON c.id = a.parent_id
+        )
+        SELECT * FROM (
+            SELECT DISTINCT ON (at.id)
+                   at.id, at.codigo, at.nome, at.tipo, at.unidade, at.opcoes, ca.obrigatorio, ca.categoria_id
+            FROM acima
+            JOIN categorias_atributos ca ON ca.categoria_id = acima.id
+            JOIN atributos at ON at.id = ca.atributo_id
+            ORDER BY at.id, acima.nivel
+        ) atributos
+        ORDER BY nome, id
+        "#
+    )
+    .bind(categoria_id)
+    .fetch_all(conn)
+    .await
+}
+
+/// Valor de atributo pronto para gravar em `produtos_atributos`.
+pub struct ValorNormalizado {
+    pub atributo_id: i32,
+    pub valor: String,
+    pub valor_numero: Option<BigDecimal>,
+}
+
+/// Confere os valores enviados contra os atributos da categoria do produto e os converte
+/// para o formato gravado. Retorna todos os problemas encontrados de uma vez.
+pub fn normalizar_valores(
+    atributos: &[AtributoDaCategoria],
+    valores: &ValoresAtributos,
+) -> Result<Vec<ValorNormalizado>, Vec<ErroAtributo>> {
+    let mut normalizados = Vec::new();
+    let mut erros = Vec::new();
+    let erro = |atributo: &str, motivo: &str| ErroAtributo { atributo: atributo.to_string(), motivo: motivo.to_string() };
+
+    for (codigo, valor) in valores {
+        let atributo = match atributos.iter().find(|a| &a.codigo == codigo) {
+            Some(atributo) => atributo,
+            None => {
+                erros.push(erro(codigo, "Atributo inexistente ou que não se aplica à categoria do produto."));
+                continue;
+            }
+        };
+        if valor.is_null() {
+            continue;
+        }
+        let tipo = match TipoAtributo::da_coluna(&atributo.tipo) {
+            Some(tipo) => tipo,
+            None => {
+                erros.push(erro(codigo, "Tipo de atributo desconhecido."));
+                continue;
+            }
+        };
+
+        let convertidos: Result<Vec<(String, Option<BigDecimal>)>, &str> = match (tipo, valor) {
+            (TipoAtributo::Booleano, serde_json::Value::Bool(b)) => Ok(vec![(b.to_string(), None)]),
+            (TipoAtributo::Booleano, _) => Err("Informe true ou false."),
+            (TipoAtributo::Numero, serde_json::Value::Number(n)) => match BigDecimal::from_str(&n.to_string()) {
+                Ok(numero) => {
+                    let numero = numero.normalized();
+                    Ok(vec![(numero.to_string(), Some(numero))])
+                }
+                Err(_) => Err("Número inválido."),
+            },
+            (TipoAtributo::Numero, _) => Err("Informe um número."),
+            (TipoAtributo::Texto, serde_json::Value::String(texto)) => {
+                let texto = texto.trim();
+                if texto.is_empty() || texto.chars().count() > TAMANHO_MAXIMO_VALOR {
+                    Err("O texto deve ter de 1 a 100 caracteres.")
+                } else {
+                    Ok(vec![(texto.to_string(), None)])
+                }
+            }
+            (TipoAtributo::Texto, _) => Err("Informe um texto."),
+            (TipoAtributo::Opcao, serde_json::Value::String(opcao)) => {
+                if atributo.opcoes.contains(opcao) {
+                    Ok(vec![(opcao.clone(), None)])
+                } else {
+                    Err("Valor fora das opções do atributo.")
+                }
+            }
+            (TipoAtributo::Opcao, _) => Err("Informe uma das opções do atributo."),
+            (TipoAtributo::MultiplasOpcoes, serde_json::Value::Array(lista)) => {
+                let opcoes: Option<HashSet<&String>> = lista
+                    .iter()
+                    .map(|item| item.as_str().and_then(|opcao| atributo.opcoes.iter().find(|o| *o == opcao)))
+                    .collect();
+                match opcoes {
+                    Some(opcoes) if !opcoes.is_empty() => Ok(opcoes.into_iter().map(|o| (o.clone(), None)).collect()),
+                    Some(_) => Err("Informe ao menos uma opção ou null para remover o atributo."),
+                    None => Err("Valor fora das opções do atributo."),
+                }
+            }
+            (TipoAtributo::MultiplasOpcoes, _) => Err("Informe uma lista de opções do atributo."),
+        };
+
+        match convertidos {
+            Ok(convertidos) => normalizados.extend(convertidos.into_iter().map(|(valor, valor_numero)| ValorNormalizado {
+                atributo_id: atributo.id,
+                valor,
+                valor_numero,
+            })),
+            Err(motivo) => erros.push(erro(codigo, motivo)),
+        }
+    }
+
+    for atributo in atributos.iter().filter(|a| a.obrigatorio) {
+        if !normalizados.iter().any(|v| v.atributo_id == atributo.id) {
+            erros.push(erro(&atributo.codigo, "Atributo obrigatório para a categoria do produto."));
+        }
+    }
+
+    if erros.is_empty() {
+        Ok(normalizados)
+    } else {
+        erros.sort_by(|a, b| a.atributo.cmp(&b.atributo));
+        Err(erros)
+    }
+}
+
+/// Motivos para os filtros da listagem não serem aceitos.
+pub enum FalhaFiltro {
+    Invalido(String),
+    Banco(sqlx::Error),
+}
+
+impl From<sqlx::Error> for FalhaFiltro {
+    fn from(e: sqlx::Error) -> Self {
+        FalhaFiltro::Banco(e)
+    }
+}
+
+/// Interpreta os filtros por atributo da query string da listagem (`?vegano=true&tipo_pele=oleosa`).
+/// Repetir o parâmetro aceita mais de um valor; atributos numéricos aceitam uma faixa `min..max`
+/// (qualquer lado pode ficar vazio) ou um valor exato. Parâmetros que não são códigos de
+/// atributo (ex.: `utm_source` de campanhas) são ignorados; valores inválidos de um atributo não.
+pub async fn interpretar_filtros(
+    pool: &Pool<Postgres>,
+    parametros: &[(String, String)],
+) -> Result<Vec<FiltroAtributo>, FalhaFiltro> {
+    let mut agrupados: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (chave, valor) in parametros {
+        if !PARAMETROS_RESERVADOS.contains(&chave.as_str()) {
+            agrupados.entry(chave.as_str()).or_default().push(valor.as_str());
+        }
+    }
+    if agrupados.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let codigos: Vec<&str> = agrupados.keys().copied().collect();
+    let atributos = query_as::<_, Atributo>(
+        "SELECT id, codigo, nome, tipo, unidade, opcoes FROM atributos WHERE codigo = ANY($1)"
+    )
+    .bind(&codigos)
+    .fetch_all(pool)
+    .await?;
+
+    let mut filtros = Vec::new();
+    for (codigo, valores) in agrupados {
+        let atributo = match atributos.iter().find(|a| a.codigo == codigo) {
+            Some(atributo) => atributo,
+            None => continue,
+        };
+        let filtro = match TipoAtributo::da_coluna(&atributo.tipo) {
+            Some(TipoAtributo::Numero) => {
+                let faixa = match valores.as_slice() {
+                    [faixa] => interpretar_faixa(faixa),
+                    _ => None,
+                };
+                match faixa {
+                    Some((minimo, maximo)) => FiltroAtributo { atributo_id: atributo.id, valores: Vec::new(), minimo, maximo },
+                    None => return Err(FalhaFiltro::Invalido(format!("Filtro '{}' inválido: use um número ou uma faixa min..max.", codigo))),
+                }
+            }
+            Some(TipoAtributo::Booleano) if valores.iter().any(|v| *v != "true" && *v != "false") => {
+                return Err(FalhaFiltro::Invalido(format!("Filtro '{}' inválido: use true ou false.", codigo)));
+            }
+            Some(tipo) if tipo.usa_opcoes() && valores.iter().any(|v| !atributo.opcoes.iter().any(|o| o == v)) => {
+                return Err(FalhaFiltro::Invalido(format!("Filtro '{}' inválido: opções aceitas: {}.", codigo, atributo.opcoes.join(", "))));
+            }
+            _ => FiltroAtributo {
+                atributo_id: atributo.id,
+                valores: valores.iter().map(|v| v.to_string()).collect(),
+                minimo: None,
+                maximo: None,
+            },
+        };
+        filtros.push(filtro);
+    }
+    Ok(filtros)
+}
+
+/// Interpreta `min..max`, `min..`, `..max` ou um valor exato.
+fn interpretar_faixa(faixa: &str) -> Option<(Option<BigDecimal>, Option<BigDecimal>)> {
+    let numero = |texto: &str| -> Option<Option<BigDecimal>> {
+        if texto.is_empty() {
+            Some(None)
+        } else {
+            BigDecimal::from_str(texto).ok().map(Some)
+        }
+    };
+    match faixa.split_once("..") {
+        Some((minimo, maximo)) => Some((numero(minimo)?, numero(maximo)?)),
+        None => {
+            let exato = BigDecimal::from_str(faixa).ok()?;
+            Some((Some(exato.clone()), Some(exato)))
+        }
+    }
+}
+
+/// Valor de um parâmetro posicional ($n) das consultas filtradas.
+#[derive(Clone)]
+pub enum ParametroSql {
+    Inteiro(i32),
+    Textos(Vec<String>),
+    Decimal(Option<BigDecimal>),
+}
+
+/// Condições da listagem de produtos (sobre `produtos p`) e seus parâmetros.
+/// `condicoes_faceta` repete as condições para a consulta de contagens, onde o filtro de
+/// cada atributo não restringe as linhas do próprio atributo (`pa`); `condicoes_marca`
+/// são as da contagem por marca, sem o filtro de marca.
+#[derive(Default)]
+pub struct CondicoesProdutos {
+    pub condicoes: Vec<String>,
+    pub condicoes_faceta: Vec<String>,
+    pub condicoes_marca: Vec<String>,
+    pub parametros: Vec<ParametroSql>,
+}
+
+impl CondicoesProdutos {
+    /// Acrescenta um parâmetro e devolve o seu marcador ($n).
+    fn parametro(&mut self, valor: ParametroSql) -> String {
+        self.parametros.push(valor);
+        format!("${}", self.parametros.len())
+    }
+
+    /// Restringe aos produtos da categoria e das suas subcategorias.
+    pub fn categoria(&mut self, categoria_id: i32) {
+        let id = self.parametro(ParametroSql::Inteiro(categoria_id));
+        let condicao = format!(
+            "p.categoria_id IN (WITH RECURSIVE abaixo AS (SELECT id FROM categorias WHERE id = {} \
+             UNION ALL SELECT c2.id FROM categorias c2 JOIN abaixo ON c2.parent_id = abaixo.id) SELECT id FROM abaixo)",
+            id
+        );
+        self.condicoes_faceta.push(condicao.clone());
+        self.condicoes_marca.push(condicao.clone());
+        self.condicoes.push(condicao);
+    }
+
+    /// Restringe aos produtos da marca.
+    pub fn marca(&mut self, marca_id: i32) {
+        let id = self.parametro(ParametroSql::Inteiro(marca_id));
+        let condicao = format!("p.marca_id = {}", id);
+        self.condicoes_faceta.push(condicao.clone());
+        self.condicoes.push(condicao);
+    }
+
+    /// Restringe aos produtos que atendem ao filtro de atributo.
+    pub fn atributo(&mut self, filtro: &FiltroAtributo) {
+        let atributo_id = self.parametro(ParametroSql::Inteiro(filtro.atributo_id));
+        let condicao = if filtro.valores.is_empty() {
+            let minimo = self.parametro(ParametroSql::Decimal(filtro.minimo.clone()));
+            let maximo = self.parametro(ParametroSql::Decimal(filtro.maximo.clone()));
+            format!(
+                "EXISTS (SELECT 1 FROM produtos_atributos fa WHERE fa.produto_id = p.id AND fa.atributo_id = {} \
+                 AND ({min}::numeric IS NULL OR fa.valor_numero >= {min}) AND ({max}::numeric IS NULL OR fa.valor_numero <= {max}))",
+                atributo_id, min = minimo, max = maximo
+            )
+        } else {
+            let valores = self.parametro(ParametroSql::Textos(filtro.valores.clone()));
+            format!(
+                "EXISTS (SELECT 1 FROM produtos_atributos fa WHERE fa.produto_id = p.id AND fa.atributo_id = {} AND fa.valor = ANY({}))",
+                atributo_id, valores
+            )
+        };
+        self.condicoes_faceta.push(format!("(pa.atributo_id = {} OR {})", atributo_id, condicao));
+        self.condicoes_marca.push(condicao.clone());
+        self.condicoes.push(condicao);
+    }
+
+    /// Condições prontas para acrescentar a um WHERE já iniciado.
+    pub fn sql(&self) -> String {
+        self.condicoes.iter().map(|c| format!(" AND {}", c)).collect()
+    }
+
+    /// Liga os parâmetros, na ordem dos marcadores, a uma consulta montada com estas condições.
+    pub fn ligar<'q, O>(
+        &self,
+        mut consulta: QueryAs<'q, Postgres, O, PgArguments>,
+    ) -> QueryAs<'q, Postgres, O, PgArguments> {
+        for parametro in &self.parametros {
+            consulta = match parametro.clone() {
+                ParametroSql::Inteiro(valor) => consulta.bind(valor),
+                ParametroSql::Textos(valores) => consulta.bind(valores),
+                ParametroSql::Decimal(valor) => consulta.bind(valor),
+            };
+        }
+        consulta
+    }
+}
+
+/// Conta, para cada valor de atributo, os produtos da listagem que o possuem.
+/// A primeira faceta, `marca_id`, conta os produtos por marca ativa; o valor é o ID da marca
+/// (o parâmetro do filtro) e o `rotulo`, o nome.
+pub async fn contar_facetas(pool: &Pool<Postgres>, condicoes: &CondicoesProdutos) -> Result<Vec<Faceta>, sqlx::Error> {
+    let sql_marcas = format!(
+        r#"
+        SELECT m.id AS marca_id, m.nome, COUNT(DISTINCT p.id) AS quantidade
+        FROM produtos p
+        JOIN categorias c ON c.id = p.categoria_id
+        JOIN marcas m ON m.id = p.marca_id AND m.deleted_at IS NULL
+        WHERE p.deleted_at IS NULL AND c.deleted_at IS NULL{}
+        GROUP BY m.id, m.nome
+        ORDER BY COUNT(DISTINCT p.id) DESC, m.nome
+        "#,
+        condicoes.condicoes_marca.iter().map(|c| format!(" AND {}", c)).collect::<String>()
+    );
+    let marcas = condicoes.ligar(query_as::<_, LinhaFacetaMarca>(&sql_marcas)).fetch_all(pool).await?;
+
+    let sql = format!(
+        r#"
+        SELECT a.codigo, a.nome, a.tipo, a.unidade, pa.valor, COUNT(DISTINCT p.id) AS quantidade
+        FROM produtos p
+        JOIN categorias c ON c.id = p.categoria_id
+        JOIN produtos_atributos pa ON pa.produto_id = p.id
+        JOIN atributos a ON a.id = pa.atributo_id
+        WHERE p.deleted_at IS NULL AND c.deleted_at IS NULL{}
+        GROUP BY a.id, a.codigo, a.nome, a.tipo, a.unidade, pa.valor
+        ORDER BY a.nome, a.id, MAX(pa.valor_numero), COUNT(DISTINCT p.id) DESC, pa.valor
+        "#,
+        condicoes.condicoes_faceta.iter().map(|c| format!(" AND {}", c)).collect::<String>()
+    );
+    let linhas = condicoes.ligar(query_as::<_, LinhaFaceta>(&sql)).fetch_all(pool).await?;
+
+    let mut facetas: Vec<Faceta> = Vec::new();
+    if !marcas.is_empty() {
+        facetas.push(Faceta {
+            atributo: "marca_id".to_string(),
+            nome: "Marca".to_string(),
+            tipo: "opcao".to_string(),
+            unidade: None,
+            valores: marcas
+                .into_iter()
+                .map(|m| ValorFaceta { valor: m.marca_id.to_string(), rotulo: Some(m.nome), quantidade: m.quantidade })
+                .collect(),
+        });
+    }
+    for linha in linhas {
+        let valor = ValorFaceta { valor: linha.valor, rotulo: None, quantidade: linha.quantidade };
+        match facetas.last_mut() {
+            Some(faceta) if faceta.atributo == linha.codigo => faceta.valores.push(valor),
+            _ => facetas.push(Faceta {
+                atributo: linha.codigo,
+                nome: linha.nome,
+                tipo: linha.tipo,
+                unidade: linha.unidade,
+                valores: vec![valor],
+            }),
+        }
+    }
+    Ok(facetas)
+}
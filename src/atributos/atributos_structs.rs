@@ -0,0 +1,161 @@
+// src/atributos/atributos_structs.rs
+
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::HashMap;
+
+/// Tipos de atributo. Persistidos como texto na coluna `atributos.tipo`.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TipoAtributo {
+    Texto,           // Texto livre, ex.: linha do produto
+    Opcao,           // Um valor dentre `opcoes`
+    MultiplasOpcoes, // Um ou mais valores dentre `opcoes`, ex.: tipos de pele
+    Booleano,        // Sim ou não, ex.: vegano
+    Numero,          // Valor numérico, filtrável por faixa, ex.: volume em ml
+}
+
+impl TipoAtributo {
+    /// Valor gravado na coluna `atributos.tipo`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TipoAtributo::Texto => "texto",
+            TipoAtributo::Opcao => "opcao",
+            TipoAtributo::MultiplasOpcoes => "multiplas_opcoes",
+            TipoAtributo::Booleano => "booleano",
+            TipoAtributo::Numero => "numero",
+        }
+    }
+
+    /// Converte o valor da coluna `atributos.tipo`.
+    pub fn da_coluna(tipo: &str) -> Option<TipoAtributo> {
+        match tipo {
+            "texto" => Some(TipoAtributo::Texto),
+            "opcao" => Some(TipoAtributo::Opcao),
+            "multiplas_opcoes" => Some(TipoAtributo::MultiplasOpcoes),
+            "booleano" => Some(TipoAtributo::Booleano),
+            "numero" => Some(TipoAtributo::Numero),
+            _ => None,
+        }
+    }
+
+    /// Indica se os valores precisam estar na lista de opções do atributo.
+    pub fn usa_opcoes(&self) -> bool {
+        matches!(self, TipoAtributo::Opcao | TipoAtributo::MultiplasOpcoes)
+    }
+}
+
+/// Estrutura que representa a definição de um atributo no banco de dados.
+#[derive(Serialize, FromRow, Clone)]
+pub struct Atributo {
+    pub id: i32,
+    pub codigo: String,
+    pub nome: String,
+    pub tipo: String,
+    pub unidade: Option<String>,
+    pub opcoes: Vec<String>,
+}
+
+/// Estrutura para receber um novo atributo na requisição POST.
+#[derive(Deserialize)]
+pub struct NovoAtributo {
+    pub codigo: String, // Letras minúsculas, dígitos e "_", ex.: "tipo_pele"
+    pub nome: String,
+    pub tipo: TipoAtributo,
+    pub unidade: Option<String>,
+    #[serde(default)]
+    pub opcoes: Vec<String>, // Obrigatório para `opcao` e `multiplas_opcoes`
+}
+
+/// Estrutura para receber a atualização de um atributo na requisição PUT.
+/// Código e tipo não mudam, pois já estão gravados nos produtos e nos filtros dos clientes.
+#[derive(Deserialize)]
+pub struct AtualizacaoAtributo {
+    pub nome: String,
+    pub unidade: Option<String>,
+    #[serde(default)]
+    pub opcoes: Vec<String>,
+}
+
+/// Atributo ligado a uma categoria, na requisição PUT `/categorias/{id}/atributos`.
+#[derive(Deserialize)]
+pub struct AtributoCategoria {
+    pub atributo_id: i32,
+    #[serde(default)]
+    pub obrigatorio: bool,
+}
+
+/// Atributo que vale para uma categoria, ligado a ela ou a uma categoria acima.
+#[derive(Serialize, FromRow)]
+pub struct AtributoDaCategoria {
+    pub id: i32,
+    pub codigo: String,
+    pub nome: String,
+    pub tipo: String,
+    pub unidade: Option<String>,
+    pub opcoes: Vec<String>,
+    pub obrigatorio: bool,
+    pub categoria_id: i32, // Categoria em que o atributo foi ligado
+}
+
+/// Valores dos atributos de um produto, por código: texto, lista de textos, booleano ou número,
+/// conforme o tipo. `null` remove o atributo.
+pub type ValoresAtributos = HashMap<String, serde_json::Value>;
+
+/// Problema encontrado em um valor de atributo.
+/// Todos os valores são conferidos, e os problemas são devolvidos juntos.
+#[derive(Serialize)]
+pub struct ErroAtributo {
+    pub atributo: String,
+    pub motivo: String,
+}
+
+/// Filtro por atributo da listagem de produtos, já validado.
+/// Valores de um mesmo atributo são alternativas (OU); atributos diferentes se somam (E).
+pub struct FiltroAtributo {
+    pub atributo_id: i32,
+    pub valores: Vec<String>,           // Atributos não numéricos
+    pub minimo: Option<BigDecimal>,     // Atributos numéricos (inclusive)
+    pub maximo: Option<BigDecimal>,
+}
+
+/// Quantidade de produtos da listagem com um valor de atributo.
+#[derive(Serialize)]
+pub struct ValorFaceta {
+    pub valor: String,
+    #[serde(skip_serializing_if = "Option::is_none")] // Só na faceta de marca, em que o valor é o ID
+    pub rotulo: Option<String>,
+    pub quantidade: i64,
+}
+
+/// Contagens de um atributo (ou da marca) para a barra de filtros da loja.
+/// Cada contagem considera os demais filtros aplicados, mas não o do próprio atributo,
+/// para que as alternativas continuem visíveis depois de escolher uma.
+#[derive(Serialize)]
+pub struct Faceta {
+    pub atributo: String, // Código, usado como parâmetro do filtro
+    pub nome: String,
+    pub tipo: String,
+    pub unidade: Option<String>,
+    pub valores: Vec<ValorFaceta>,
+}
+
+/// Linha da contagem de produtos por marca.
+#[derive(FromRow)]
+pub struct LinhaFacetaMarca {
+    pub marca_id: i32,
+    pub nome: String,
+    pub quantidade: i64,
+}
+
+/// Linha da consulta de contagens, agrupada depois em `Faceta`.
+#[derive(FromRow)]
+pub struct LinhaFaceta {
+    pub codigo: String,
+    pub nome: String,
+    pub tipo: String,
+    pub unidade: Option<String>,
+    pub valor: String,
+    pub quantidade: i64,
+}
@@ -0,0 +1,8 @@
+// src/atributos/mod.rs
+
+// Declara o submódulo que contém as definições das structs de atributos e filtros
+pub mod atributos_structs;
+// Declara o submódulo com a validação dos valores e a montagem dos filtros e contagens da listagem
+pub mod atributos_service;
+// Declara o submódulo que contém as funções de rota de atributos
+pub mod atributos_router;
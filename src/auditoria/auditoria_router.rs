@@ -0,0 +1,70 @@
+// src/auditoria/auditoria_router.rs
+
+use actix_web::{get, web, HttpResponse};
+use sqlx::query_as;
+
+// Importa as structs de auditoria
+use super::auditoria_structs::{FiltroAuditoria, RegistroAuditoria};
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+// Importa o extrator de autorização para as rotas administrativas
+use crate::usuarios::auth_middleware::AdminUser;
+
+/// Quantidade de registros retornada quando `limite` não é informado.
+const LIMITE_PADRAO: i64 = 100;
+/// Quantidade máxima de registros retornada por consulta.
+const LIMITE_MAXIMO: i64 = 500;
+
+/// Rota administrativa para consultar a trilha de auditoria.
+/// Aceita filtros opcionais por entidade, ID da entidade, autor, ação e período,
+/// retornando os registros mais recentes primeiro.
+#[get("/auditoria")]
+pub async fn buscar_auditoria(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroAuditoria>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let limite = filtro.limite.unwrap_or(LIMITE_PADRAO).clamp(1, LIMITE_MAXIMO);
+
+    let registros_result = query_as::<_, RegistroAuditoria>(
+        r#"
+        SELECT id, usuario_id, usuario_email, entidade, entidade_id, acao, antes, depois, criado_em
+        FROM auditoria
+        WHERE ($1::text IS NULL OR entidade = $1)
+          AND ($2::int IS NULL OR entidade_id = $2)
+          AND ($3::int IS NULL OR usuario_id = $3)
+          AND ($4::text IS NULL OR acao = $4)
+          AND ($5::timestamptz IS NULL OR criado_em >= $5)
+          AND ($6::timestamptz IS NULL OR criado_em <= $6)
+        ORDER BY criado_em DESC, id DESC
+        LIMIT $7
+        "#
+    )
+    .bind(&filtro.entidade)
+    .bind(filtro.entidade_id)
+    .bind(filtro.usuario_id)
+    .bind(&filtro.acao)
+    .bind(filtro.desde)
+    .bind(filtro.ate)
+    .bind(limite)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match registros_result {
+        Ok(registros) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Registros de auditoria listados com sucesso!".to_string(),
+            body: Some(registros),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar registros de auditoria: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar registros de auditoria".to_string(),
+                body: None,
+            })
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// src/auditoria/auditoria_service.rs
+
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{query, PgConnection, Pool, Postgres, Row};
+
+use super::auditoria_structs::AcaoAuditoria;
+// Importa o usuário autenticado, que é o autor da alteração
+use crate::usuarios::auth_middleware::AuthenticatedUser;
+
+/// Grava um registro de auditoria usando a conexão (ou transação) da própria alteração.
+pub async fn registrar_auditoria(
+    conn: &mut PgConnection,
+    ator: &AuthenticatedUser,
+    entidade: &str,
+    entidade_id: i32,
+    acao: AcaoAuditoria,
+    antes: Option<serde_json::Value>,
+    depois: Option<serde_json::Value>,
+) -> Result<(), sqlx::Error> {
+    query(
+        r#"
+        INSERT INTO auditoria (usuario_id, usuario_email, entidade, entidade_id, acao, antes, depois)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#
+    )
+    .bind(ator.user_id)
+    .bind(&ator.user_email)
+    .bind(entidade)
+    .bind(entidade_id)
+    .bind(acao.as_str())
+    .bind(antes)
+    .bind(depois)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Executa um comando de alteração e registra a auditoria na mesma transação.
+///
+/// O comando deve retornar no máximo uma linha com as colunas `id`, `antes` e `depois`
+/// (snapshots JSONB; `antes` é NULL em criações). Retorna o ID da linha alterada,
+/// ou `None` quando o comando não afetou nenhuma linha.
+pub async fn executar_com_auditoria(
+    pool: &Pool<Postgres>,
+    ator: &AuthenticatedUser,
+    entidade: &str,
+    acao: AcaoAuditoria,
+    comando: Query<'_, Postgres, PgArguments>,
+) -> Result<Option<i32>, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let row = match comando.fetch_optional(&mut *transaction).await? {
+        Some(row) => row,
+        None => return Ok(None), // A transação é desfeita ao ser descartada
+    };
+
+    let id: i32 = row.try_get("id")?;
+    let antes: Option<serde_json::Value> = row.try_get("antes")?;
+    let depois: Option<serde_json::Value> = row.try_get("depois")?;
+
+    registrar_auditoria(&mut transaction, ator, entidade, id, acao, antes, depois).await?;
+    transaction.commit().await?;
+
+    Ok(Some(id))
+}
@@ -0,0 +1,56 @@
+// src/auditoria/auditoria_structs.rs
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+/// Ações administrativas registradas na trilha de auditoria.
+#[derive(Debug, Clone, Copy)]
+pub enum AcaoAuditoria {
+    Criacao,
+    Atualizacao,
+    Exclusao,
+    Restauracao,
+    AlteracaoStatus,
+}
+
+impl AcaoAuditoria {
+    /// Valor gravado na coluna `acao` da tabela `auditoria`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AcaoAuditoria::Criacao => "criacao",
+            AcaoAuditoria::Atualizacao => "atualizacao",
+            AcaoAuditoria::Exclusao => "exclusao",
+            AcaoAuditoria::Restauracao => "restauracao",
+            AcaoAuditoria::AlteracaoStatus => "alteracao_status",
+        }
+    }
+}
+
+/// Estrutura que representa um registro de auditoria no banco de dados.
+/// `antes` e `depois` guardam o snapshot completo da linha alterada em JSON.
+#[derive(Serialize, FromRow)]
+pub struct RegistroAuditoria {
+    pub id: i32,
+    pub usuario_id: i32,
+    pub usuario_email: String,
+    pub entidade: String,
+    pub entidade_id: i32,
+    pub acao: String,
+    pub antes: Option<serde_json::Value>,
+    pub depois: Option<serde_json::Value>,
+    pub criado_em: DateTime<Utc>,
+}
+
+/// Filtros aceitos na query string de `GET /auditoria`.
+/// Todos são opcionais; `limite` tem valor padrão e teto definidos na rota.
+#[derive(Deserialize)]
+pub struct FiltroAuditoria {
+    pub entidade: Option<String>,
+    pub entidade_id: Option<i32>,
+    pub usuario_id: Option<i32>,
+    pub acao: Option<String>,
+    pub desde: Option<DateTime<Utc>>,
+    pub ate: Option<DateTime<Utc>>,
+    pub limite: Option<i64>,
+}
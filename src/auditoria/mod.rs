@@ -0,0 +1,8 @@
+// src/auditoria/mod.rs
+
+// Declara o submódulo que contém as definições das structs de auditoria
+pub mod auditoria_structs;
+// Declara o submódulo com as funções de gravação dos registros de auditoria
+pub mod auditoria_service;
+// Declara o submódulo que contém as funções de rota relacionadas à auditoria
+pub mod auditoria_router;
@@ -0,0 +1,288 @@
+// src/avaliacoes/avaliacoes_router.rs
+
+use actix_web::{get, post, put, web, HttpResponse};
+use sqlx::{query, query_as, query_scalar};
+
+// Importa as structs de avaliações
+use super::avaliacoes_structs::{
+    AvaliacaoModeracao,
+    AvaliacaoPublica,
+    FiltroAvaliacoes,
+    ModeracaoAvaliacao,
+    NovaAvaliacao,
+    SituacaoAvaliacao,
+};
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+// Importa os extratores de autenticação e autorização
+use crate::usuarios::auth_middleware::{AuthenticatedUser, AdminUser};
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::executar_com_auditoria;
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
+
+/// Tamanho máximo do texto de uma avaliação (em caracteres).
+const TAMANHO_MAXIMO_TEXTO: usize = 2000;
+/// Quantidade de avaliações retornada quando `limite` não é informado.
+const LIMITE_PADRAO: i64 = 100;
+/// Quantidade máxima de avaliações retornada por consulta.
+const LIMITE_MAXIMO: i64 = 500;
+
+/// Rota para o cliente avaliar um produto com nota de 1 a 5 estrelas e um texto.
+/// Só é aceita de quem tem um pedido entregue contendo o produto.
+/// Cada cliente tem uma avaliação por produto: enviar de novo substitui a anterior,
+/// que volta para a fila de moderação.
+#[post("/produtos/{id}/avaliacoes")]
+pub async fn avaliar_produto(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+    item: web::Json<NovaAvaliacao>,
+) -> HttpResponse {
+    let produto_id = path.into_inner();
+    let texto = item.texto.trim();
+
+    if !(1..=5).contains(&item.nota) {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "A nota deve ser de 1 a 5 estrelas.".to_string(),
+            body: None,
+        });
+    }
+    if texto.is_empty() || texto.chars().count() > TAMANHO_MAXIMO_TEXTO {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("O texto da avaliação deve ter de 1 a {} caracteres.", TAMANHO_MAXIMO_TEXTO),
+            body: None,
+        });
+    }
+
+    let produto_existe = query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM produtos WHERE id = $1 AND deleted_at IS NULL)"
+    )
+    .bind(produto_id)
+    .fetch_one(&data.db_pool)
+    .await;
+
+    match produto_existe {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Produto com ID {} não encontrado.", produto_id),
+                body: None,
+            });
+        }
+        Err(e) => {
+            eprintln!("Erro ao verificar produto {} para avaliação: {:?}", produto_id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao registrar avaliação.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    let recebeu_produto = query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM vendas v
+            JOIN vendas_itens vi ON vi.venda_id = v.id
+            WHERE v.usuario_id = $1 AND vi.produto_id = $2 AND v.status = 'entregue'
+        )
+        "#
+    )
+    .bind(auth_user.user_id)
+    .bind(produto_id)
+    .fetch_one(&data.db_pool)
+    .await;
+
+    match recebeu_produto {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Só é possível avaliar produtos de pedidos já entregues.".to_string(),
+                body: None,
+            });
+        }
+        Err(e) => {
+            eprintln!("Erro ao verificar compras do usuário {} para avaliação: {:?}", auth_user.user_id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao registrar avaliação.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    let result = query_scalar::<_, i32>(
+        r#"
+        INSERT INTO avaliacoes (produto_id, usuario_id, nota, texto)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (produto_id, usuario_id) DO UPDATE
+            SET nota = EXCLUDED.nota, texto = EXCLUDED.texto, situacao = 'pendente',
+                criado_em = NOW(), moderado_em = NULL
+        RETURNING id
+        "#
+    )
+    .bind(produto_id)
+    .bind(auth_user.user_id)
+    .bind(item.nota)
+    .bind(texto)
+    .fetch_one(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(id) => HttpResponse::Created().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Avaliação recebida! Ela será publicada após a moderação.".to_string(),
+            body: Some(serde_json::json!({ "id": id, "situacao": SituacaoAvaliacao::Pendente })),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao registrar avaliação do usuário {} para o produto {}: {:?}", auth_user.user_id, produto_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao registrar avaliação.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota pública para listar as avaliações aprovadas de um produto, das mais recentes para as mais antigas.
+#[get("/produtos/{id}/avaliacoes")]
+pub async fn buscar_avaliacoes_produto(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let produto_id = path.into_inner();
+    let result = query_as::<_, AvaliacaoPublica>(
+        r#"
+        SELECT a.id, a.nota, a.texto, split_part(u.nome, ' ', 1) AS autor, a.criado_em
+        FROM avaliacoes a
+        JOIN usuarios u ON u.id = a.usuario_id
+        WHERE a.produto_id = $1 AND a.situacao = 'aprovada'
+        ORDER BY a.criado_em DESC, a.id DESC
+        "#
+    )
+    .bind(produto_id)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(avaliacoes) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Avaliações listadas com sucesso!".to_string(),
+            body: Some(avaliacoes),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar avaliações do produto {}: {:?}", produto_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar avaliações.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para listar avaliações por situação (padrão: pendentes),
+/// opcionalmente de um único produto. A fila é exibida das mais antigas para as mais novas.
+#[get("/avaliacoes")]
+pub async fn buscar_avaliacoes_moderacao(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroAvaliacoes>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let situacao = filtro.situacao.unwrap_or(SituacaoAvaliacao::Pendente);
+    let limite = filtro.limite.unwrap_or(LIMITE_PADRAO).clamp(1, LIMITE_MAXIMO);
+
+    let result = query_as::<_, AvaliacaoModeracao>(
+        r#"
+        SELECT a.id, a.produto_id, p.nome AS produto_nome, a.usuario_id, u.email AS usuario_email,
+               a.nota, a.texto, a.situacao, a.criado_em, a.moderado_em
+        FROM avaliacoes a
+        JOIN produtos p ON p.id = a.produto_id
+        JOIN usuarios u ON u.id = a.usuario_id
+        WHERE a.situacao = $1 AND ($2::int IS NULL OR a.produto_id = $2)
+        ORDER BY a.criado_em, a.id
+        LIMIT $3
+        "#
+    )
+    .bind(situacao.as_str())
+    .bind(filtro.produto_id)
+    .bind(limite)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(avaliacoes) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Avaliações listadas com sucesso!".to_string(),
+            body: Some(avaliacoes),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar avaliações para moderação: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar avaliações.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para aprovar ou rejeitar uma avaliação.
+/// A decisão é registrada na auditoria com os snapshots anterior e posterior.
+#[put("/avaliacoes/{id}/moderacao")]
+pub async fn moderar_avaliacao(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<ModeracaoAvaliacao>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    if item.situacao == SituacaoAvaliacao::Pendente {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Informe a decisão da moderação: 'aprovada' ou 'rejeitada'.".to_string(),
+            body: None,
+        });
+    }
+
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT a.id, to_jsonb(a) AS antes FROM avaliacoes a WHERE a.id = $2 FOR UPDATE)
+        UPDATE avaliacoes SET situacao = $1, moderado_em = NOW()
+        FROM anterior -- Junta o snapshot aqui para que ele seja lido antes da alteração
+        WHERE avaliacoes.id = anterior.id
+        RETURNING avaliacoes.id, anterior.antes, to_jsonb(avaliacoes) AS depois
+        "#
+    )
+    .bind(item.situacao.as_str())
+    .bind(id);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "avaliacao", AcaoAuditoria::AlteracaoStatus, comando).await {
+        Ok(Some(_)) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Avaliação {} marcada como '{}'.", id, item.situacao.as_str()),
+            body: None,
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Avaliação com ID {} não encontrada.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao moderar avaliação {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao moderar avaliação.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
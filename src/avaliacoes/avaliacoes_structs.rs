@@ -0,0 +1,74 @@
+// src/avaliacoes/avaliacoes_structs.rs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Situações de uma avaliação na fila de moderação.
+/// Persistidas como texto na coluna `avaliacoes.situacao`.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SituacaoAvaliacao {
+    Pendente,  // Aguardando moderação; não aparece na loja
+    Aprovada,  // Publicada e contada na média do produto
+    Rejeitada, // Recusada pela moderação
+}
+
+impl SituacaoAvaliacao {
+    /// Valor gravado na coluna `avaliacoes.situacao`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SituacaoAvaliacao::Pendente => "pendente",
+            SituacaoAvaliacao::Aprovada => "aprovada",
+            SituacaoAvaliacao::Rejeitada => "rejeitada",
+        }
+    }
+}
+
+/// Estrutura para receber uma avaliação na requisição POST.
+#[derive(Deserialize)]
+pub struct NovaAvaliacao {
+    pub nota: i16, // De 1 a 5 estrelas
+    pub texto: String,
+}
+
+/// Avaliação aprovada, como exibida na página do produto.
+/// O autor aparece só pelo primeiro nome.
+#[derive(Serialize, FromRow)]
+pub struct AvaliacaoPublica {
+    pub id: i32,
+    pub nota: i16,
+    pub texto: String,
+    pub autor: String,
+    pub criado_em: DateTime<Utc>,
+}
+
+/// Avaliação na visão administrativa de moderação.
+#[derive(Serialize, FromRow)]
+pub struct AvaliacaoModeracao {
+    pub id: i32,
+    pub produto_id: i32,
+    pub produto_nome: String,
+    pub usuario_id: i32,
+    pub usuario_email: String,
+    pub nota: i16,
+    pub texto: String,
+    pub situacao: String,
+    pub criado_em: DateTime<Utc>,
+    pub moderado_em: Option<DateTime<Utc>>,
+}
+
+/// Filtros aceitos na query string de `GET /avaliacoes`.
+/// Sem `situacao`, lista a fila de pendentes.
+#[derive(Deserialize)]
+pub struct FiltroAvaliacoes {
+    pub situacao: Option<SituacaoAvaliacao>,
+    pub produto_id: Option<i32>,
+    pub limite: Option<i64>,
+}
+
+/// Estrutura para receber a decisão da moderação na requisição PUT.
+#[derive(Deserialize)]
+pub struct ModeracaoAvaliacao {
+    pub situacao: SituacaoAvaliacao, // `aprovada` ou `rejeitada`
+}
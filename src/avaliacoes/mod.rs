@@ -0,0 +1,6 @@
+// src/avaliacoes/mod.rs
+
+// Declara o submódulo que contém as definições das structs de avaliações
+pub mod avaliacoes_structs;
+// Declara o submódulo que contém as funções de rota de avaliações e moderação
+pub mod avaliacoes_router;
@@ -1,15 +1,32 @@
 // src/categorias/categoria_router.rs
 
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sqlx::{query_as, query, Row};
+use actix_web::{get, post, put, patch, delete, http::header, web, HttpRequest, HttpResponse, Responder};
+use sqlx::{query_as, query, query_scalar, postgres::PgRow, FromRow, Row};
+use std::collections::HashSet;
 
 // Importa as structs de categoria
-use super::categoria_structs::{Categoria, NovaCategoria};
+use super::categoria_structs::{
+    AtualizacaoParcialCategoria, Categoria, CategoriaExcluida, DivergenciaOrdem, FiltroCategorias, ModoExclusao, NovaCategoria,
+    OpcoesExclusaoCategoria, PlanoExclusao, ProdutoAfetado, SubcategoriaAfetada,
+};
 // Importa GenericResponse do novo módulo shared_structs
 use crate::shared::shared_structs::GenericResponse;
+// Importa os auxiliares de concorrência otimista (ETag/If-Match)
+use crate::shared::concorrencia::{etag_da_versao, versao_do_if_match, conflito_de_versao};
+// Importa o redirecionamento de slugs antigos
+use crate::shared::slugs::redirecionar_para_slug;
 
 // Importa o AppState do módulo raiz (main.rs)
 use crate::AppState;
+// Importa o extrator de autorização para as rotas administrativas
+use crate::usuarios::auth_middleware::AdminUser;
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::{executar_com_auditoria, registrar_auditoria};
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
+
+/// Colunas de `Categoria`, na ordem da struct.
+const COLUNAS_CATEGORIA: &str =
+    "id, nome, slug, parent_id, posicao, visivel, descricao, icone_url, banner_url, seo_titulo, seo_descricao, versao";
 
 
 // --- Rotas para SESSÕES (Categorias Pai) ---
@@ -20,31 +37,46 @@ use crate::AppState;
 pub async fn cadastrar_sessao(
     data: web::Data<AppState>,
     item: web::Json<NovaCategoria>, // Reutiliza NovaCategoria, mas parent_id será ignorado/forçado a NULL
+    admin: AdminUser,
 ) -> HttpResponse {
-    let result = query(
-        "INSERT INTO categorias (nome, parent_id) VALUES ($1, NULL) RETURNING id" // Força parent_id para NULL
+    if let Some(problema) = item.problema_apresentacao() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: problema,
+            body: None,
+        });
+    }
+
+    // A nova sessão entra no fim do menu
+    let comando = query(
+        r#"
+        INSERT INTO categorias (nome, parent_id, visivel, descricao, icone_url, banner_url, seo_titulo, seo_descricao, posicao)
+        VALUES ($1, NULL, $2, $3, $4, $5, $6, $7,
+                (SELECT COALESCE(MAX(posicao), 0) + 1 FROM categorias WHERE parent_id IS NULL AND deleted_at IS NULL))
+        RETURNING id, NULL::jsonb AS antes, to_jsonb(categorias) AS depois
+        "# // Força parent_id para NULL
     )
     .bind(&item.nome)
-    .fetch_one(&data.db_pool)
-    .await;
+    .bind(item.visivel.unwrap_or(true))
+    .bind(&item.descricao)
+    .bind(&item.icone_url)
+    .bind(&item.banner_url)
+    .bind(&item.seo_titulo)
+    .bind(&item.seo_descricao);
 
-    match result {
-        Ok(row) => {
-            match row.try_get::<i32, &str>("id") {
-                Ok(id) => HttpResponse::Ok().json(GenericResponse {
-                    status: "success".to_string(),
-                    message: format!("Sessão cadastrada com sucesso! ID: {}", id),
-                    body: Some(serde_json::json!({ "id": id })),
-                }),
-                Err(e) => {
-                    eprintln!("Erro ao obter id da nova sessão: {:?}", e);
-                    HttpResponse::InternalServerError().json(GenericResponse::<()>{
-                        status: "error".to_string(),
-                        message: "Erro ao processar resposta do cadastro da sessão".to_string(),
-                        body: None,
-                    })
-                }
-            }
+    match executar_com_auditoria(&data.db_pool, &admin.0, "categoria", AcaoAuditoria::Criacao, comando).await {
+        Ok(Some(id)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: format!("Sessão cadastrada com sucesso! ID: {}", id),
+            body: Some(serde_json::json!({ "id": id })),
+        }),
+        Ok(None) => {
+            eprintln!("Erro ao obter id da nova sessão: nenhuma linha retornada");
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao processar resposta do cadastro da sessão".to_string(),
+                body: None,
+            })
         }
         Err(e) => {
             eprintln!("Erro ao inserir sessão: {:?}", e);
@@ -57,11 +89,18 @@ pub async fn cadastrar_sessao(
     }
 }
 
-/// Rota para buscar todas as SESSÕES (Categorias Pai).
-/// Retorna apenas as categorias onde `parent_id` é NULL.
+/// Rota para buscar todas as SESSÕES (Categorias Pai), na ordem do menu.
+/// Retorna apenas as categorias onde `parent_id` é NULL. As ocultas só aparecem
+/// para administradores que pedirem `incluir_ocultas=true`.
 #[get("/sessoes")]
-pub async fn buscar_sessoes(data: web::Data<AppState>) -> impl Responder {
-    let categorias_result = query_as::<_, Categoria>("SELECT id, nome, parent_id FROM categorias WHERE parent_id IS NULL ORDER BY id")
+pub async fn buscar_sessoes(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroCategorias>,
+    admin: Option<AdminUser>,
+) -> impl Responder {
+    let incluir_ocultas = filtro.incluir_ocultas.unwrap_or(false) && admin.is_some();
+    let categorias_result = query_as::<_, Categoria>(&format!("SELECT {} FROM categorias WHERE parent_id IS NULL AND deleted_at IS NULL AND (visivel OR $1) ORDER BY posicao, id", COLUNAS_CATEGORIA))
+        .bind(incluir_ocultas)
         .fetch_all(&data.db_pool)
         .await;
 
@@ -85,24 +124,26 @@ pub async fn buscar_sessoes(data: web::Data<AppState>) -> impl Responder {
 }
 
 /// Rota para buscar uma SESSÃO (Categoria Pai) por ID.
-/// Retorna apenas a sessão se ela existir e tiver `parent_id` NULL.
+/// Retorna apenas a sessão se ela existir e tiver `parent_id` NULL, com a versão no cabeçalho `ETag`.
 #[get("/sessoes/{id}")]
 pub async fn buscar_sessao_por_id(
     data: web::Data<AppState>,
     path: web::Path<i32>,
 ) -> HttpResponse {
     let id = path.into_inner();
-    let sessao_result = query_as::<_, Categoria>("SELECT id, nome, parent_id FROM categorias WHERE id = $1 AND parent_id IS NULL")
+    let sessao_result = query_as::<_, Categoria>(&format!("SELECT {} FROM categorias WHERE id = $1 AND parent_id IS NULL AND deleted_at IS NULL", COLUNAS_CATEGORIA))
         .bind(id)
         .fetch_optional(&data.db_pool)
         .await;
 
     match sessao_result {
-        Ok(Some(sessao)) => HttpResponse::Ok().json(GenericResponse {
-            status: "success".to_string(),
-            message: format!("Sessão com ID {} encontrada.", id),
-            body: Some(sessao),
-        }),
+        Ok(Some(sessao)) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag_da_versao(sessao.versao)))
+            .json(GenericResponse {
+                status: "success".to_string(),
+                message: format!("Sessão com ID {} encontrada.", id),
+                body: Some(sessao),
+            }),
         Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
             status: "error".to_string(),
             message: format!("Sessão com ID {} não encontrada ou não é uma sessão principal.", id),
@@ -119,127 +160,99 @@ pub async fn buscar_sessao_por_id(
     }
 }
 
+/// Rota para buscar uma SESSÃO (Categoria Pai) pelo slug, como `/sessoes/slug/cabelos`.
+/// Um slug antigo (de antes de uma troca de nome) responde 301 com o endereço atual.
+/// Deve ser registrada antes de `/sessoes/{session_id}/categorias` para não ser capturada por ela.
+#[get("/sessoes/slug/{slug}")]
+pub async fn buscar_sessao_por_slug(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    responder_categoria_por_slug(&data, path.into_inner(), true).await
+}
+
 /// Rota para atualizar uma SESSÃO (Categoria Pai) existente.
-/// Permite atualizar apenas o `nome`. O `parent_id` é mantido como NULL.
+/// Permite atualizar o `nome` e os dados de apresentação. O `parent_id` é mantido como NULL.
+/// Exige o cabeçalho `If-Match` com o ETag da sessão (412 se ela foi alterada nesse meio tempo).
 #[put("/sessoes/{id}")]
 pub async fn atualizar_sessao(
     data: web::Data<AppState>,
     path: web::Path<i32>,
     item: web::Json<NovaCategoria>, // Reutiliza NovaCategoria, mas parent_id será ignorado
+    req: HttpRequest,
+    admin: AdminUser,
 ) -> HttpResponse {
     let id = path.into_inner();
-    let result = query(
-        "UPDATE categorias SET nome = $1 WHERE id = $2 AND parent_id IS NULL" // Garante que só atualiza sessões
-    )
-    .bind(&item.nome)
-    .bind(id)
-    .execute(&data.db_pool)
-    .await;
+    let versao = match versao_do_if_match(&req) {
+        Ok(v) => v,
+        Err(resposta) => return resposta,
+    };
 
-    match result {
-        Ok(res) => {
-            if res.rows_affected() > 0 {
-                HttpResponse::Ok().json(GenericResponse::<()>{
-                    status: "success".to_string(),
-                    message: format!("Sessão com ID {} atualizada com sucesso.", id),
-                    body: None,
-                })
-            } else {
-                HttpResponse::NotFound().json(GenericResponse::<()>{
-                    status: "error".to_string(),
-                    message: format!("Sessão com ID {} não encontrada ou não é uma sessão principal para atualização.", id),
-                    body: None,
-                })
-            }
-        },
-        Err(e) => {
-            eprintln!("Erro ao atualizar sessão com ID {}: {:?}", id, e);
-            HttpResponse::InternalServerError().json(GenericResponse::<()>{
-                status: "error".to_string(),
-                message: "Erro ao atualizar sessão".to_string(),
-                body: None,
-            })
-        }
+    if let Some(problema) = item.problema_apresentacao() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: problema,
+            body: None,
+        });
     }
+
+    let mut dados = AtualizacaoParcialCategoria::from(item.into_inner());
+    dados.parent_id = None; // O parent_id de uma sessão é mantido como NULL
+    aplicar_atualizacao_categoria(&data, id, versao, dados, true, &admin).await
 }
 
 /// Rota para deletar uma SESSÃO (Categoria Pai).
 /// Garante que apenas sessões (parent_id IS NULL) podem ser deletadas por esta rota.
 /// Adiciona validação para impedir a exclusão de categorias filhas por este endpoint.
+/// O destino das subcategorias e dos produtos ativos é escolhido em `modo` (veja `excluir_categoria`).
+/// A exclusão é lógica (soft delete) e pode ser desfeita por `/sessoes/{id}/restaurar`.
 #[delete("/sessoes/{id}")]
 pub async fn deletar_sessao(
     data: web::Data<AppState>,
     path: web::Path<i32>,
+    opcoes: web::Query<OpcoesExclusaoCategoria>,
+    admin: AdminUser,
 ) -> HttpResponse {
-    let id = path.into_inner();
+    excluir_categoria(&data, path.into_inner(), opcoes.into_inner(), true, &admin).await
+}
 
-    // 1. Busca a categoria existente para verificar seu parent_id
-    let existing_category_result = query_as::<_, Categoria>("SELECT id, nome, parent_id FROM categorias WHERE id = $1")
-        .bind(id)
-        .fetch_optional(&data.db_pool)
-        .await;
+/// Rota administrativa para restaurar uma SESSÃO excluída logicamente.
+#[post("/sessoes/{id}/restaurar")]
+pub async fn restaurar_sessao(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT to_jsonb(c) AS antes FROM categorias c WHERE c.id = $1 FOR UPDATE)
+        UPDATE categorias SET deleted_at = NULL, versao = versao + 1 WHERE id = $1 AND parent_id IS NULL AND deleted_at IS NOT NULL
+        RETURNING id, (SELECT antes FROM anterior) AS antes, to_jsonb(categorias) AS depois
+        "#
+    )
+    .bind(id);
 
-    let existing_category = match existing_category_result {
-        Ok(Some(cat)) => cat,
-        Ok(None) => return HttpResponse::NotFound().json(GenericResponse::<()>{
-            status: "error".to_string(),
-            message: format!("Sessão com ID {} não encontrada para exclusão.", id),
-            body: None,
-        }),
-        Err(e) => {
-            eprintln!("Erro ao buscar categoria existente para exclusão {}: {:?}", id, e);
-            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+    match executar_com_auditoria(&data.db_pool, &admin.0, "categoria", AcaoAuditoria::Restauracao, comando).await {
+        Ok(Some(_)) => {
+            HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Sessão com ID {} restaurada com sucesso.", id),
+                body: None,
+            })
+        },
+        Ok(None) => {
+            HttpResponse::NotFound().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: "Erro interno ao buscar categoria para exclusão.".to_string(),
+                message: format!("Sessão com ID {} não encontrada entre as excluídas.", id),
                 body: None,
-            });
-        }
-    };
-
-    // 2. Validação: Se a categoria encontrada NÃO é uma sessão (parent_id IS NOT NULL),
-    // retorna um erro.
-    if existing_category.parent_id.is_some() {
-        return HttpResponse::BadRequest().json(GenericResponse::<()>{
-            status: "error".to_string(),
-            message: "Não é possível excluir uma categoria filha na rota de sessão. Use /categorias/{id} para isso.".to_string(),
-            body: None,
-        });
-    }
-
-    // 3. Procede com a exclusão da sessão (parent_id IS NULL)
-    let result = query("DELETE FROM categorias WHERE id = $1 AND parent_id IS NULL") // Garante que só deleta sessões
-        .bind(id)
-        .execute(&data.db_pool)
-        .await;
-
-    match result {
-        Ok(res) => {
-            if res.rows_affected() > 0 {
-                HttpResponse::Ok().json(GenericResponse::<()>{
-                    status: "success".to_string(),
-                    message: format!("Sessão com ID {} deletada com sucesso.", id),
-                    body: None,
-                })
-            } else {
-                // Esta parte pode ser redundante devido à verificação inicial, mas mantém a consistência
-                HttpResponse::NotFound().json(GenericResponse::<()>{
-                    status: "error".to_string(),
-                    message: format!("Sessão com ID {} não encontrada para exclusão.", id),
-                    body: None,
-                })
-            }
+            })
         },
         Err(e) => {
-            eprintln!("Erro ao deletar sessão com ID {}: {:?}", id, e);
-            // Adicionar tratamento para erro de chave estrangeira se houver categorias filhas
-            let error_message = if e.to_string().contains("foreign key constraint") {
-                "Não é possível deletar a sessão: existem categorias filhas associadas a ela.".to_string()
-            } else {
-                "Erro ao deletar sessão.".to_string()
-            };
+            eprintln!("Erro ao restaurar sessão com ID {}: {:?}", id, e);
             HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: error_message,
+                message: "Erro ao restaurar sessão.".to_string(),
                 body: None,
             })
         }
@@ -256,6 +269,7 @@ pub async fn deletar_sessao(
 pub async fn cadastrar_categoria(
     data: web::Data<AppState>,
     item: web::Json<NovaCategoria>,
+    admin: AdminUser,
 ) -> HttpResponse {
     // Verifica se parent_id foi fornecido, pois é obrigatório para categorias filhas
     if item.parent_id.is_none() {
@@ -266,31 +280,45 @@ pub async fn cadastrar_categoria(
         });
     }
 
-    let result = query(
-        "INSERT INTO categorias (nome, parent_id) VALUES ($1, $2) RETURNING id"
+    if let Some(problema) = item.problema_apresentacao() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: problema,
+            body: None,
+        });
+    }
+
+    // A nova categoria entra depois das outras filhas do mesmo pai
+    let comando = query(
+        r#"
+        INSERT INTO categorias (nome, parent_id, visivel, descricao, icone_url, banner_url, seo_titulo, seo_descricao, posicao)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8,
+                (SELECT COALESCE(MAX(posicao), 0) + 1 FROM categorias WHERE parent_id = $2 AND deleted_at IS NULL))
+        RETURNING id, NULL::jsonb AS antes, to_jsonb(categorias) AS depois
+        "#
     )
     .bind(&item.nome)
     .bind(item.parent_id) // Binda o parent_id que deve ser fornecido
-    .fetch_one(&data.db_pool)
-    .await;
+    .bind(item.visivel.unwrap_or(true))
+    .bind(&item.descricao)
+    .bind(&item.icone_url)
+    .bind(&item.banner_url)
+    .bind(&item.seo_titulo)
+    .bind(&item.seo_descricao);
 
-    match result {
-        Ok(row) => {
-            match row.try_get::<i32, &str>("id") {
-                Ok(id) => HttpResponse::Ok().json(GenericResponse {
-                    status: "success".to_string(),
-                    message: format!("Categoria cadastrada com sucesso! ID: {}", id),
-                    body: Some(serde_json::json!({ "id": id })),
-                }),
-                Err(e) => {
-                    eprintln!("Erro ao obter id da nova categoria: {:?}", e);
-                    HttpResponse::InternalServerError().json(GenericResponse::<()>{
-                        status: "error".to_string(),
-                        message: "Erro ao processar resposta do cadastro da categoria".to_string(),
-                        body: None,
-                    })
-                }
-            }
+    match executar_com_auditoria(&data.db_pool, &admin.0, "categoria", AcaoAuditoria::Criacao, comando).await {
+        Ok(Some(id)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: format!("Categoria cadastrada com sucesso! ID: {}", id),
+            body: Some(serde_json::json!({ "id": id })),
+        }),
+        Ok(None) => {
+            eprintln!("Erro ao obter id da nova categoria: nenhuma linha retornada");
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao processar resposta do cadastro da categoria".to_string(),
+                body: None,
+            })
         }
         Err(e) => {
             eprintln!("Erro ao inserir categoria: {:?}", e);
@@ -308,16 +336,21 @@ pub async fn cadastrar_categoria(
     }
 }
 
-/// Rota para buscar CATEGORIAS FILHAS de uma SESSÃO específica.
-/// Retorna categorias onde `parent_id` é igual ao ID da sessão fornecido.
+/// Rota para buscar CATEGORIAS FILHAS de uma SESSÃO específica, na ordem do menu.
+/// Retorna categorias onde `parent_id` é igual ao ID da sessão fornecido. As ocultas só
+/// aparecem para administradores que pedirem `incluir_ocultas=true`.
 #[get("/sessoes/{session_id}/categorias")]
 pub async fn buscar_categorias_por_sessao(
     data: web::Data<AppState>,
     path: web::Path<i32>,
+    filtro: web::Query<FiltroCategorias>,
+    admin: Option<AdminUser>,
 ) -> impl Responder {
     let session_id = path.into_inner();
-    let categorias_result = query_as::<_, Categoria>("SELECT id, nome, parent_id FROM categorias WHERE parent_id = $1 ORDER BY id")
+    let incluir_ocultas = filtro.incluir_ocultas.unwrap_or(false) && admin.is_some();
+    let categorias_result = query_as::<_, Categoria>(&format!("SELECT {} FROM categorias WHERE parent_id = $1 AND deleted_at IS NULL AND (visivel OR $2) ORDER BY posicao, id", COLUNAS_CATEGORIA))
         .bind(session_id)
+        .bind(incluir_ocultas)
         .fetch_all(&data.db_pool)
         .await;
 
@@ -340,26 +373,156 @@ pub async fn buscar_categorias_por_sessao(
     }
 }
 
+/// Rota administrativa para reordenar as SESSÕES no menu.
+/// Recebe a lista com os IDs de todas as sessões ativas (inclusive as ocultas) na ordem desejada.
+/// Deve ser registrada antes de `/sessoes/{id}` para não ser capturada por ela.
+#[put("/sessoes/ordem")]
+pub async fn reordenar_sessoes(
+    data: web::Data<AppState>,
+    item: web::Json<Vec<i32>>,
+    admin: AdminUser,
+) -> HttpResponse {
+    reordenar_categorias(&data, None, item.into_inner(), &admin).await
+}
+
+/// Rota administrativa para reordenar as categorias filhas de uma sessão (ou de outra categoria).
+/// Recebe a lista com os IDs de todas as filhas ativas (inclusive as ocultas) na ordem desejada.
+#[put("/sessoes/{session_id}/categorias/ordem")]
+pub async fn reordenar_categorias_da_sessao(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<Vec<i32>>,
+    admin: AdminUser,
+) -> HttpResponse {
+    reordenar_categorias(&data, Some(path.into_inner()), item.into_inner(), &admin).await
+}
+
+/// Resultado da reordenação das categorias com o mesmo pai.
+enum ResultadoOrdem {
+    Reordenado,
+    PaiNaoEncontrado,
+    Divergente(DivergenciaOrdem),
+}
+
+/// Grava a nova ordem das categorias com o pai informado (`None` para as sessões).
+/// A lista precisa conter cada categoria do grupo exatamente uma vez; as que mudam de posição
+/// têm a versão incrementada e a alteração registrada na auditoria.
+async fn reordenar_categorias(
+    data: &web::Data<AppState>,
+    parent_id: Option<i32>,
+    ids: Vec<i32>,
+    admin: &AdminUser,
+) -> HttpResponse {
+    let result: Result<ResultadoOrdem, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        if let Some(parent_id) = parent_id {
+            let pai = query_scalar::<_, i32>("SELECT id FROM categorias WHERE id = $1 AND deleted_at IS NULL")
+                .bind(parent_id)
+                .fetch_optional(&mut *transaction)
+                .await?;
+            if pai.is_none() {
+                return Ok(ResultadoOrdem::PaiNaoEncontrado);
+            }
+        }
+
+        let atuais = query_scalar::<_, i32>(
+            "SELECT id FROM categorias WHERE parent_id IS NOT DISTINCT FROM $1 AND deleted_at IS NULL ORDER BY id FOR UPDATE"
+        )
+        .bind(parent_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut vistos = HashSet::new();
+        let mut repetidos: Vec<i32> = ids.iter().copied().filter(|id| !vistos.insert(*id)).collect();
+        repetidos.sort_unstable();
+        repetidos.dedup();
+        let faltando: Vec<i32> = atuais.iter().copied().filter(|id| !vistos.contains(id)).collect();
+        let desconhecidos: Vec<i32> = ids.iter().copied().filter(|id| !atuais.contains(id)).collect();
+        if !(faltando.is_empty() && desconhecidos.is_empty() && repetidos.is_empty()) {
+            return Ok(ResultadoOrdem::Divergente(DivergenciaOrdem { faltando, desconhecidos, repetidos }));
+        }
+
+        let alteradas = query(
+            r#"
+            WITH nova_ordem AS (
+                SELECT id, posicao FROM UNNEST($1::int[]) WITH ORDINALITY AS o(id, posicao)
+            ),
+            anterior AS (
+                SELECT c.id, to_jsonb(c) AS antes FROM categorias c WHERE c.id = ANY($1)
+            )
+            UPDATE categorias SET posicao = nova_ordem.posicao, versao = versao + 1
+            FROM nova_ordem JOIN anterior ON anterior.id = nova_ordem.id
+            WHERE categorias.id = nova_ordem.id AND categorias.posicao <> nova_ordem.posicao
+            RETURNING categorias.id, anterior.antes, to_jsonb(categorias) AS depois
+            "#
+        )
+        .bind(&ids)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        for linha in alteradas {
+            let id: i32 = linha.try_get("id")?;
+            let antes: Option<serde_json::Value> = linha.try_get("antes")?;
+            let depois: Option<serde_json::Value> = linha.try_get("depois")?;
+            registrar_auditoria(&mut transaction, &admin.0, "categoria", id, AcaoAuditoria::Atualizacao, antes, depois).await?;
+        }
+
+        transaction.commit().await?;
+        Ok(ResultadoOrdem::Reordenado)
+    }
+    .await;
+
+    match result {
+        Ok(ResultadoOrdem::Reordenado) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Ordem atualizada com sucesso.".to_string(),
+            body: None,
+        }),
+        Ok(ResultadoOrdem::PaiNaoEncontrado) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Categoria com ID {} não encontrada.", parent_id.unwrap_or_default()),
+            body: None,
+        }),
+        Ok(ResultadoOrdem::Divergente(divergencia)) => HttpResponse::BadRequest().json(GenericResponse {
+            status: "error".to_string(),
+            message: "A lista deve conter cada categoria do grupo exatamente uma vez.".to_string(),
+            body: Some(divergencia),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao reordenar categorias do pai {:?}: {:?}", parent_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao reordenar categorias.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
 // --- Rotas genéricas de Categoria (podem ser usadas para Sessões ou Categorias Filhas por ID) ---
 
 /// Rota para buscar uma categoria (sessão ou filha) por ID.
+/// A versão atual é enviada no cabeçalho `ETag`.
 #[get("/categorias/{id}")]
 pub async fn buscar_categoria_por_id(
     data: web::Data<AppState>,
     path: web::Path<i32>,
 ) -> HttpResponse {
     let id = path.into_inner();
-    let categoria_result = query_as::<_, Categoria>("SELECT id, nome, parent_id FROM categorias WHERE id = $1")
+    let categoria_result = query_as::<_, Categoria>(&format!("SELECT {} FROM categorias WHERE id = $1 AND deleted_at IS NULL", COLUNAS_CATEGORIA))
         .bind(id)
         .fetch_optional(&data.db_pool)
         .await;
 
     match categoria_result {
-        Ok(Some(categoria)) => HttpResponse::Ok().json(GenericResponse {
-            status: "success".to_string(),
-            message: format!("Categoria com ID {} encontrada.", id),
-            body: Some(categoria),
-        }),
+        Ok(Some(categoria)) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag_da_versao(categoria.versao)))
+            .json(GenericResponse {
+                status: "success".to_string(),
+                message: format!("Categoria com ID {} encontrada.", id),
+                body: Some(categoria),
+            }),
         Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
             status: "error".to_string(),
             message: format!("Categoria com ID {} não encontrada.", id),
@@ -376,20 +539,84 @@ pub async fn buscar_categoria_por_id(
     }
 }
 
+/// Rota para buscar uma categoria (sessão ou filha) pelo slug, como `/categorias/slug/shampoos`.
+/// Um slug antigo (de antes de uma troca de nome) responde 301 com o endereço atual.
+/// Deve ser registrada antes das rotas `/categorias/{id}/...` para não ser capturada por elas.
+#[get("/categorias/slug/{slug}")]
+pub async fn buscar_categoria_por_slug(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    responder_categoria_por_slug(&data, path.into_inner(), false).await
+}
+
+/// Busca a categoria dona do slug (atual ou antigo) e monta a resposta das rotas por slug.
+/// Com `somente_sessao`, categorias filhas não são encontradas.
+async fn responder_categoria_por_slug(
+    data: &web::Data<AppState>,
+    slug: String,
+    somente_sessao: bool,
+) -> HttpResponse {
+    let (descricao, caminho_base) = if somente_sessao { ("Sessão", "/sessoes/slug") } else { ("Categoria", "/categorias/slug") };
+    let categoria_result = query_as::<_, Categoria>(&format!(
+        r#"
+        SELECT {} FROM categorias
+        WHERE id = (SELECT entidade_id FROM slugs WHERE entidade = 'categoria' AND slug = $1)
+          AND deleted_at IS NULL AND (NOT $2 OR parent_id IS NULL)
+        "#,
+        COLUNAS_CATEGORIA
+    ))
+    .bind(&slug)
+    .bind(somente_sessao)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    match categoria_result {
+        Ok(Some(categoria)) if categoria.slug != slug => redirecionar_para_slug(caminho_base, &categoria.slug),
+        Ok(Some(categoria)) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag_da_versao(categoria.versao)))
+            .json(GenericResponse {
+                status: "success".to_string(),
+                message: format!("{} '{}' encontrada.", descricao, slug),
+                body: Some(categoria),
+            }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("{} '{}' não encontrada.", descricao, slug),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar categoria pelo slug '{}': {:?}", slug, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Erro ao buscar {}", descricao.to_lowercase()),
+                body: None,
+            })
+        }
+    }
+}
+
 /// Rota para atualizar uma categoria (sessão ou filha) existente.
-/// Permite atualizar o `nome` e o `parent_id`.
+/// Permite atualizar o `nome`, o `parent_id` e os dados de apresentação.
 /// Inclui validação para impedir que uma sessão se torne uma subcategoria
 /// e que uma subcategoria se torne uma sessão.
+/// Exige o cabeçalho `If-Match` com o ETag da categoria (412 se ela foi alterada nesse meio tempo).
 #[put("/categorias/{id}")]
 pub async fn atualizar_categoria(
     data: web::Data<AppState>,
     path: web::Path<i32>,
     item: web::Json<NovaCategoria>,
+    req: HttpRequest,
+    admin: AdminUser,
 ) -> HttpResponse {
     let id = path.into_inner();
+    let versao = match versao_do_if_match(&req) {
+        Ok(v) => v,
+        Err(resposta) => return resposta,
+    };
 
     // 1. Busca a categoria existente para verificar seu parent_id atual
-    let existing_category_result = query_as::<_, Categoria>("SELECT id, nome, parent_id FROM categorias WHERE id = $1")
+    let existing_category_result = query_as::<_, Categoria>(&format!("SELECT {} FROM categorias WHERE id = $1 AND deleted_at IS NULL", COLUNAS_CATEGORIA))
         .bind(id)
         .fetch_optional(&data.db_pool)
         .await;
@@ -433,39 +660,159 @@ pub async fn atualizar_categoria(
         });
     }
 
+    if let Some(problema) = item.problema_apresentacao() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: problema,
+            body: None,
+        });
+    }
+
     // 3. Procede com a atualização
-    let result = query(
-        "UPDATE categorias SET nome = $1, parent_id = $2 WHERE id = $3"
+    aplicar_atualizacao_categoria(&data, id, versao, item.into_inner().into(), false, &admin).await
+}
+
+/// Rota para atualizar parcialmente uma categoria (sessão ou filha) existente.
+/// Apenas os campos presentes no corpo são alterados. Um `parent_id` só pode ser
+/// informado para subcategorias, mantendo as mesmas regras da atualização completa.
+/// Exige o cabeçalho `If-Match`, assim como a atualização completa.
+#[patch("/categorias/{id}")]
+pub async fn atualizar_categoria_parcial(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<AtualizacaoParcialCategoria>,
+    req: HttpRequest,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let versao = match versao_do_if_match(&req) {
+        Ok(v) => v,
+        Err(resposta) => return resposta,
+    };
+
+    if item.is_vazia() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Informe ao menos um campo para atualizar.".to_string(),
+            body: None,
+        });
+    }
+    if let Some(problema) = item.problema_apresentacao() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: problema,
+            body: None,
+        });
+    }
+
+    // Uma sessão não pode receber parent_id; verifica o tipo da categoria existente
+    if item.parent_id.is_some() {
+        let existing_category_result = query_as::<_, Categoria>(&format!("SELECT {} FROM categorias WHERE id = $1 AND deleted_at IS NULL", COLUNAS_CATEGORIA))
+            .bind(id)
+            .fetch_optional(&data.db_pool)
+            .await;
+
+        match existing_category_result {
+            Ok(Some(cat)) if cat.parent_id.is_none() => {
+                return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "Uma sessão (categoria principal) não pode ser convertida em subcategoria.".to_string(),
+                    body: None,
+                });
+            },
+            Ok(Some(_)) => {},
+            Ok(None) => return HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Categoria com ID {} não encontrada para atualização.", id),
+                body: None,
+            }),
+            Err(e) => {
+                eprintln!("Erro ao buscar categoria existente para atualização {}: {:?}", id, e);
+                return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "Erro interno ao buscar categoria para atualização.".to_string(),
+                    body: None,
+                });
+            }
+        }
+    }
+
+    aplicar_atualizacao_categoria(&data, id, versao, item.into_inner(), false, &admin).await
+}
+
+/// Aplica a atualização (completa ou parcial) de uma categoria, condicionada à versão esperada.
+/// Campos `None` são mantidos e a versão é incrementada a cada alteração. Com `somente_sessao`,
+/// só atualiza sessões. Uma categoria que muda de pai vai para o fim das filhas do novo pai.
+async fn aplicar_atualizacao_categoria(
+    data: &web::Data<AppState>,
+    id: i32,
+    versao_esperada: i32,
+    item: AtualizacaoParcialCategoria,
+    somente_sessao: bool,
+    admin: &AdminUser,
+) -> HttpResponse {
+    let descricao = if somente_sessao { "Sessão" } else { "Categoria" };
+    let comando = query(
+        r#"
+        WITH anterior AS (
+            SELECT c.id, c.parent_id, to_jsonb(c) AS antes FROM categorias c
+            WHERE c.id = $1 AND c.deleted_at IS NULL AND (NOT $2 OR c.parent_id IS NULL)
+            FOR UPDATE
+        )
+        UPDATE categorias SET
+            nome = COALESCE($3, nome),
+            parent_id = COALESCE($4, categorias.parent_id),
+            posicao = CASE
+                WHEN $4::int IS DISTINCT FROM anterior.parent_id AND $4::int IS NOT NULL THEN
+                    (SELECT COALESCE(MAX(irmas.posicao), 0) + 1 FROM categorias irmas WHERE irmas.parent_id = $4 AND irmas.deleted_at IS NULL)
+                ELSE posicao
+            END,
+            visivel = COALESCE($5, visivel),
+            descricao = CASE WHEN $6 THEN $7 ELSE descricao END,
+            icone_url = CASE WHEN $8 THEN $9 ELSE icone_url END,
+            banner_url = CASE WHEN $10 THEN $11 ELSE banner_url END,
+            seo_titulo = CASE WHEN $12 THEN $13 ELSE seo_titulo END,
+            seo_descricao = CASE WHEN $14 THEN $15 ELSE seo_descricao END,
+            versao = versao + 1
+        FROM anterior
+        WHERE categorias.id = anterior.id AND categorias.versao = $16
+        RETURNING categorias.id, anterior.antes, to_jsonb(categorias) AS depois
+        "#
     )
-    .bind(&item.nome)
-    .bind(item.parent_id) // Binda o novo parent_id (pode ser NULL ou um ID válido)
     .bind(id)
-    .execute(&data.db_pool)
-    .await;
+    .bind(somente_sessao)
+    .bind(&item.nome)
+    .bind(item.parent_id) // Binda o novo parent_id (NULL mantém o atual)
+    .bind(item.visivel)
+    .bind(item.descricao.is_some())
+    .bind(item.descricao.flatten())
+    .bind(item.icone_url.is_some())
+    .bind(item.icone_url.flatten())
+    .bind(item.banner_url.is_some())
+    .bind(item.banner_url.flatten())
+    .bind(item.seo_titulo.is_some())
+    .bind(item.seo_titulo.flatten())
+    .bind(item.seo_descricao.is_some())
+    .bind(item.seo_descricao.flatten())
+    .bind(versao_esperada);
 
-    match result {
-        Ok(res) => {
-            if res.rows_affected() > 0 {
-                HttpResponse::Ok().json(GenericResponse::<()>{
+    match executar_com_auditoria(&data.db_pool, &admin.0, "categoria", AcaoAuditoria::Atualizacao, comando).await {
+        Ok(Some(_)) => {
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag_da_versao(versao_esperada + 1)))
+                .json(GenericResponse::<()>{
                     status: "success".to_string(),
-                    message: format!("Categoria com ID {} atualizada com sucesso.", id),
-                    body: None,
-                })
-            } else {
-                // Esta parte pode ser redundante devido às verificações iniciais, mas mantém a consistência
-                HttpResponse::NotFound().json(GenericResponse::<()>{
-                    status: "error".to_string(),
-                    message: format!("Categoria com ID {} não encontrada para atualização.", id),
+                    message: format!("{} com ID {} atualizada com sucesso.", descricao, id),
                     body: None,
                 })
-            }
         },
+        Ok(None) => responder_categoria_nao_alterada(data, id, somente_sessao).await,
         Err(e) => {
-            eprintln!("Erro ao atualizar categoria com ID {}: {:?}", id, e);
+            eprintln!("Erro ao atualizar {} com ID {}: {:?}", descricao.to_lowercase(), id, e);
             let error_message = if e.to_string().contains("foreign key constraint") {
                 "Erro ao atualizar categoria: parent_id inválido. Verifique o ID da categoria pai.".to_string()
             } else {
-                "Erro ao atualizar categoria.".to_string()
+                format!("Erro ao atualizar {}.", descricao.to_lowercase())
             };
             HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
@@ -476,84 +823,422 @@ pub async fn atualizar_categoria(
     }
 }
 
-/// Rota para deletar uma categoria (sessão ou filha).
-/// Esta rota pode deletar qualquer categoria pelo seu ID, mas com as devidas restrições de FK.
+/// Monta a resposta para uma atualização condicional que não alterou nenhuma linha:
+/// 412 se a categoria existe com outra versão, ou 404 se ela não existe (ou não é do tipo esperado).
+async fn responder_categoria_nao_alterada(
+    data: &web::Data<AppState>,
+    id: i32,
+    somente_sessao: bool,
+) -> HttpResponse {
+    let descricao = if somente_sessao { "Sessão" } else { "Categoria" };
+    let versao_atual = sqlx::query_scalar::<_, i32>(
+        "SELECT versao FROM categorias WHERE id = $1 AND deleted_at IS NULL AND (NOT $2 OR parent_id IS NULL)"
+    )
+    .bind(id)
+    .bind(somente_sessao)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    match versao_atual {
+        Ok(Some(versao_atual)) => conflito_de_versao(
+            format!("{} com ID {} foi alterada por outra pessoa. Recarregue e tente novamente.", descricao, id),
+            versao_atual,
+        ),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("{} com ID {} não encontrada para atualização.", descricao, id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao verificar versão da categoria com ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Erro ao atualizar {}.", descricao.to_lowercase()),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para deletar uma categoria filha.
 /// Adiciona validação para impedir a exclusão de sessões por este endpoint.
+/// O destino das subcategorias e dos produtos ativos é escolhido em `modo` (veja `excluir_categoria`).
+/// A exclusão é lógica (soft delete) e pode ser desfeita por `/categorias/{id}/restaurar`.
 #[delete("/categorias/{id}")]
 pub async fn deletar_categoria(
     data: web::Data<AppState>,
     path: web::Path<i32>,
+    opcoes: web::Query<OpcoesExclusaoCategoria>,
+    admin: AdminUser,
+) -> HttpResponse {
+    excluir_categoria(&data, path.into_inner(), opcoes.into_inner(), false, &admin).await
+}
+
+/// Rota administrativa para restaurar uma CATEGORIA filha excluída logicamente.
+/// A restauração é recusada enquanto a categoria pai continuar excluída.
+#[post("/categorias/{id}/restaurar")]
+pub async fn restaurar_categoria(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    admin: AdminUser,
 ) -> HttpResponse {
     let id = path.into_inner();
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT to_jsonb(c) AS antes FROM categorias c WHERE c.id = $1 FOR UPDATE)
+        UPDATE categorias c SET deleted_at = NULL, versao = c.versao + 1
+        WHERE c.id = $1 AND c.parent_id IS NOT NULL AND c.deleted_at IS NOT NULL
+          AND EXISTS (SELECT 1 FROM categorias pai WHERE pai.id = c.parent_id AND pai.deleted_at IS NULL)
+        RETURNING c.id, (SELECT antes FROM anterior) AS antes, to_jsonb(c) AS depois
+        "#
+    )
+    .bind(id);
 
-    // 1. Busca a categoria existente para verificar seu parent_id
-    let existing_category_result = query_as::<_, Categoria>("SELECT id, nome, parent_id FROM categorias WHERE id = $1")
-        .bind(id)
-        .fetch_optional(&data.db_pool)
-        .await;
+    match executar_com_auditoria(&data.db_pool, &admin.0, "categoria", AcaoAuditoria::Restauracao, comando).await {
+        Ok(Some(_)) => {
+            HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Categoria com ID {} restaurada com sucesso.", id),
+                body: None,
+            })
+        },
+        Ok(None) => {
+            HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Categoria com ID {} não encontrada entre as excluídas ou sua categoria pai ainda está excluída.", id),
+                body: None,
+            })
+        },
+        Err(e) => {
+            eprintln!("Erro ao restaurar categoria com ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao restaurar categoria.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
 
-    let existing_category = match existing_category_result {
-        Ok(Some(cat)) => cat,
-        Ok(None) => return HttpResponse::NotFound().json(GenericResponse::<()>{
-            status: "error".to_string(),
-            message: format!("Categoria com ID {} não encontrada para exclusão.", id),
-            body: None,
+/// Rota administrativa para listar sessões e categorias excluídas logicamente.
+/// Deve ser registrada antes de `/categorias/{id}` para não ser capturada por ela.
+#[get("/categorias/excluidas")]
+pub async fn buscar_categorias_excluidas(
+    data: web::Data<AppState>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let categorias_result = query_as::<_, CategoriaExcluida>(
+        "SELECT id, nome, parent_id, deleted_at FROM categorias WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"
+    )
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match categorias_result {
+        Ok(categorias) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Categorias excluídas listadas com sucesso!".to_string(),
+            body: Some(categorias),
         }),
         Err(e) => {
-            eprintln!("Erro ao buscar categoria existente para exclusão {}: {:?}", id, e);
-            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+            eprintln!("Erro ao buscar categorias excluídas: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: "Erro interno ao buscar categoria para exclusão.".to_string(),
+                message: "Erro ao buscar categorias excluídas".to_string(),
                 body: None,
-            });
+            })
         }
-    };
+    }
+}
 
-    // 2. Validação: Se a categoria encontrada É uma sessão (parent_id IS NULL),
-    // retorna um erro, pois sessões devem ser deletadas pela rota específica.
-    if existing_category.parent_id.is_none() {
-        return HttpResponse::BadRequest().json(GenericResponse::<()>{
-            status: "error".to_string(),
-            message: "Não é possível excluir uma sessão principal na rota de categorias. Use /sessoes/{id} para isso.".to_string(),
-            body: None,
-        });
+/// Resultado da exclusão de uma sessão ou categoria.
+enum ResultadoExclusao {
+    NaoEncontrada,
+    TipoIncorreto,
+    DestinoInvalido(String),
+    Bloqueada(PlanoExclusao),
+    Concluida(PlanoExclusao),
+}
+
+/// Exclui logicamente uma sessão (`somente_sessao`) ou uma categoria filha.
+/// Com dependentes ativos, o `modo` decide o que acontece:
+/// - `recusar` (padrão): nada é alterado e a resposta lista as subcategorias e os produtos diretos;
+/// - `mover`: subcategorias (com as suas subárvores) e produtos diretos passam para `destino_id`,
+///   entrando no fim da ordem do destino;
+/// - `cascata`: todas as subcategorias e os produtos da subárvore também são excluídos.
+///
+/// Tudo roda em uma transação e cada linha alterada é registrada na auditoria. Com `simular=true`
+/// a transação é desfeita no fim e a resposta traz exatamente o que seria alterado.
+async fn excluir_categoria(
+    data: &web::Data<AppState>,
+    id: i32,
+    opcoes: OpcoesExclusaoCategoria,
+    somente_sessao: bool,
+    admin: &AdminUser,
+) -> HttpResponse {
+    let modo = opcoes.modo.unwrap_or(ModoExclusao::Recusar);
+    let simular = opcoes.simular.unwrap_or(false);
+    let descricao = if somente_sessao { "Sessão" } else { "Categoria" };
+
+    match (modo, opcoes.destino_id) {
+        (ModoExclusao::Mover, None) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "O modo 'mover' exige o parâmetro 'destino_id'.".to_string(),
+                body: None,
+            });
+        }
+        (ModoExclusao::Recusar | ModoExclusao::Cascata, Some(_)) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "O parâmetro 'destino_id' só é usado no modo 'mover'.".to_string(),
+                body: None,
+            });
+        }
+        _ => {}
     }
 
-    // 3. Procede com a exclusão da categoria filha
-    let result = query("DELETE FROM categorias WHERE id = $1")
+    let result: Result<ResultadoExclusao, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let parent_id = query_scalar::<_, Option<i32>>(
+            "SELECT parent_id FROM categorias WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"
+        )
         .bind(id)
-        .execute(&data.db_pool)
-        .await;
+        .fetch_optional(&mut *transaction)
+        .await?;
+        match parent_id {
+            None => return Ok(ResultadoExclusao::NaoEncontrada),
+            Some(parent_id) if parent_id.is_none() != somente_sessao => return Ok(ResultadoExclusao::TipoIncorreto),
+            Some(_) => {}
+        }
+
+        let mut plano = PlanoExclusao { modo, destino_id: opcoes.destino_id, subcategorias: Vec::new(), produtos: Vec::new() };
+
+        match modo {
+            ModoExclusao::Recusar => {
+                plano.subcategorias = query_as::<_, SubcategoriaAfetada>(
+                    "SELECT id, nome, parent_id FROM categorias WHERE parent_id = $1 AND deleted_at IS NULL ORDER BY posicao, id"
+                )
+                .bind(id)
+                .fetch_all(&mut *transaction)
+                .await?;
+                plano.produtos = query_as::<_, ProdutoAfetado>(
+                    "SELECT id, nome, categoria_id FROM produtos WHERE categoria_id = $1 AND deleted_at IS NULL ORDER BY id"
+                )
+                .bind(id)
+                .fetch_all(&mut *transaction)
+                .await?;
+                if !(plano.subcategorias.is_empty() && plano.produtos.is_empty()) {
+                    return Ok(ResultadoExclusao::Bloqueada(plano));
+                }
+            }
+            ModoExclusao::Mover => {
+                let destino_id = opcoes.destino_id.unwrap_or_default();
+                // O destino não pode ser a própria categoria nem estar abaixo dela, senão a árvore teria um ciclo
+                let destino = query(
+                    r#"
+                    WITH RECURSIVE subarvore AS (
+                        SELECT $1::int AS id
+                        UNION ALL
+                        SELECT c.id FROM categorias c JOIN subarvore s ON c.parent_id = s.id
+                    )
+                    SELECT d.id, EXISTS (SELECT 1 FROM subarvore WHERE id = d.id) AS dentro
+                    FROM categorias d WHERE d.id = $2 AND d.deleted_at IS NULL FOR UPDATE OF d
+                    "#
+                )
+                .bind(id)
+                .bind(destino_id)
+                .fetch_optional(&mut *transaction)
+                .await?;
+                match destino {
+                    None => {
+                        return Ok(ResultadoExclusao::DestinoInvalido(
+                            format!("Categoria de destino com ID {} não encontrada.", destino_id)
+                        ));
+                    }
+                    Some(linha) if linha.try_get::<bool, _>("dentro")? => {
+                        return Ok(ResultadoExclusao::DestinoInvalido(
+                            "A categoria de destino não pode ser a própria categoria excluída nem uma das suas subcategorias.".to_string()
+                        ));
+                    }
+                    Some(_) => {}
+                }
+
+                let linhas = query(
+                    r#"
+                    WITH anterior AS (
+                        SELECT c.id, to_jsonb(c) AS antes,
+                               (SELECT COALESCE(MAX(posicao), 0) FROM categorias WHERE parent_id = $2 AND deleted_at IS NULL)
+                               + ROW_NUMBER() OVER (ORDER BY c.posicao, c.id) AS nova_posicao
+                        FROM categorias c WHERE c.parent_id = $1 AND c.deleted_at IS NULL
+                    )
+                    UPDATE categorias SET parent_id = $2, posicao = anterior.nova_posicao, versao = categorias.versao + 1
+                    FROM anterior WHERE categorias.id = anterior.id
+                    RETURNING categorias.id, categorias.nome, $1::int AS parent_id, anterior.antes, to_jsonb(categorias) AS depois
+                    "#
+                )
+                .bind(id)
+                .bind(destino_id)
+                .fetch_all(&mut *transaction)
+                .await?;
+                auditar_linhas(&mut transaction, admin, "categoria", AcaoAuditoria::Atualizacao, &linhas, simular).await?;
+                plano.subcategorias = linhas.iter().map(SubcategoriaAfetada::from_row).collect::<Result<_, _>>()?;
+
+                let linhas = query(
+                    r#"
+                    WITH anterior AS (
+                        SELECT p.id, to_jsonb(p) AS antes FROM produtos p WHERE p.categoria_id = $1 AND p.deleted_at IS NULL FOR UPDATE
+                    )
+                    UPDATE produtos SET categoria_id = $2, versao = produtos.versao + 1
+                    FROM anterior WHERE produtos.id = anterior.id
+                    RETURNING produtos.id, produtos.nome, $1::int AS categoria_id, anterior.antes, to_jsonb(produtos) AS depois
+                    "#
+                )
+                .bind(id)
+                .bind(destino_id)
+                .fetch_all(&mut *transaction)
+                .await?;
+                auditar_linhas(&mut transaction, admin, "produto", AcaoAuditoria::Atualizacao, &linhas, simular).await?;
+                plano.produtos = linhas.iter().map(ProdutoAfetado::from_row).collect::<Result<_, _>>()?;
+            }
+            ModoExclusao::Cascata => {
+                // Os produtos saem primeiro, enquanto a subárvore ainda está ativa
+                let linhas = query(
+                    r#"
+                    WITH RECURSIVE subarvore AS (
+                        SELECT $1::int AS id
+                        UNION ALL
+                        SELECT c.id FROM categorias c JOIN subarvore s ON c.parent_id = s.id WHERE c.deleted_at IS NULL
+                    ),
+                    anterior AS (
+                        SELECT p.id, to_jsonb(p) AS antes FROM produtos p JOIN subarvore s ON p.categoria_id = s.id
+                        WHERE p.deleted_at IS NULL
+                    )
+                    UPDATE produtos SET deleted_at = NOW(), versao = produtos.versao + 1
+                    FROM anterior WHERE produtos.id = anterior.id
+                    RETURNING produtos.id, produtos.nome, produtos.categoria_id, anterior.antes, to_jsonb(produtos) AS depois
+                    "#
+                )
+                .bind(id)
+                .fetch_all(&mut *transaction)
+                .await?;
+                auditar_linhas(&mut transaction, admin, "produto", AcaoAuditoria::Exclusao, &linhas, simular).await?;
+                plano.produtos = linhas.iter().map(ProdutoAfetado::from_row).collect::<Result<_, _>>()?;
+
+                let linhas = query(
+                    r#"
+                    WITH RECURSIVE descendentes AS (
+                        SELECT id FROM categorias WHERE parent_id = $1 AND deleted_at IS NULL
+                        UNION ALL
+                        SELECT c.id FROM categorias c JOIN descendentes d ON c.parent_id = d.id WHERE c.deleted_at IS NULL
+                    ),
+                    anterior AS (
+                        SELECT c.id, to_jsonb(c) AS antes FROM categorias c JOIN descendentes d ON d.id = c.id
+                    )
+                    UPDATE categorias SET deleted_at = NOW(), versao = categorias.versao + 1
+                    FROM anterior WHERE categorias.id = anterior.id
+                    RETURNING categorias.id, categorias.nome, categorias.parent_id, anterior.antes, to_jsonb(categorias) AS depois
+                    "#
+                )
+                .bind(id)
+                .fetch_all(&mut *transaction)
+                .await?;
+                auditar_linhas(&mut transaction, admin, "categoria", AcaoAuditoria::Exclusao, &linhas, simular).await?;
+                plano.subcategorias = linhas.iter().map(SubcategoriaAfetada::from_row).collect::<Result<_, _>>()?;
+            }
+        }
+
+        let linhas = query(
+            r#"
+            WITH anterior AS (SELECT c.id, to_jsonb(c) AS antes FROM categorias c WHERE c.id = $1)
+            UPDATE categorias SET deleted_at = NOW(), versao = categorias.versao + 1
+            FROM anterior WHERE categorias.id = anterior.id
+            RETURNING categorias.id, anterior.antes, to_jsonb(categorias) AS depois
+            "#
+        )
+        .bind(id)
+        .fetch_all(&mut *transaction)
+        .await?;
+        auditar_linhas(&mut transaction, admin, "categoria", AcaoAuditoria::Exclusao, &linhas, simular).await?;
+
+        plano.subcategorias.sort_by_key(|subcategoria| subcategoria.id);
+        plano.produtos.sort_by_key(|produto| produto.id);
+
+        if simular {
+            transaction.rollback().await?;
+        } else {
+            transaction.commit().await?;
+        }
+        Ok(ResultadoExclusao::Concluida(plano))
+    }
+    .await;
 
     match result {
-        Ok(res) => {
-            if res.rows_affected() > 0 {
-                HttpResponse::Ok().json(GenericResponse::<()>{
-                    status: "success".to_string(),
-                    message: format!("Categoria com ID {} deletada com sucesso.", id),
-                    body: None,
-                })
+        Ok(ResultadoExclusao::Concluida(plano)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: if simular {
+                format!("Simulação da exclusão da {} com ID {}: nada foi alterado.", descricao.to_lowercase(), id)
             } else {
-                HttpResponse::NotFound().json(GenericResponse::<()>{
-                    status: "error".to_string(),
-                    message: format!("Categoria com ID {} não encontrada para exclusão.", id),
-                    body: None,
-                })
-            }
-        },
-        Err(e) => {
-            eprintln!("Erro ao deletar categoria com ID {}: {:?}", id, e);
-            // Adicionar tratamento para erro de chave estrangeira se houver categorias filhas ou produtos associados
-            let error_message = if e.to_string().contains("foreign key constraint") {
-                "Não é possível deletar a categoria: existem subcategorias ou produtos associados a ela.".to_string()
+                format!("{} com ID {} deletada com sucesso.", descricao, id)
+            },
+            body: Some(plano),
+        }),
+        Ok(ResultadoExclusao::Bloqueada(plano)) => HttpResponse::BadRequest().json(GenericResponse {
+            status: "error".to_string(),
+            message: format!(
+                "Não é possível deletar a {}: existem {} subcategoria(s) e {} produto(s) ativos associados. Use modo=mover ou modo=cascata.",
+                descricao.to_lowercase(), plano.subcategorias.len(), plano.produtos.len()
+            ),
+            body: Some(plano),
+        }),
+        Ok(ResultadoExclusao::DestinoInvalido(mensagem)) => HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: mensagem,
+            body: None,
+        }),
+        Ok(ResultadoExclusao::TipoIncorreto) => HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: if somente_sessao {
+                "Não é possível excluir uma categoria filha na rota de sessão. Use /categorias/{id} para isso.".to_string()
             } else {
-                "Erro ao deletar categoria.".to_string()
-            };
+                "Não é possível excluir uma sessão principal na rota de categorias. Use /sessoes/{id} para isso.".to_string()
+            },
+            body: None,
+        }),
+        Ok(ResultadoExclusao::NaoEncontrada) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("{} com ID {} não encontrada para exclusão.", descricao, id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao deletar {} com ID {}: {:?}", descricao.to_lowercase(), id, e);
             HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: error_message,
+                message: format!("Erro ao deletar {}.", descricao.to_lowercase()),
                 body: None,
             })
         }
     }
-}
\ No newline at end of file
+}
+
+/// Registra na auditoria as linhas devolvidas por um UPDATE com `id`, `antes` e `depois`.
+/// Na simulação nada é registrado, já que a transação será desfeita.
+async fn auditar_linhas(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    admin: &AdminUser,
+    entidade: &str,
+    acao: AcaoAuditoria,
+    linhas: &[PgRow],
+    simular: bool,
+) -> Result<(), sqlx::Error> {
+    if simular {
+        return Ok(());
+    }
+    for linha in linhas {
+        let id: i32 = linha.try_get("id")?;
+        let antes: Option<serde_json::Value> = linha.try_get("antes")?;
+        let depois: Option<serde_json::Value> = linha.try_get("depois")?;
+        registrar_auditoria(transaction, &admin.0, entidade, id, acao, antes, depois).await?;
+    }
+    Ok(())
+}
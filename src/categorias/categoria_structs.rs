@@ -2,20 +2,205 @@
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use chrono::{DateTime, Utc};
 
-/// Estrutura para receber dados de uma nova categoria na requisição POST/PUT
+use crate::shared::shared_structs::campo_anulavel;
+
+/// Tamanhos máximos dos metadados para buscadores, acima dos quais eles são cortados nos resultados.
+const TAMANHO_MAXIMO_SEO_TITULO: usize = 70;
+const TAMANHO_MAXIMO_SEO_DESCRICAO: usize = 160;
+
+/// Estrutura para receber dados de uma nova categoria na requisição POST/PUT.
+/// No PUT, os campos de apresentação ausentes são apagados e `visivel` volta a `true`.
 #[derive(Deserialize)]
 pub struct NovaCategoria {
     pub nome: String,
     pub parent_id: Option<i32>,
+    pub visivel: Option<bool>,          // Padrão: visível no menu
+    pub descricao: Option<String>,
+    pub icone_url: Option<String>,      // Ícone do menu
+    pub banner_url: Option<String>,     // Imagem do topo da página da categoria
+    pub seo_titulo: Option<String>,     // Título para buscadores (até 70 caracteres; padrão: o nome)
+    pub seo_descricao: Option<String>,  // Descrição para buscadores (até 160 caracteres)
+}
+
+/// Estrutura para receber uma atualização parcial de categoria na requisição PATCH.
+/// Campos ausentes permanecem como estão; nos campos de apresentação, `null` apaga o valor.
+#[derive(Deserialize)]
+pub struct AtualizacaoParcialCategoria {
+    pub nome: Option<String>,
+    pub parent_id: Option<i32>,
+    pub visivel: Option<bool>,
+    #[serde(default, deserialize_with = "campo_anulavel")]
+    pub descricao: Option<Option<String>>,
+    #[serde(default, deserialize_with = "campo_anulavel")]
+    pub icone_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "campo_anulavel")]
+    pub banner_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "campo_anulavel")]
+    pub seo_titulo: Option<Option<String>>,
+    #[serde(default, deserialize_with = "campo_anulavel")]
+    pub seo_descricao: Option<Option<String>>,
+}
+
+impl AtualizacaoParcialCategoria {
+    /// Indica se a requisição não informou nenhum campo para alterar.
+    pub fn is_vazia(&self) -> bool {
+        self.nome.is_none()
+            && self.parent_id.is_none()
+            && self.visivel.is_none()
+            && self.descricao.is_none()
+            && self.icone_url.is_none()
+            && self.banner_url.is_none()
+            && self.seo_titulo.is_none()
+            && self.seo_descricao.is_none()
+    }
+
+    /// Confere as imagens e os metadados informados, devolvendo o primeiro problema encontrado.
+    pub fn problema_apresentacao(&self) -> Option<String> {
+        problema_apresentacao(
+            self.icone_url.as_ref().and_then(Option::as_ref),
+            self.banner_url.as_ref().and_then(Option::as_ref),
+            self.seo_titulo.as_ref().and_then(Option::as_ref),
+            self.seo_descricao.as_ref().and_then(Option::as_ref),
+        )
+    }
+}
+
+impl NovaCategoria {
+    /// Confere as imagens e os metadados informados, devolvendo o primeiro problema encontrado.
+    pub fn problema_apresentacao(&self) -> Option<String> {
+        problema_apresentacao(
+            self.icone_url.as_ref(),
+            self.banner_url.as_ref(),
+            self.seo_titulo.as_ref(),
+            self.seo_descricao.as_ref(),
+        )
+    }
+}
+
+/// Imagens devem ser endereços http(s) ou caminhos da própria loja; os metadados
+/// para buscadores respeitam os tamanhos exibidos nos resultados.
+fn problema_apresentacao(
+    icone_url: Option<&String>,
+    banner_url: Option<&String>,
+    seo_titulo: Option<&String>,
+    seo_descricao: Option<&String>,
+) -> Option<String> {
+    for (campo, url) in [("icone_url", icone_url), ("banner_url", banner_url)] {
+        if let Some(url) = url {
+            if !(url.starts_with("https://") || url.starts_with("http://") || url.starts_with('/')) {
+                return Some(format!("'{}' deve ser um endereço http(s) ou um caminho começando por '/'.", campo));
+            }
+        }
+    }
+    if seo_titulo.is_some_and(|titulo| titulo.chars().count() > TAMANHO_MAXIMO_SEO_TITULO) {
+        return Some(format!("'seo_titulo' deve ter até {} caracteres.", TAMANHO_MAXIMO_SEO_TITULO));
+    }
+    if seo_descricao.is_some_and(|descricao| descricao.chars().count() > TAMANHO_MAXIMO_SEO_DESCRICAO) {
+        return Some(format!("'seo_descricao' deve ter até {} caracteres.", TAMANHO_MAXIMO_SEO_DESCRICAO));
+    }
+    None
+}
+
+impl From<NovaCategoria> for AtualizacaoParcialCategoria {
+    /// Uma atualização completa (PUT) é uma atualização parcial com todos os campos informados.
+    fn from(categoria: NovaCategoria) -> Self {
+        AtualizacaoParcialCategoria {
+            nome: Some(categoria.nome),
+            parent_id: categoria.parent_id,
+            visivel: Some(categoria.visivel.unwrap_or(true)),
+            descricao: Some(categoria.descricao),
+            icone_url: Some(categoria.icone_url),
+            banner_url: Some(categoria.banner_url),
+            seo_titulo: Some(categoria.seo_titulo),
+            seo_descricao: Some(categoria.seo_descricao),
+        }
+    }
 }
 
 /// Estrutura que representa uma categoria no banco de dados
 #[derive(Serialize, FromRow)]
 pub struct Categoria {
+    pub id: i32,
+    pub nome: String,
+    pub slug: String, // Endereço amigável, gerado a partir do nome
+    pub parent_id: Option<i32>,
+    pub posicao: i32,  // Ordem de exibição entre as categorias com o mesmo pai
+    pub visivel: bool, // Categorias ocultas saem do menu, mas continuam acessíveis pelo ID e pelo slug
+    pub descricao: Option<String>,
+    pub icone_url: Option<String>,
+    pub banner_url: Option<String>,
+    pub seo_titulo: Option<String>,
+    pub seo_descricao: Option<String>,
+    pub versao: i32, // Versão para controle de concorrência (também enviada como ETag)
+}
+
+/// Filtros aceitos na query string das listagens de sessões e categorias.
+#[derive(Deserialize)]
+pub struct FiltroCategorias {
+    pub incluir_ocultas: Option<bool>, // Só vale para administradores
+}
+
+/// Divergência entre a lista enviada na reordenação e as categorias com o mesmo pai.
+/// A lista precisa conter cada uma delas exatamente uma vez.
+#[derive(Serialize)]
+pub struct DivergenciaOrdem {
+    pub faltando: Vec<i32>,      // Categorias do grupo que não vieram na lista
+    pub desconhecidos: Vec<i32>, // IDs da lista que não pertencem ao grupo
+    pub repetidos: Vec<i32>,
+}
+
+/// Parâmetros da query string na exclusão de sessões e categorias.
+#[derive(Deserialize)]
+pub struct OpcoesExclusaoCategoria {
+    pub modo: Option<ModoExclusao>, // Padrão: recusar
+    pub destino_id: Option<i32>,    // Obrigatório no modo `mover`
+    pub simular: Option<bool>,      // Devolve o plano da exclusão sem gravar nada
+}
+
+/// O que fazer com as subcategorias e os produtos ativos da categoria excluída.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModoExclusao {
+    Recusar, // Só exclui a categoria se ela não tiver dependentes
+    Mover,   // Passa as subcategorias e os produtos para a categoria `destino_id`
+    Cascata, // Exclui logicamente toda a subárvore e os produtos dela
+}
+
+/// Subcategoria afetada pela exclusão, com o pai que tinha antes dela.
+#[derive(Serialize, FromRow)]
+pub struct SubcategoriaAfetada {
+    pub id: i32,
+    pub nome: String,
+    pub parent_id: Option<i32>,
+}
+
+/// Produto afetado pela exclusão, com a categoria que tinha antes dela.
+#[derive(Serialize, FromRow)]
+pub struct ProdutoAfetado {
+    pub id: i32,
+    pub nome: String,
+    pub categoria_id: i32,
+}
+
+/// Plano da exclusão: o que foi (ou seria, na simulação) movido ou excluído junto com a categoria.
+/// No modo `recusar`, lista o que impede a exclusão.
+#[derive(Serialize)]
+pub struct PlanoExclusao {
+    pub modo: ModoExclusao,
+    pub destino_id: Option<i32>,
+    pub subcategorias: Vec<SubcategoriaAfetada>,
+    pub produtos: Vec<ProdutoAfetado>,
+}
+
+/// Estrutura para a visão administrativa de categorias excluídas (soft delete).
+#[derive(Serialize, FromRow)]
+pub struct CategoriaExcluida {
     pub id: i32,
     pub nome: String,
     pub parent_id: Option<i32>,
+    pub deleted_at: DateTime<Utc>,
 }
 
 // Re-exporta GenericResponse para que possa ser facilmente usada dentro do módulo categorias
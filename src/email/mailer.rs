@@ -0,0 +1,66 @@
+// src/email/mailer.rs
+
+use futures::future::{self, BoxFuture};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Mensagem de e-mail a ser enviada pela aplicação.
+pub struct Email {
+    pub para: String,
+    pub assunto: String,
+    pub corpo: String,
+}
+
+/// Abstração do envio de e-mails.
+/// Permite trocar o provedor (SMTP, serviço transacional etc.) sem alterar as rotas;
+/// em desenvolvimento são usadas as implementações locais deste módulo.
+pub trait Mailer: Send + Sync {
+    fn enviar(&self, email: Email) -> BoxFuture<'_, Result<(), String>>;
+}
+
+/// Mailer de desenvolvimento que apenas escreve as mensagens no log da aplicação.
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn enviar(&self, email: Email) -> BoxFuture<'_, Result<(), String>> {
+        println!(
+            "[e-mail] Para: {}\n[e-mail] Assunto: {}\n{}\n[e-mail] ---",
+            email.para, email.assunto, email.corpo
+        );
+        Box::pin(future::ready(Ok(())))
+    }
+}
+
+/// Mailer de desenvolvimento que acrescenta as mensagens a um arquivo local,
+/// útil para consultar links e tokens enviados durante os testes manuais.
+pub struct ArquivoMailer {
+    caminho: String,
+    trava: Mutex<()>, // Evita que mensagens concorrentes se intercalem no arquivo
+}
+
+impl ArquivoMailer {
+    pub fn new(caminho: impl Into<String>) -> Self {
+        ArquivoMailer { caminho: caminho.into(), trava: Mutex::new(()) }
+    }
+}
+
+impl Mailer for ArquivoMailer {
+    fn enviar(&self, email: Email) -> BoxFuture<'_, Result<(), String>> {
+        let resultado = (|| {
+            let _guard = self.trava.lock().map_err(|e| e.to_string())?;
+            let mut arquivo = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.caminho)
+                .map_err(|e| e.to_string())?;
+            writeln!(
+                arquivo,
+                "Para: {}\nAssunto: {}\n\n{}\n---",
+                email.para, email.assunto, email.corpo
+            )
+            .map_err(|e| e.to_string())
+        })();
+        Box::pin(future::ready(resultado))
+    }
+}
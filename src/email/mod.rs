@@ -0,0 +1,4 @@
+// src/email/mod.rs
+
+// Declara o submódulo com a abstração de envio de e-mails e suas implementações locais
+pub mod mailer;
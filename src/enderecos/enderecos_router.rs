@@ -0,0 +1,366 @@
+// src/enderecos/enderecos_router.rs
+
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use sqlx::{query, query_as};
+
+// Importa as structs de endereços
+use super::enderecos_structs::{Endereco, NovoEndereco};
+// Importa o extrator de autenticação
+use crate::usuarios::auth_middleware::AuthenticatedUser;
+// Importa as validações de CEP e UF
+use crate::shared::validacoes::{cep_valido, somente_digitos, uf_valida};
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+
+/// Valida os campos obrigatórios e o formato de CEP e UF de um endereço.
+fn validar_endereco(item: &NovoEndereco) -> Result<(), HttpResponse> {
+    let erro = |mensagem: &str| HttpResponse::BadRequest().json(GenericResponse::<()>{
+        status: "error".to_string(),
+        message: mensagem.to_string(),
+        body: None,
+    });
+
+    if !cep_valido(&item.cep) {
+        return Err(erro("CEP inválido. Use o formato 12345-678."));
+    }
+    if !uf_valida(&item.uf) {
+        return Err(erro("UF inválida. Informe a sigla do estado, ex.: SP."));
+    }
+    if [&item.logradouro, &item.numero, &item.bairro, &item.cidade].iter().any(|campo| campo.trim().is_empty()) {
+        return Err(erro("Logradouro, número, bairro e cidade são obrigatórios."));
+    }
+    Ok(())
+}
+
+/// Complemento vazio é gravado como ausente.
+fn complemento_normalizado(item: &NovoEndereco) -> Option<String> {
+    item.complemento.as_deref().map(str::trim).filter(|c| !c.is_empty()).map(str::to_string)
+}
+
+/// Rota para listar os endereços do usuário autenticado, com o padrão primeiro.
+#[get("/usuarios/me/enderecos")]
+pub async fn buscar_meus_enderecos(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+) -> HttpResponse {
+    let result = query_as::<_, Endereco>(
+        "SELECT id, cep, logradouro, numero, complemento, bairro, cidade, uf, padrao FROM enderecos WHERE usuario_id = $1 ORDER BY padrao DESC, id"
+    )
+    .bind(auth_user.user_id)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(enderecos) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Endereços encontrados.".to_string(),
+            body: Some(enderecos),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar endereços do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar endereços.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para buscar um endereço do usuário autenticado pelo ID.
+#[get("/usuarios/me/enderecos/{id}")]
+pub async fn buscar_meu_endereco_por_id(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let result = query_as::<_, Endereco>(
+        "SELECT id, cep, logradouro, numero, complemento, bairro, cidade, uf, padrao FROM enderecos WHERE id = $1 AND usuario_id = $2"
+    )
+    .bind(id)
+    .bind(auth_user.user_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(Some(endereco)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Endereço encontrado.".to_string(),
+            body: Some(endereco),
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Endereço com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar endereço {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar endereço.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para cadastrar um endereço.
+/// O primeiro endereço do cliente se torna o padrão automaticamente.
+#[post("/usuarios/me/enderecos")]
+pub async fn cadastrar_endereco(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    item: web::Json<NovoEndereco>,
+) -> HttpResponse {
+    if let Err(resposta) = validar_endereco(&item) {
+        return resposta;
+    }
+
+    let result: Result<Endereco, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let possui_enderecos = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM enderecos WHERE usuario_id = $1)"
+        )
+        .bind(auth_user.user_id)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let padrao = item.padrao || !possui_enderecos;
+        if padrao {
+            query("UPDATE enderecos SET padrao = FALSE WHERE usuario_id = $1 AND padrao")
+                .bind(auth_user.user_id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        let endereco = query_as::<_, Endereco>(
+            r#"
+            INSERT INTO enderecos (usuario_id, cep, logradouro, numero, complemento, bairro, cidade, uf, padrao)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, cep, logradouro, numero, complemento, bairro, cidade, uf, padrao
+            "#
+        )
+        .bind(auth_user.user_id)
+        .bind(somente_digitos(&item.cep))
+        .bind(item.logradouro.trim())
+        .bind(item.numero.trim())
+        .bind(complemento_normalizado(&item))
+        .bind(item.bairro.trim())
+        .bind(item.cidade.trim())
+        .bind(item.uf.trim().to_uppercase())
+        .bind(padrao)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+        Ok(endereco)
+    }
+    .await;
+
+    match result {
+        Ok(endereco) => HttpResponse::Created().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Endereço cadastrado com sucesso.".to_string(),
+            body: Some(endereco),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao cadastrar endereço do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao cadastrar endereço.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para atualizar um endereço.
+/// Enviar `padrao: true` torna o endereço padrão; enviar `false` não remove o padrão atual.
+#[put("/usuarios/me/enderecos/{id}")]
+pub async fn atualizar_endereco(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+    item: web::Json<NovoEndereco>,
+) -> HttpResponse {
+    let id = path.into_inner();
+    if let Err(resposta) = validar_endereco(&item) {
+        return resposta;
+    }
+
+    let result: Result<Option<Endereco>, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        if item.padrao {
+            query("UPDATE enderecos SET padrao = FALSE WHERE usuario_id = $1 AND padrao AND id <> $2")
+                .bind(auth_user.user_id)
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        let endereco = query_as::<_, Endereco>(
+            r#"
+            UPDATE enderecos SET cep = $1, logradouro = $2, numero = $3, complemento = $4,
+                bairro = $5, cidade = $6, uf = $7, padrao = (padrao OR $8)
+            WHERE id = $9 AND usuario_id = $10
+            RETURNING id, cep, logradouro, numero, complemento, bairro, cidade, uf, padrao
+            "#
+        )
+        .bind(somente_digitos(&item.cep))
+        .bind(item.logradouro.trim())
+        .bind(item.numero.trim())
+        .bind(complemento_normalizado(&item))
+        .bind(item.bairro.trim())
+        .bind(item.cidade.trim())
+        .bind(item.uf.trim().to_uppercase())
+        .bind(item.padrao)
+        .bind(id)
+        .bind(auth_user.user_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        // Sem endereço atualizado, o rollback desfaz a troca de padrão
+        if endereco.is_some() {
+            transaction.commit().await?;
+        }
+        Ok(endereco)
+    }
+    .await;
+
+    match result {
+        Ok(Some(endereco)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Endereço atualizado com sucesso.".to_string(),
+            body: Some(endereco),
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Endereço com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao atualizar endereço {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao atualizar endereço.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para definir o endereço padrão, usado na compra quando nenhum endereço é informado.
+#[put("/usuarios/me/enderecos/{id}/padrao")]
+pub async fn definir_endereco_padrao(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let id = path.into_inner();
+
+    let result: Result<bool, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+        query("UPDATE enderecos SET padrao = FALSE WHERE usuario_id = $1 AND padrao AND id <> $2")
+            .bind(auth_user.user_id)
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+        let res = query("UPDATE enderecos SET padrao = TRUE WHERE id = $1 AND usuario_id = $2")
+            .bind(id)
+            .bind(auth_user.user_id)
+            .execute(&mut *transaction)
+            .await?;
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+        transaction.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Endereço {} definido como padrão.", id),
+            body: None,
+        }),
+        Ok(false) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Endereço com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao definir endereço padrão {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao definir endereço padrão.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para excluir um endereço.
+/// Os pedidos já feitos guardam uma cópia do endereço e não são afetados.
+/// Se o endereço excluído era o padrão, o mais recente dos restantes assume o seu lugar.
+#[delete("/usuarios/me/enderecos/{id}")]
+pub async fn deletar_endereco(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let id = path.into_inner();
+
+    let result: Result<bool, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+        let era_padrao = sqlx::query_scalar::<_, bool>(
+            "DELETE FROM enderecos WHERE id = $1 AND usuario_id = $2 RETURNING padrao"
+        )
+        .bind(id)
+        .bind(auth_user.user_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        match era_padrao {
+            None => return Ok(false),
+            Some(true) => {
+                query(
+                    "UPDATE enderecos SET padrao = TRUE WHERE id = (SELECT id FROM enderecos WHERE usuario_id = $1 ORDER BY criado_em DESC, id DESC LIMIT 1)"
+                )
+                .bind(auth_user.user_id)
+                .execute(&mut *transaction)
+                .await?;
+            },
+            Some(false) => {},
+        }
+        transaction.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Endereço excluído com sucesso.".to_string(),
+            body: None,
+        }),
+        Ok(false) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Endereço com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao excluir endereço {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao excluir endereço.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
@@ -0,0 +1,33 @@
+// src/enderecos/enderecos_structs.rs
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Estrutura que representa um endereço salvo pelo cliente.
+#[derive(Debug, FromRow, Serialize)]
+pub struct Endereco {
+    pub id: i32,
+    pub cep: String,
+    pub logradouro: String,
+    pub numero: String,
+    pub complemento: Option<String>,
+    pub bairro: String,
+    pub cidade: String,
+    pub uf: String,
+    pub padrao: bool,
+}
+
+/// Estrutura para receber um endereço nas requisições POST e PUT.
+/// Se `padrao` for verdadeiro, o endereço passa a ser o padrão e o anterior deixa de ser.
+#[derive(Deserialize)]
+pub struct NovoEndereco {
+    pub cep: String, // "12345-678" ou "12345678"
+    pub logradouro: String,
+    pub numero: String,
+    pub complemento: Option<String>,
+    pub bairro: String,
+    pub cidade: String,
+    pub uf: String, // Sigla da unidade federativa, ex.: "SP"
+    #[serde(default)]
+    pub padrao: bool,
+}
@@ -0,0 +1,6 @@
+// src/enderecos/mod.rs
+
+// Declara o submódulo que contém as definições das structs de endereços
+pub mod enderecos_structs;
+// Declara o submódulo que contém as funções de rota do catálogo de endereços
+pub mod enderecos_router;
@@ -0,0 +1,294 @@
+// src/estoque/estoque_router.rs
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{query, query_as, query_scalar};
+
+// Importa as structs de estoque
+use super::estoque_structs::{EntradaEstoque, InscricaoEstoque};
+// Importa o enfileiramento dos avisos e a validade das inscrições
+use super::estoque_service::{enfileirar_avisos_estoque, VALIDADE_INSCRICAO_DIAS};
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+// Importa os extratores de autenticação e autorização
+use crate::usuarios::auth_middleware::{AuthenticatedUser, AdminUser};
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::executar_com_auditoria;
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
+
+/// Rota administrativa para registrar uma entrada de estoque (recebimento de mercadoria).
+/// A quantidade é somada ao estoque atual, sem exigir `If-Match`, e a alteração é auditada.
+/// Se o produto estava esgotado, os clientes inscritos no "avise-me" são enfileirados para aviso.
+#[post("/produtos/{id}/estoque/entradas")]
+pub async fn registrar_entrada_estoque(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<EntradaEstoque>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    if item.quantidade <= 0 {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "A quantidade recebida deve ser maior que zero.".to_string(),
+            body: None,
+        });
+    }
+
+    let comando = query(
+        r#"
+        WITH anterior AS (
+            SELECT p.id, p.estoque, to_jsonb(p) AS antes FROM produtos p WHERE p.id = $1 AND p.deleted_at IS NULL FOR UPDATE
+        ),
+        atualizado AS (
+            UPDATE produtos SET estoque = produtos.estoque + $2, versao = versao + 1
+            FROM anterior
+            WHERE produtos.id = anterior.id
+            RETURNING produtos.id, produtos.estoque, anterior.estoque AS estoque_anterior, anterior.antes, to_jsonb(produtos) AS depois
+        ),
+        avisos AS (
+            UPDATE inscricoes_estoque i SET enfileirada_em = NOW()
+            FROM atualizado a
+            WHERE i.produto_id = a.id AND a.estoque_anterior = 0 AND a.estoque > 0
+              AND i.enfileirada_em IS NULL AND i.expira_em > NOW()
+        )
+        SELECT id, antes, depois FROM atualizado
+        "#
+    )
+    .bind(id)
+    .bind(item.quantidade);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "produto", AcaoAuditoria::Atualizacao, comando).await {
+        Ok(Some(_)) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Entrada de {} unidade(s) registrada no produto {}.", item.quantidade, id),
+            body: None,
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Produto com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao registrar entrada de estoque do produto {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao registrar entrada de estoque.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para o cliente pedir um aviso quando um produto esgotado voltar ao estoque.
+/// Repetir a inscrição não a duplica, só renova a validade.
+#[post("/produtos/{id}/avise-me")]
+pub async fn inscrever_aviso_estoque(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let produto_id = path.into_inner();
+
+    let estoque = query_scalar::<_, i32>("SELECT estoque FROM produtos WHERE id = $1 AND deleted_at IS NULL")
+        .bind(produto_id)
+        .fetch_optional(&data.db_pool)
+        .await;
+
+    match estoque {
+        Ok(Some(estoque)) if estoque <= 0 => {},
+        Ok(Some(_)) => {
+            return HttpResponse::Conflict().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "O produto está disponível em estoque.".to_string(),
+                body: None,
+            });
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Produto com ID {} não encontrado.", produto_id),
+                body: None,
+            });
+        }
+        Err(e) => {
+            eprintln!("Erro ao verificar estoque do produto {}: {:?}", produto_id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao registrar aviso de estoque.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    let result: Result<DateTime<Utc>, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let expira_em = query_scalar::<_, DateTime<Utc>>(
+            r#"
+            INSERT INTO inscricoes_estoque (usuario_id, produto_id, expira_em)
+            VALUES ($1, $2, NOW() + make_interval(days => $3))
+            ON CONFLICT (usuario_id, produto_id) WHERE notificado_em IS NULL
+            DO UPDATE SET expira_em = EXCLUDED.expira_em
+            RETURNING expira_em
+            "#
+        )
+        .bind(auth_user.user_id)
+        .bind(produto_id)
+        .bind(VALIDADE_INSCRICAO_DIAS)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        // O estoque pode ter voltado depois da verificação acima
+        enfileirar_avisos_estoque(&mut transaction, &[produto_id]).await?;
+
+        transaction.commit().await?;
+        Ok(expira_em)
+    }
+    .await;
+
+    match result {
+        Ok(expira_em) => HttpResponse::Created().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Você será avisado quando o produto voltar ao estoque.".to_string(),
+            body: Some(serde_json::json!({ "produto_id": produto_id, "expira_em": expira_em })),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao registrar aviso de estoque do usuário {} para o produto {}: {:?}", auth_user.user_id, produto_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao registrar aviso de estoque.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para o cliente cancelar o aviso de volta ao estoque de um produto.
+#[delete("/produtos/{id}/avise-me")]
+pub async fn cancelar_aviso_estoque(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let produto_id = path.into_inner();
+    let result = query("DELETE FROM inscricoes_estoque WHERE usuario_id = $1 AND produto_id = $2 AND notificado_em IS NULL")
+        .bind(auth_user.user_id)
+        .bind(produto_id)
+        .execute(&data.db_pool)
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Aviso de estoque cancelado.".to_string(),
+            body: None,
+        }),
+        Ok(_) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Nenhum aviso de estoque ativo para o produto {}.", produto_id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao cancelar aviso de estoque do usuário {} para o produto {}: {:?}", auth_user.user_id, produto_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao cancelar aviso de estoque.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para listar os avisos de estoque ativos do usuário autenticado.
+#[get("/usuarios/me/avise-me")]
+pub async fn buscar_meus_avisos_estoque(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+) -> HttpResponse {
+    let result = query_as::<_, InscricaoEstoque>(
+        r#"
+        SELECT i.produto_id, p.nome AS produto_nome, i.criado_em, i.expira_em
+        FROM inscricoes_estoque i
+        JOIN produtos p ON p.id = i.produto_id
+        WHERE i.usuario_id = $1 AND i.notificado_em IS NULL AND i.expira_em > NOW()
+        ORDER BY i.criado_em DESC
+        "#
+    )
+    .bind(auth_user.user_id)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(inscricoes) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Avisos de estoque listados com sucesso!".to_string(),
+            body: Some(inscricoes),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar avisos de estoque do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar avisos de estoque.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    use crate::shared::testes::{autorizacao_de_admin, criar_produto, criar_usuario, estado_de_teste, gateway_de_teste};
+
+    /// A entrada só enfileira os avisos quando o produto estava esgotado; uma reposição de
+    /// produto que ainda tinha estoque não avisa inscritos de novo.
+    #[actix_web::test]
+    async fn entrada_so_avisa_quando_o_estoque_sai_de_zero() {
+        let data = match estado_de_teste(gateway_de_teste()).await {
+            Some(data) => data,
+            None => return,
+        };
+        let app = test::init_service(App::new().app_data(data.clone()).service(registrar_entrada_estoque)).await;
+        let admin = autorizacao_de_admin(&data).await;
+        let produto_id = criar_produto(&data, 30, 0).await;
+        let usuario_id = criar_usuario(&data).await;
+        query("INSERT INTO inscricoes_estoque (usuario_id, produto_id, expira_em) VALUES ($1, $2, NOW() + INTERVAL '1 day')")
+            .bind(usuario_id)
+            .bind(produto_id)
+            .execute(&data.db_pool)
+            .await
+            .unwrap();
+        let entrada = || {
+            test::TestRequest::post()
+                .uri(&format!("/produtos/{}/estoque/entradas", produto_id))
+                .insert_header(admin.clone())
+                .set_json(serde_json::json!({ "quantidade": 5 }))
+                .to_request()
+        };
+        let enfileirada = || async {
+            query_scalar::<_, bool>("SELECT enfileirada_em IS NOT NULL FROM inscricoes_estoque WHERE produto_id = $1")
+                .bind(produto_id)
+                .fetch_one(&data.db_pool)
+                .await
+                .unwrap()
+        };
+
+        // De 0 para 5: o inscrito entra na fila
+        assert_eq!(test::call_service(&app, entrada()).await.status(), 200);
+        assert!(enfileirada().await);
+
+        // De 5 para 10: nada é enfileirado
+        query("UPDATE inscricoes_estoque SET enfileirada_em = NULL WHERE produto_id = $1")
+            .bind(produto_id)
+            .execute(&data.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(test::call_service(&app, entrada()).await.status(), 200);
+        assert!(!enfileirada().await);
+    }
+}
@@ -0,0 +1,122 @@
+// src/estoque/estoque_service.rs
+
+use sqlx::{query, query_as, PgConnection, Pool, Postgres};
+use std::sync::Arc;
+
+use super::estoque_structs::AvisoEstoque;
+use super::notificador::NotificadorEstoque;
+
+/// Por quantos dias uma inscrição "avise-me" fica ativa (renovada a cada nova inscrição).
+pub const VALIDADE_INSCRICAO_DIAS: i32 = 90;
+
+/// Falhas de envio toleradas antes de desistir de um aviso.
+const MAXIMO_TENTATIVAS: i32 = 5;
+
+/// Avisos enviados por rodada.
+const LOTE_ENVIO: i64 = 50;
+
+/// Intervalo entre as rodadas de envio dos avisos.
+const INTERVALO_ENVIO_SEGUNDOS: u64 = 60;
+
+/// Enfileira os avisos das inscrições ativas dos produtos indicados que estão com estoque.
+/// Como só é possível se inscrever em produto esgotado, estoque positivo significa que ele voltou.
+pub async fn enfileirar_avisos_estoque(conn: &mut PgConnection, produto_ids: &[i32]) -> Result<u64, sqlx::Error> {
+    let res = query(
+        r#"
+        UPDATE inscricoes_estoque i SET enfileirada_em = NOW()
+        FROM produtos p
+        WHERE p.id = i.produto_id AND i.produto_id = ANY($1)
+          AND p.estoque > 0 AND p.deleted_at IS NULL
+          AND i.enfileirada_em IS NULL AND i.expira_em > NOW()
+        "#
+    )
+    .bind(produto_ids)
+    .execute(conn)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Envia uma rodada de avisos enfileirados e retorna quantos foram entregues.
+/// Avisos de produtos que esgotaram de novo ficam na fila até o estoque voltar.
+/// `SKIP LOCKED` permite mais de uma instância da aplicação enviando ao mesmo tempo.
+pub async fn enviar_avisos_estoque(
+    pool: &Pool<Postgres>,
+    notificador: &dyn NotificadorEstoque,
+) -> Result<usize, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let avisos = query_as::<_, AvisoEstoque>(
+        r#"
+        SELECT i.id AS inscricao_id, u.email, split_part(u.nome, ' ', 1) AS nome_cliente,
+               p.id AS produto_id, p.nome AS produto_nome
+        FROM inscricoes_estoque i
+        JOIN usuarios u ON u.id = i.usuario_id
+        JOIN produtos p ON p.id = i.produto_id
+        WHERE i.enfileirada_em IS NOT NULL AND i.notificado_em IS NULL AND i.tentativas < $1
+          AND p.estoque > 0 AND p.deleted_at IS NULL
+        ORDER BY i.enfileirada_em, i.id
+        LIMIT $2
+        FOR UPDATE OF i SKIP LOCKED
+        "#
+    )
+    .bind(MAXIMO_TENTATIVAS)
+    .bind(LOTE_ENVIO)
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    let mut enviados = 0;
+    for aviso in &avisos {
+        match notificador.notificar(aviso).await {
+            Ok(()) => {
+                query("UPDATE inscricoes_estoque SET notificado_em = NOW() WHERE id = $1")
+                    .bind(aviso.inscricao_id)
+                    .execute(&mut *transaction)
+                    .await?;
+                enviados += 1;
+            }
+            Err(e) => {
+                eprintln!("Erro ao enviar aviso de estoque da inscrição {}: {}", aviso.inscricao_id, e);
+                query("UPDATE inscricoes_estoque SET tentativas = tentativas + 1 WHERE id = $1")
+                    .bind(aviso.inscricao_id)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+        }
+    }
+
+    transaction.commit().await?;
+    Ok(enviados)
+}
+
+/// Remove as inscrições que expiraram sem aviso enviado e as já notificadas há mais que a validade.
+async fn remover_inscricoes_vencidas(pool: &Pool<Postgres>) -> Result<u64, sqlx::Error> {
+    let res = query(
+        r#"
+        DELETE FROM inscricoes_estoque
+        WHERE (notificado_em IS NULL AND expira_em < NOW())
+           OR notificado_em < NOW() - make_interval(days => $1)
+        "#
+    )
+    .bind(VALIDADE_INSCRICAO_DIAS)
+    .execute(pool)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Agenda o envio periódico dos avisos e a limpeza das inscrições vencidas enquanto a aplicação estiver no ar.
+pub fn iniciar_envio_avisos_estoque(pool: Pool<Postgres>, notificador: Arc<dyn NotificadorEstoque>) {
+    actix_web::rt::spawn(async move {
+        let mut intervalo = actix_web::rt::time::interval(std::time::Duration::from_secs(INTERVALO_ENVIO_SEGUNDOS));
+        loop {
+            intervalo.tick().await;
+            match enviar_avisos_estoque(&pool, notificador.as_ref()).await {
+                Ok(0) => {},
+                Ok(quantidade) => println!("{} aviso(s) de volta ao estoque enviado(s).", quantidade),
+                Err(e) => eprintln!("Erro ao enviar avisos de estoque: {:?}", e),
+            }
+            if let Err(e) = remover_inscricoes_vencidas(&pool).await {
+                eprintln!("Erro ao remover inscrições de estoque vencidas: {:?}", e);
+            }
+        }
+    });
+}
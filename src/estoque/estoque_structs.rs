@@ -0,0 +1,30 @@
+// src/estoque/estoque_structs.rs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Estrutura para receber uma entrada de estoque (recebimento de mercadoria) na requisição POST.
+#[derive(Deserialize)]
+pub struct EntradaEstoque {
+    pub quantidade: i32, // Unidades recebidas, somadas ao estoque atual
+}
+
+/// Inscrição "avise-me" ativa, na visão do cliente.
+#[derive(Serialize, FromRow)]
+pub struct InscricaoEstoque {
+    pub produto_id: i32,
+    pub produto_nome: String,
+    pub criado_em: DateTime<Utc>,
+    pub expira_em: DateTime<Utc>,
+}
+
+/// Aviso de que um produto voltou ao estoque, entregue ao `NotificadorEstoque`.
+#[derive(FromRow)]
+pub struct AvisoEstoque {
+    pub inscricao_id: i32,
+    pub email: String,
+    pub nome_cliente: String,
+    pub produto_id: i32,
+    pub produto_nome: String,
+}
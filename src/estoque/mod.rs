@@ -0,0 +1,10 @@
+// src/estoque/mod.rs
+
+// Declara o submódulo que contém as definições das structs de estoque e avisos
+pub mod estoque_structs;
+// Declara o submódulo com a abstração do envio de avisos de estoque e a implementação por e-mail
+pub mod notificador;
+// Declara o submódulo com o enfileiramento e o envio periódico dos avisos
+pub mod estoque_service;
+// Declara o submódulo que contém as funções de rota de entrada de estoque e "avise-me"
+pub mod estoque_router;
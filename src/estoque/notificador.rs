@@ -0,0 +1,40 @@
+// src/estoque/notificador.rs
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+use super::estoque_structs::AvisoEstoque;
+use crate::email::mailer::{Email, Mailer};
+
+/// Abstração do envio dos avisos de volta ao estoque.
+/// Permite trocar o canal (e-mail, push, WhatsApp etc.) sem alterar o enfileiramento.
+pub trait NotificadorEstoque: Send + Sync {
+    fn notificar<'a>(&'a self, aviso: &'a AvisoEstoque) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// Notificador que envia os avisos por e-mail usando o `Mailer` da aplicação.
+pub struct EmailNotificadorEstoque {
+    mailer: Arc<dyn Mailer>,
+    url_publica: String, // Base do link do produto no e-mail
+}
+
+impl EmailNotificadorEstoque {
+    pub fn new(mailer: Arc<dyn Mailer>, url_publica: impl Into<String>) -> Self {
+        EmailNotificadorEstoque { mailer, url_publica: url_publica.into() }
+    }
+}
+
+impl NotificadorEstoque for EmailNotificadorEstoque {
+    fn notificar<'a>(&'a self, aviso: &'a AvisoEstoque) -> BoxFuture<'a, Result<(), String>> {
+        let email = Email {
+            para: aviso.email.clone(),
+            assunto: format!("BellaVibe - {} chegou!", aviso.produto_nome),
+            corpo: format!(
+                "Olá, {}!\n\nO produto que você pediu para acompanhar voltou ao estoque:\n\n{}\n{}/produtos/{}\n\n\
+                 Corra, as unidades são limitadas.",
+                aviso.nome_cliente, aviso.produto_nome, self.url_publica, aviso.produto_id
+            ),
+        };
+        self.mailer.enviar(email)
+    }
+}
@@ -0,0 +1,231 @@
+// src/favoritos/favoritos_router.rs
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use sqlx::{query, query_as, query_scalar};
+use std::sync::RwLock;
+
+// Importa as structs de favoritos
+use super::favoritos_structs::MovimentoParaSacola;
+// Importa a consulta e as structs de produtos, para listar os favoritos no mesmo formato
+use crate::produtos::produtos_router::CONSULTA_PRODUTOS;
+use crate::produtos::produtos_structs::{ProdutoRawData, ProdutoResponse};
+// Importa a sacola e a adição de itens a ela
+use crate::vendas::vendas_router::processar_adicao_sacola;
+use crate::vendas::vendas_structs::{Carrinho, ItemVenda};
+// Importa o extrator de autenticação
+use crate::usuarios::auth_middleware::AuthenticatedUser;
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+
+/// Rota para listar os favoritos do usuário autenticado, dos mais recentes para os mais antigos,
+/// com preço e estoque atuais. Produtos excluídos do catálogo não aparecem.
+#[get("/usuarios/me/favoritos")]
+pub async fn buscar_meus_favoritos(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+) -> HttpResponse {
+    let sql = format!(
+        r#"
+        {}
+        JOIN favoritos f ON f.produto_id = p.id
+        WHERE f.usuario_id = $1 AND p.deleted_at IS NULL AND c.deleted_at IS NULL
+        ORDER BY f.criado_em DESC, p.id
+        "#,
+        CONSULTA_PRODUTOS
+    );
+    let result = query_as::<_, ProdutoRawData>(&sql)
+        .bind(auth_user.user_id)
+        .fetch_all(&data.db_pool)
+        .await;
+
+    match result {
+        Ok(produtos_raw) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Favoritos listados com sucesso!".to_string(),
+            body: Some(produtos_raw.into_iter().map(ProdutoResponse::from).collect::<Vec<_>>()),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar favoritos do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar favoritos.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para salvar um produto nos favoritos. Salvar de novo um produto que já está na lista não tem efeito.
+#[post("/usuarios/me/favoritos/{produto_id}")]
+pub async fn adicionar_favorito(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let produto_id = path.into_inner();
+    let result = query(
+        r#"
+        INSERT INTO favoritos (usuario_id, produto_id)
+        SELECT $1, id FROM produtos WHERE id = $2 AND deleted_at IS NULL
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .bind(auth_user.user_id)
+    .bind(produto_id)
+    .execute(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => HttpResponse::Created().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Produto salvo nos favoritos.".to_string(),
+            body: None,
+        }),
+        Ok(_) => {
+            // Nada foi inserido: o produto já era favorito ou não existe
+            let ja_favorito = query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM favoritos WHERE usuario_id = $1 AND produto_id = $2)"
+            )
+            .bind(auth_user.user_id)
+            .bind(produto_id)
+            .fetch_one(&data.db_pool)
+            .await;
+
+            match ja_favorito {
+                Ok(true) => HttpResponse::Ok().json(GenericResponse::<()>{
+                    status: "success".to_string(),
+                    message: "Produto já está nos favoritos.".to_string(),
+                    body: None,
+                }),
+                Ok(false) => HttpResponse::NotFound().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: format!("Produto com ID {} não encontrado.", produto_id),
+                    body: None,
+                }),
+                Err(e) => {
+                    eprintln!("Erro ao verificar favorito {} do usuário {}: {:?}", produto_id, auth_user.user_id, e);
+                    HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                        status: "error".to_string(),
+                        message: "Erro ao salvar favorito.".to_string(),
+                        body: None,
+                    })
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Erro ao salvar favorito {} do usuário {}: {:?}", produto_id, auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao salvar favorito.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para remover um produto dos favoritos.
+#[delete("/usuarios/me/favoritos/{produto_id}")]
+pub async fn remover_favorito(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let produto_id = path.into_inner();
+    let result = query("DELETE FROM favoritos WHERE usuario_id = $1 AND produto_id = $2")
+        .bind(auth_user.user_id)
+        .bind(produto_id)
+        .execute(&data.db_pool)
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Produto removido dos favoritos.".to_string(),
+            body: None,
+        }),
+        Ok(_) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Produto com ID {} não está nos favoritos.", produto_id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao remover favorito {} do usuário {}: {:?}", produto_id, auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao remover favorito.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para mover um favorito para a sacola (`?quantidade=`, padrão 1).
+/// O produto só sai dos favoritos se for adicionado à sacola com sucesso.
+#[post("/usuarios/me/favoritos/{produto_id}/sacola")]
+pub async fn mover_favorito_para_sacola(
+    data: web::Data<AppState>,
+    carrinho_data: web::Data<RwLock<Carrinho>>,
+    auth_user: AuthenticatedUser,
+    path: web::Path<i32>,
+    parametros: web::Query<MovimentoParaSacola>,
+) -> HttpResponse {
+    let produto_id = path.into_inner();
+    if parametros.quantidade <= 0 {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "A quantidade deve ser maior que zero.".to_string(),
+            body: None,
+        });
+    }
+
+    let favorito = query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM favoritos WHERE usuario_id = $1 AND produto_id = $2)"
+    )
+    .bind(auth_user.user_id)
+    .bind(produto_id)
+    .fetch_one(&data.db_pool)
+    .await;
+
+    match favorito {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Produto com ID {} não está nos favoritos.", produto_id),
+                body: None,
+            });
+        }
+        Err(e) => {
+            eprintln!("Erro ao verificar favorito {} do usuário {}: {:?}", produto_id, auth_user.user_id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao mover favorito para a sacola.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    let item = ItemVenda { produto_id, quantidade: parametros.quantidade };
+    let adicao = processar_adicao_sacola(&carrinho_data, &item, &data).await;
+    if !adicao.status().is_success() {
+        return adicao;
+    }
+
+    let remocao = query("DELETE FROM favoritos WHERE usuario_id = $1 AND produto_id = $2")
+        .bind(auth_user.user_id)
+        .bind(produto_id)
+        .execute(&data.db_pool)
+        .await;
+    if let Err(e) = remocao {
+        // O item já está na sacola; o favorito só deixa de ser removido
+        eprintln!("Erro ao remover favorito {} do usuário {} após movê-lo para a sacola: {:?}", produto_id, auth_user.user_id, e);
+    }
+
+    HttpResponse::Ok().json(GenericResponse::<()>{
+        status: "success".to_string(),
+        message: "Produto movido dos favoritos para a sacola.".to_string(),
+        body: None,
+    })
+}
@@ -0,0 +1,14 @@
+// src/favoritos/favoritos_structs.rs
+
+use serde::Deserialize;
+
+/// Parâmetros de `POST /usuarios/me/favoritos/{produto_id}/sacola`.
+#[derive(Deserialize)]
+pub struct MovimentoParaSacola {
+    #[serde(default = "quantidade_padrao")]
+    pub quantidade: i32,
+}
+
+fn quantidade_padrao() -> i32 {
+    1
+}
@@ -0,0 +1,6 @@
+// src/favoritos/mod.rs
+
+// Declara o submódulo que contém as definições das structs de favoritos
+pub mod favoritos_structs;
+// Declara o submódulo que contém as funções de rota da lista de favoritos
+pub mod favoritos_router;
@@ -0,0 +1,151 @@
+// src/frete/calculadora.rs
+
+use bigdecimal::BigDecimal;
+use futures::future::{self, BoxFuture};
+
+use super::frete_structs::{FaixaCep, FaixaPeso, OpcaoFrete, PedidoFrete};
+
+/// Abstração de uma modalidade de frete.
+/// Permite plugar transportadoras (tabelas próprias, APIs externas etc.) sem alterar as rotas.
+/// `cotar` retorna `Ok(None)` quando a modalidade não atende o envio (CEP fora da área,
+/// peso acima do limite) e `Err` quando não foi possível calcular.
+pub trait CalculadoraFrete: Send + Sync {
+    /// Código estável da modalidade, informado pelo cliente ao finalizar a compra.
+    fn modalidade(&self) -> &str;
+    fn cotar<'a>(&'a self, pedido: &'a PedidoFrete) -> BoxFuture<'a, Result<Option<OpcaoFrete>, String>>;
+}
+
+/// Tabela de frete por faixas de peso e de CEP.
+/// O peso cobrado é o maior entre o peso real e o peso cubado (volume / fator de cubagem).
+pub struct TabelaFrete {
+    pub modalidade: String,
+    pub nome: String,
+    pub fator_cubagem: i64, // cm³ por kg; 6000 é o usual das transportadoras
+    pub faixas_peso: Vec<FaixaPeso>,
+    pub faixas_cep: Vec<FaixaCep>,
+}
+
+impl TabelaFrete {
+    /// Peso considerado na tabela, em gramas.
+    fn peso_taxado(&self, pedido: &PedidoFrete) -> i64 {
+        let peso_cubado = pedido.volume_cm3 * 1000 / self.fator_cubagem;
+        pedido.peso_gramas.max(peso_cubado)
+    }
+}
+
+impl CalculadoraFrete for TabelaFrete {
+    fn modalidade(&self) -> &str {
+        &self.modalidade
+    }
+
+    fn cotar<'a>(&'a self, pedido: &'a PedidoFrete) -> BoxFuture<'a, Result<Option<OpcaoFrete>, String>> {
+        let peso = self.peso_taxado(pedido);
+        let faixa_peso = self.faixas_peso.iter().find(|f| peso <= f.ate_gramas);
+        let faixa_cep = self.faixas_cep.iter().find(|f| (f.inicio..=f.fim).contains(&pedido.cep_destino));
+
+        let opcao = match (faixa_peso, faixa_cep) {
+            (Some(faixa_peso), Some(faixa_cep)) => Some(OpcaoFrete {
+                modalidade: self.modalidade.clone(),
+                nome: self.nome.clone(),
+                valor: &faixa_peso.valor + &faixa_cep.acrescimo,
+                prazo_dias: faixa_cep.prazo_dias,
+            }),
+            _ => None,
+        };
+        Box::pin(future::ready(Ok(opcao)))
+    }
+}
+
+/// Frete grátis quando o valor dos itens atinge o mínimo; abaixo dele, vale a calculadora original.
+pub struct FreteGratisAcimaDe {
+    pub calculadora: Box<dyn CalculadoraFrete>,
+    pub valor_minimo: BigDecimal,
+}
+
+impl CalculadoraFrete for FreteGratisAcimaDe {
+    fn modalidade(&self) -> &str {
+        self.calculadora.modalidade()
+    }
+
+    fn cotar<'a>(&'a self, pedido: &'a PedidoFrete) -> BoxFuture<'a, Result<Option<OpcaoFrete>, String>> {
+        Box::pin(async move {
+            let opcao = self.calculadora.cotar(pedido).await?;
+            Ok(opcao.map(|mut opcao| {
+                if pedido.valor_itens >= self.valor_minimo {
+                    opcao.valor = BigDecimal::from(0);
+                }
+                opcao
+            }))
+        })
+    }
+}
+
+/// Retirada na loja: sem custo, disponível para qualquer CEP.
+pub struct RetiradaNaLoja {
+    pub prazo_dias: i32, // Tempo de separação até o pedido ficar disponível
+}
+
+impl CalculadoraFrete for RetiradaNaLoja {
+    fn modalidade(&self) -> &str {
+        "retirada"
+    }
+
+    fn cotar<'a>(&'a self, _pedido: &'a PedidoFrete) -> BoxFuture<'a, Result<Option<OpcaoFrete>, String>> {
+        Box::pin(future::ready(Ok(Some(OpcaoFrete {
+            modalidade: "retirada".to_string(),
+            nome: "Retirada na loja".to_string(),
+            valor: BigDecimal::from(0),
+            prazo_dias: self.prazo_dias,
+        }))))
+    }
+}
+
+/// Valor em reais a partir de centavos, para montar as tabelas.
+fn reais(centavos: i64) -> BigDecimal {
+    BigDecimal::new(centavos.into(), 2)
+}
+
+/// Faixas de CEP por região, a partir da loja em São Paulo: (início, fim, prazo, acréscimo em centavos).
+fn faixas_cep_por_regiao(prazos: [i32; 5]) -> Vec<FaixaCep> {
+    [
+        (1_000_000, 19_999_999, prazos[0], 0),      // SP
+        (20_000_000, 39_999_999, prazos[1], 500),   // RJ, ES e MG
+        (80_000_000, 99_999_999, prazos[2], 700),   // PR, SC e RS
+        (70_000_000, 79_999_999, prazos[3], 1000),  // DF, GO, TO, MT, MS e RO
+        (40_000_000, 69_999_999, prazos[4], 1500),  // Nordeste e Norte
+    ]
+    .into_iter()
+    .map(|(inicio, fim, prazo_dias, acrescimo)| FaixaCep { inicio, fim, prazo_dias, acrescimo: reais(acrescimo) })
+    .collect()
+}
+
+/// Modalidades de frete oferecidas pela loja.
+/// Para trocar uma tabela por uma transportadora real, basta implementar `CalculadoraFrete`.
+pub fn modalidades_padrao() -> Vec<Box<dyn CalculadoraFrete>> {
+    let economico = TabelaFrete {
+        modalidade: "economico".to_string(),
+        nome: "Econômico".to_string(),
+        fator_cubagem: 6000,
+        faixas_peso: [(300, 1590), (1_000, 1990), (5_000, 2990), (10_000, 4490), (30_000, 7990)]
+            .into_iter()
+            .map(|(ate_gramas, valor)| FaixaPeso { ate_gramas, valor: reais(valor) })
+            .collect(),
+        faixas_cep: faixas_cep_por_regiao([3, 5, 6, 7, 9]),
+    };
+    let expresso = TabelaFrete {
+        modalidade: "expresso".to_string(),
+        nome: "Expresso".to_string(),
+        fator_cubagem: 6000,
+        faixas_peso: [(300, 2490), (1_000, 3290), (5_000, 4990), (10_000, 6990), (30_000, 11990)]
+            .into_iter()
+            .map(|(ate_gramas, valor)| FaixaPeso { ate_gramas, valor: reais(valor) })
+            .collect(),
+        faixas_cep: faixas_cep_por_regiao([1, 2, 2, 3, 4]),
+    };
+
+    vec![
+        Box::new(FreteGratisAcimaDe { calculadora: Box::new(economico), valor_minimo: reais(29_900) }),
+        Box::new(expresso),
+        Box::new(RetiradaNaLoja { prazo_dias: 1 }),
+    ]
+}
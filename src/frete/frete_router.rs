@@ -0,0 +1,78 @@
+// src/frete/frete_router.rs
+
+use actix_web::{post, web, HttpResponse};
+use std::sync::RwLock;
+
+// Importa as structs de frete
+use super::frete_structs::CotacaoFrete;
+// Importa a montagem do envio e a cotação nas modalidades
+use super::frete_service::{cotar_modalidades, montar_pedido_frete, ErroFrete};
+// Importa a sacola de compras
+use crate::vendas::vendas_structs::Carrinho;
+// Importa a validação de CEP
+use crate::shared::validacoes::cep_valido;
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+
+/// Rota para cotar o frete da sacola atual para um CEP.
+/// Retorna as modalidades que atendem o envio, da mais barata para a mais cara.
+/// O código da modalidade escolhida é informado em `modalidade_frete` ao finalizar a compra.
+#[post("/frete/cotacao")]
+pub async fn cotar_frete(
+    data: web::Data<AppState>,
+    carrinho_data: web::Data<RwLock<Carrinho>>,
+    item: web::Json<CotacaoFrete>,
+) -> HttpResponse {
+    if !cep_valido(&item.cep) {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "CEP inválido. Use o formato 12345-678.".to_string(),
+            body: None,
+        });
+    }
+
+    let itens = carrinho_data.read().unwrap().itens.clone();
+    if itens.is_empty() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "A sacola está vazia. Adicione itens antes de cotar o frete.".to_string(),
+            body: None,
+        });
+    }
+
+    let pedido = match montar_pedido_frete(&data.db_pool, &item.cep, &itens).await {
+        Ok(pedido) => pedido,
+        Err(ErroFrete::ProdutoNaoEncontrado(id)) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Produto com ID {} da sacola não está mais disponível.", id),
+                body: None,
+            });
+        },
+        Err(ErroFrete::Banco(e)) => {
+            eprintln!("Erro ao montar envio para cotação de frete: {:?}", e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao cotar frete.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    let opcoes = cotar_modalidades(&data.calculadoras_frete, &pedido).await;
+    if opcoes.is_empty() {
+        return HttpResponse::UnprocessableEntity().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Nenhuma modalidade de frete atende este envio.".to_string(),
+            body: None,
+        });
+    }
+
+    HttpResponse::Ok().json(GenericResponse {
+        status: "success".to_string(),
+        message: "Frete cotado com sucesso.".to_string(),
+        body: Some(opcoes),
+    })
+}
@@ -0,0 +1,81 @@
+// src/frete/frete_service.rs
+
+use bigdecimal::BigDecimal;
+use sqlx::{query_as, Executor, Postgres};
+
+use super::calculadora::CalculadoraFrete;
+use super::frete_structs::{MedidasProduto, OpcaoFrete, PedidoFrete};
+use crate::shared::validacoes::somente_digitos;
+use crate::vendas::vendas_structs::ItemVenda;
+
+/// Falhas ao montar os dados de envio de uma sacola.
+#[derive(Debug)]
+pub enum ErroFrete {
+    ProdutoNaoEncontrado(i32),
+    Banco(sqlx::Error),
+}
+
+/// Consolida peso, volume e valor dos itens para a cotação de frete.
+/// Dentro da transação da venda, usa os mesmos preços das linhas já bloqueadas.
+pub async fn montar_pedido_frete<'c, E>(executor: E, cep: &str, itens: &[ItemVenda]) -> Result<PedidoFrete, ErroFrete>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let ids: Vec<i32> = itens.iter().map(|item| item.produto_id).collect();
+    let medidas = query_as::<_, MedidasProduto>(
+        r#"
+        SELECT id, preco, peso_gramas, (altura_cm::BIGINT * largura_cm * comprimento_cm) AS volume_cm3
+        FROM produtos WHERE id = ANY($1) AND deleted_at IS NULL
+        "#
+    )
+    .bind(&ids)
+    .fetch_all(executor)
+    .await
+    .map_err(ErroFrete::Banco)?;
+
+    let mut pedido = PedidoFrete {
+        cep_destino: somente_digitos(cep).parse().unwrap_or(0),
+        valor_itens: BigDecimal::from(0),
+        peso_gramas: 0,
+        volume_cm3: 0,
+    };
+    for item in itens {
+        let produto = medidas
+            .iter()
+            .find(|m| m.id == item.produto_id)
+            .ok_or(ErroFrete::ProdutoNaoEncontrado(item.produto_id))?;
+        let quantidade = i64::from(item.quantidade);
+        pedido.valor_itens += &produto.preco * BigDecimal::from(quantidade);
+        pedido.peso_gramas += i64::from(produto.peso_gramas) * quantidade;
+        pedido.volume_cm3 += produto.volume_cm3 * quantidade;
+    }
+    Ok(pedido)
+}
+
+/// Cota o envio em todas as modalidades, do frete mais barato para o mais caro.
+/// Modalidades que não atendem o envio ou que falharem ficam de fora da lista.
+pub async fn cotar_modalidades(calculadoras: &[Box<dyn CalculadoraFrete>], pedido: &PedidoFrete) -> Vec<OpcaoFrete> {
+    let mut opcoes = Vec::new();
+    for calculadora in calculadoras {
+        match calculadora.cotar(pedido).await {
+            Ok(Some(opcao)) => opcoes.push(opcao),
+            Ok(None) => {},
+            Err(e) => eprintln!("Erro ao cotar frete na modalidade {}: {}", calculadora.modalidade(), e),
+        }
+    }
+    opcoes.sort_by(|a, b| a.valor.cmp(&b.valor).then(a.prazo_dias.cmp(&b.prazo_dias)));
+    opcoes
+}
+
+/// Cota o envio em uma modalidade específica, escolhida pelo cliente.
+/// Retorna `Ok(None)` se a modalidade não existe ou não atende o envio.
+pub async fn cotar_modalidade(
+    calculadoras: &[Box<dyn CalculadoraFrete>],
+    modalidade: &str,
+    pedido: &PedidoFrete,
+) -> Result<Option<OpcaoFrete>, String> {
+    match calculadoras.iter().find(|c| c.modalidade() == modalidade) {
+        Some(calculadora) => calculadora.cotar(pedido).await,
+        None => Ok(None),
+    }
+}
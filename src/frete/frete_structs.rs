@@ -0,0 +1,52 @@
+// src/frete/frete_structs.rs
+
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Dados de um envio, consolidados a partir dos itens da sacola.
+/// É o que as calculadoras de frete recebem para cotar.
+pub struct PedidoFrete {
+    pub cep_destino: u32, // CEP somente com dígitos, como número (facilita as faixas)
+    pub valor_itens: BigDecimal,
+    pub peso_gramas: i64,
+    pub volume_cm3: i64,
+}
+
+/// Uma opção de frete cotada, apresentada ao cliente e gravada no pedido.
+#[derive(Serialize, Clone)]
+pub struct OpcaoFrete {
+    pub modalidade: String, // Código usado para escolher a opção em `/venda`
+    pub nome: String,
+    pub valor: BigDecimal,
+    pub prazo_dias: i32,
+}
+
+/// Estrutura para receber o pedido de cotação de frete da sacola.
+#[derive(Deserialize)]
+pub struct CotacaoFrete {
+    pub cep: String,
+}
+
+/// Faixa de peso de uma tabela de frete: envios até `ate_gramas` custam `valor`.
+pub struct FaixaPeso {
+    pub ate_gramas: i64,
+    pub valor: BigDecimal,
+}
+
+/// Faixa de CEPs atendida por uma tabela de frete, com prazo e acréscimo próprios.
+pub struct FaixaCep {
+    pub inicio: u32,
+    pub fim: u32,
+    pub prazo_dias: i32,
+    pub acrescimo: BigDecimal,
+}
+
+/// Peso, dimensões e preço de um produto, lidos para montar o envio.
+#[derive(FromRow)]
+pub struct MedidasProduto {
+    pub id: i32,
+    pub preco: BigDecimal,
+    pub peso_gramas: i32,
+    pub volume_cm3: i64,
+}
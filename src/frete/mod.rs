@@ -0,0 +1,10 @@
+// src/frete/mod.rs
+
+// Declara o submódulo que contém as definições das structs de frete
+pub mod frete_structs;
+// Declara o submódulo com a abstração das calculadoras de frete e as regras disponíveis
+pub mod calculadora;
+// Declara o submódulo que monta os dados de envio da sacola e consulta as calculadoras
+pub mod frete_service;
+// Declara o submódulo que contém as funções de rota de frete
+pub mod frete_router;
@@ -2,7 +2,7 @@
 
 use actix_web::{web, App, HttpServer};
 use sqlx::{Pool, Postgres};
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 
 // Importa os módulos
@@ -14,11 +14,27 @@ mod vendas;     // Módulo de vendas
 mod categorias; // Módulo de categorias
 mod shared;     // Módulo shared
 mod usuarios;   // Módulo de usuários
+mod auditoria;  // Módulo de auditoria
+mod email;      // Módulo de envio de e-mails
+mod enderecos;  // Módulo de endereços dos clientes
+mod frete;      // Módulo de cálculo de frete
+mod pagamentos; // Módulo de pagamentos
+mod relatorios; // Módulo de relatórios de vendas
+mod avaliacoes; // Módulo de avaliações de produtos
+mod favoritos;  // Módulo de favoritos dos clientes
+mod estoque;    // Módulo de entrada de estoque e avisos de volta ao estoque
+mod atributos;  // Módulo de atributos de produtos e filtros por faceta
+mod marcas;     // Módulo de marcas
 
-// Estado compartilhado que contém a conexão com o banco de dados e a chave secreta JWT.
+// Estado compartilhado que contém a conexão com o banco de dados e as chaves de assinatura JWT.
 pub struct AppState {
     pub db_pool: Pool<Postgres>,
-    pub jwt_secret: String, //Chave secreta para JWT
+    pub chaves_jwt: usuarios::chaves_jwt::ChavesJwt, // Chaves Ed25519 que assinam os tokens de acesso
+    pub mailer: Arc<dyn email::mailer::Mailer>, // Envio de e-mails (implementação plugável)
+    pub url_publica: String, // URL base usada nos links enviados por e-mail
+    pub chave_links: String, // Chave HMAC para assinar links enviados por e-mail
+    pub calculadoras_frete: Vec<Box<dyn frete::calculadora::CalculadoraFrete>>, // Modalidades de frete oferecidas
+    pub gateway_pagamento: Arc<dyn pagamentos::gateway::GatewayPagamento>, // Gateway de pagamento (implementação plugável)
 }
 
 // Função principal da aplicação Actix Web.
@@ -32,21 +48,68 @@ async fn main() -> std::io::Result<()> {
 
     // Conecta ao banco de dados PostgreSQL usando um pool de conexões.
     // O .expect() fará com que o programa entre em pânico se a conexão falhar.
-    let db_pool = Pool::<Postgres>::connect(&database_url).await
+    let db_pool = Pool::<Postgres>::connect(database_url).await
         .expect("Falha ao conectar ao banco PostgreSQL");
 
-    // Define a chave secreta JWT (em produção, viria de variáveis de ambiente)
-    //let jwt_secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "(5ax<hF#<fT_pG>2poL1>XuL)345[sxY".into()); 
-    let jwt_secret = "minha_chave_secreta_para_testes_123".to_string();
+    // Carrega as chaves de assinatura dos tokens (EdDSA). Se o diretório estiver vazio,
+    // uma chave nova é gerada. Em produção, aponte BELLAVIBE_CHAVES_JWT para um volume protegido.
+    let diretorio_chaves = std::env::var("BELLAVIBE_CHAVES_JWT").unwrap_or_else(|_| "chaves_jwt".into());
+    let chaves_jwt = usuarios::chaves_jwt::ChavesJwt::carregar(diretorio_chaves)
+        .expect("Falha ao carregar as chaves de assinatura JWT");
+
+    // Em desenvolvimento os e-mails vão para o log ou, se BELLAVIBE_EMAIL_ARQUIVO estiver
+    // definida, para o arquivo indicado. Em produção, troque por um Mailer real.
+    let mailer: Arc<dyn email::mailer::Mailer> = match std::env::var("BELLAVIBE_EMAIL_ARQUIVO") {
+        Ok(caminho) => Arc::new(email::mailer::ArquivoMailer::new(caminho)),
+        Err(_) => Arc::new(email::mailer::LogMailer),
+    };
+    let url_publica = "http://127.0.0.1:8080".to_string();
+    // Chave dos links assinados (ex.: verificação de e-mail). Sem ela qualquer um poderia forjar os links,
+    // então a aplicação não sobe se BELLAVIBE_CHAVE_LINKS não estiver definida.
+    let chave_links = std::env::var("BELLAVIBE_CHAVE_LINKS")
+        .ok()
+        .filter(|chave| !chave.is_empty())
+        .expect("Defina BELLAVIBE_CHAVE_LINKS com a chave secreta dos links assinados");
 
     // Cria um estado compartilhado da aplicação com o pool de conexões.
     // web::Data é usado para compartilhar dados imutáveis entre as rotas.
-    let app_state = web::Data::new(AppState { db_pool, jwt_secret });
+    // Modalidades de frete (tabelas próprias e retirada na loja).
+    let calculadoras_frete = frete::calculadora::modalidades_padrao();
+
+    // Recebedor impresso no "copia e cola" do Pix. Em produção, viria de variável de ambiente.
+    let configuracao_pix = pagamentos::pix::ConfiguracaoPix {
+        chave: "pix@bellavibe.com.br".to_string(),
+        nome_recebedor: "BellaVibe".to_string(),
+        cidade: "São Paulo".to_string(),
+    };
+
+    // Em desenvolvimento os pagamentos passam pelo gateway simulado. Em produção, troque por um gateway real.
+    let gateway_pagamento: Arc<dyn pagamentos::gateway::GatewayPagamento> =
+        Arc::new(pagamentos::gateway::GatewayMock::new("minha_chave_de_webhook_para_testes_789", configuracao_pix));
+
+    let app_state = web::Data::new(AppState {
+        db_pool, chaves_jwt, mailer, url_publica, chave_links, calculadoras_frete, gateway_pagamento,
+    });
+
+    // Cancela periodicamente os pedidos não pagos dentro do prazo, devolvendo o estoque reservado.
+    pagamentos::pagamentos_service::iniciar_liberacao_de_reservas(app_state.db_pool.clone());
+    // Remove periodicamente as chaves de idempotência vencidas (24 horas).
+    shared::idempotencia::iniciar_limpeza_idempotencia(app_state.db_pool.clone());
+    // Envia periodicamente os avisos de volta ao estoque. Os avisos vão por e-mail; para outro
+    // canal, troque por outra implementação de NotificadorEstoque.
+    let notificador_estoque: Arc<dyn estoque::notificador::NotificadorEstoque> = Arc::new(
+        estoque::notificador::EmailNotificadorEstoque::new(app_state.mailer.clone(), app_state.url_publica.clone()),
+    );
+    estoque::estoque_service::iniciar_envio_avisos_estoque(app_state.db_pool.clone(), notificador_estoque);
 
     // Cria e compartilha o estado do carrinho de compras em memória.
     // RwLock permite múltiplos leitores ou um único escritor.
     let carrinho_state = web::Data::new(RwLock::new(vendas::vendas_structs::Carrinho::default()));
 
+    // Calcula antecipadamente o hash fictício do login, para que a primeira tentativa
+    // com e-mail inexistente não seja mais lenta que as demais.
+    usuarios::protecao_login::hash_ficticio();
+
     println!("Iniciando API BellaVibe na porta 8080...");
 
     // Configura e inicia o servidor HTTP.
@@ -60,34 +123,129 @@ async fn main() -> std::io::Result<()> {
 
 
             // Módulo de Produtos
+            // Rotas com segmentos fixos vêm antes de /produtos/{id} para não serem capturadas por ela.
+            .service(produtos::produtos_router::buscar_produtos_excluidos)
             .service(produtos::produtos_router::buscar_produtos)
+            .service(produtos::produtos_router::buscar_produto_por_slug)
             .service(produtos::produtos_router::buscar_produto_por_id)
             .service(produtos::produtos_router::cadastrar_produto)
             .service(produtos::produtos_router::atualizar_produto)
+            .service(produtos::produtos_router::atualizar_produto_parcial)
             .service(produtos::produtos_router::deletar_produto)
+            .service(produtos::produtos_router::restaurar_produto)
+
+            // Módulo de Estoque (entradas e "avise-me")
+            .service(estoque::estoque_router::registrar_entrada_estoque)
+            .service(estoque::estoque_router::inscrever_aviso_estoque)
+            .service(estoque::estoque_router::cancelar_aviso_estoque)
+            .service(estoque::estoque_router::buscar_meus_avisos_estoque)
+
+            // Módulo de Avaliações
+            .service(avaliacoes::avaliacoes_router::avaliar_produto)
+            .service(avaliacoes::avaliacoes_router::buscar_avaliacoes_produto)
+            .service(avaliacoes::avaliacoes_router::buscar_avaliacoes_moderacao)
+            .service(avaliacoes::avaliacoes_router::moderar_avaliacao)
+
+            // Módulo de Marcas
+            .service(marcas::marcas_router::buscar_marcas_excluidas)
+            .service(marcas::marcas_router::cadastrar_marca)
+            .service(marcas::marcas_router::buscar_marcas)
+            .service(marcas::marcas_router::buscar_marca_por_id)
+            .service(marcas::marcas_router::buscar_produtos_da_marca)
+            .service(marcas::marcas_router::atualizar_marca)
+            .service(marcas::marcas_router::deletar_marca)
+            .service(marcas::marcas_router::restaurar_marca)
                         
             //Módulo de Vendas            
             .service(vendas::vendas_router::realizar_venda)
+            .service(vendas::vendas_router::atualizar_status_venda)
             .service(vendas::vendas_router::adicionar_item_sacola)
             .service(vendas::vendas_router::ver_sacola)
 
+            // Módulo de Pagamentos
+            .service(pagamentos::pagamentos_router::receber_webhook_pagamento)
+            .service(pagamentos::pagamentos_router::estornar_venda)
+            .service(pagamentos::pagamentos_router::receber_webhook_pix)
+            .service(pagamentos::pagamentos_router::qrcode_pix)
+
+            // Módulo de Frete
+            .service(frete::frete_router::cotar_frete)
+
             // Módulo de Categorias (Rotas de Sessões)
             .service(categorias::categoria_router::cadastrar_sessao)
             .service(categorias::categoria_router::buscar_sessoes)
+            .service(categorias::categoria_router::buscar_sessao_por_slug)
+            .service(categorias::categoria_router::reordenar_sessoes)
             .service(categorias::categoria_router::buscar_sessao_por_id)
             .service(categorias::categoria_router::atualizar_sessao)    
             .service(categorias::categoria_router::deletar_sessao)      
+            .service(categorias::categoria_router::restaurar_sessao)
 
             // Módulo de Categorias (Rotas de Categorias Filhas/Genéricas)
+            .service(categorias::categoria_router::buscar_categorias_excluidas)
+            .service(categorias::categoria_router::buscar_categoria_por_slug)
             .service(categorias::categoria_router::cadastrar_categoria)
             .service(categorias::categoria_router::buscar_categorias_por_sessao)
+            .service(categorias::categoria_router::reordenar_categorias_da_sessao)
             .service(categorias::categoria_router::buscar_categoria_por_id)
             .service(categorias::categoria_router::atualizar_categoria)
+            .service(categorias::categoria_router::atualizar_categoria_parcial)
             .service(categorias::categoria_router::deletar_categoria)
+            .service(categorias::categoria_router::restaurar_categoria)
+
+            // Módulo de Atributos (depois das categorias, para /categorias/slug/{slug} vir antes de /categorias/{id}/atributos)
+            .service(atributos::atributos_router::buscar_atributos)
+            .service(atributos::atributos_router::cadastrar_atributo)
+            .service(atributos::atributos_router::atualizar_atributo)
+            .service(atributos::atributos_router::buscar_atributos_categoria)
+            .service(atributos::atributos_router::definir_atributos_categoria)
+            .service(atributos::atributos_router::definir_atributos_produto)
+
+            // Módulo de Auditoria
+            .service(auditoria::auditoria_router::buscar_auditoria)
+
+            // Módulo de Relatórios
+            .service(relatorios::relatorios_router::relatorio_faturamento)
+            .service(relatorios::relatorios_router::relatorio_produtos_mais_vendidos)
+            .service(relatorios::relatorios_router::relatorio_vendas_por_categoria)
+            .service(relatorios::relatorios_router::relatorio_resumo)
 
             // Módulo de Usuários (Novas Rotas)
             .service(usuarios::usuario_router::cadastrar_usuario)
+            .service(usuarios::usuario_router::buscar_eventos_login)
             .service(usuarios::usuario_router::login_usuario)
+            .service(usuarios::dois_fatores_router::login_2fa)
+            .service(usuarios::usuario_router::buscar_meu_perfil)
+            .service(usuarios::usuario_router::atualizar_meu_perfil)
+            .service(usuarios::usuario_router::alterar_minha_senha)
+            .service(usuarios::usuario_router::excluir_minha_conta)
+            .service(usuarios::dois_fatores_router::inscrever_2fa)
+            .service(usuarios::dois_fatores_router::ativar_2fa)
+            .service(usuarios::dois_fatores_router::desativar_2fa)
+            .service(usuarios::usuario_router::esqueci_senha)
+            .service(usuarios::usuario_router::redefinir_senha)
+            .service(usuarios::usuario_router::verificar_email)
+            .service(usuarios::usuario_router::reenviar_verificacao_email)
+
+            // Módulo de Endereços
+            .service(enderecos::enderecos_router::buscar_meus_enderecos)
+            .service(enderecos::enderecos_router::buscar_meu_endereco_por_id)
+            .service(enderecos::enderecos_router::cadastrar_endereco)
+            .service(enderecos::enderecos_router::atualizar_endereco)
+            .service(enderecos::enderecos_router::definir_endereco_padrao)
+            .service(enderecos::enderecos_router::deletar_endereco)
+
+            // Módulo de Favoritos
+            .service(favoritos::favoritos_router::buscar_meus_favoritos)
+            .service(favoritos::favoritos_router::adicionar_favorito)
+            .service(favoritos::favoritos_router::remover_favorito)
+            .service(favoritos::favoritos_router::mover_favorito_para_sacola)
+
+            // Chaves de assinatura dos tokens (JWKS e rotação)
+            .service(usuarios::chaves_jwt_router::buscar_jwks)
+            .service(usuarios::chaves_jwt_router::buscar_chaves_jwt)
+            .service(usuarios::chaves_jwt_router::rotacionar_chave_jwt)
+            .service(usuarios::chaves_jwt_router::remover_chave_jwt)
     })
     // Vincula o servidor ao endereço IP e porta. O '?' propaga erros.
     .bind("127.0.0.1:8080")?
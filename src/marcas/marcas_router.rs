@@ -0,0 +1,411 @@
+// src/marcas/marcas_router.rs
+
+use actix_web::{get, post, put, delete, http::header, web, HttpRequest, HttpResponse, Responder};
+use sqlx::{query_as, query, query_scalar};
+
+// Importa as structs de marca
+use super::marcas_structs::{Marca, MarcaExcluida, NovaMarca};
+// Importa a listagem de produtos com filtros e facetas
+use crate::produtos::produtos_router::listar_produtos;
+use crate::produtos::produtos_structs::FiltroProdutos;
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa os auxiliares de concorrência otimista (ETag/If-Match)
+use crate::shared::concorrencia::{etag_da_versao, versao_do_if_match, conflito_de_versao};
+
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+// Importa o extrator de autorização para as rotas administrativas
+use crate::usuarios::auth_middleware::AdminUser;
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::executar_com_auditoria;
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
+
+/// Indica se o erro é a unicidade do nome entre as marcas ativas.
+fn nome_repetido(e: &sqlx::Error) -> bool {
+    e.as_database_error().and_then(|d| d.constraint()) == Some("idx_marcas_nome")
+}
+
+/// Resposta 400 para marca sem nome.
+fn nome_obrigatorio() -> HttpResponse {
+    HttpResponse::BadRequest().json(GenericResponse::<()>{
+        status: "error".to_string(),
+        message: "O nome da marca é obrigatório.".to_string(),
+        body: None,
+    })
+}
+
+/// Rota para cadastrar uma nova marca.
+/// O nome não pode repetir o de outra marca ativa (sem diferenciar maiúsculas).
+#[post("/marcas")]
+pub async fn cadastrar_marca(
+    data: web::Data<AppState>,
+    item: web::Json<NovaMarca>,
+    admin: AdminUser,
+) -> HttpResponse {
+    if item.nome.trim().is_empty() {
+        return nome_obrigatorio();
+    }
+
+    let comando = query(
+        "INSERT INTO marcas (nome, descricao) VALUES ($1, $2) RETURNING id, NULL::jsonb AS antes, to_jsonb(marcas) AS depois"
+    )
+    .bind(item.nome.trim())
+    .bind(&item.descricao);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "marca", AcaoAuditoria::Criacao, comando).await {
+        Ok(Some(id)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: format!("Marca cadastrada com sucesso! ID: {}", id),
+            body: Some(serde_json::json!({ "id": id })),
+        }),
+        Ok(None) => {
+            eprintln!("Erro ao obter id da nova marca: nenhuma linha retornada");
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao processar resposta do cadastro da marca".to_string(),
+                body: None,
+            })
+        }
+        Err(e) if nome_repetido(&e) => HttpResponse::Conflict().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Já existe uma marca com o nome '{}'.", item.nome.trim()),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao inserir marca: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao inserir marca".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para buscar todas as marcas ativas, em ordem alfabética.
+#[get("/marcas")]
+pub async fn buscar_marcas(data: web::Data<AppState>) -> impl Responder {
+    let marcas_result = query_as::<_, Marca>("SELECT id, nome, descricao, versao FROM marcas WHERE deleted_at IS NULL ORDER BY nome, id")
+        .fetch_all(&data.db_pool)
+        .await;
+
+    match marcas_result {
+        Ok(marcas) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Marcas listadas com sucesso!".to_string(),
+            body: Some(marcas),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar marcas: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar marcas".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para buscar uma marca por ID, com a versão no cabeçalho `ETag`.
+#[get("/marcas/{id}")]
+pub async fn buscar_marca_por_id(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let marca_result = query_as::<_, Marca>("SELECT id, nome, descricao, versao FROM marcas WHERE id = $1 AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_optional(&data.db_pool)
+        .await;
+
+    match marca_result {
+        Ok(Some(marca)) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag_da_versao(marca.versao)))
+            .json(GenericResponse {
+                status: "success".to_string(),
+                message: format!("Marca com ID {} encontrada.", id),
+                body: Some(marca),
+            }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Marca com ID {} não encontrada.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar marca por ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar marca".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota da página da marca: os produtos ativos da marca, com os mesmos filtros,
+/// ordenação e facetas de `GET /produtos`.
+#[get("/marcas/{id}/produtos")]
+pub async fn buscar_produtos_da_marca(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    filtro: web::Query<FiltroProdutos>,
+    parametros: web::Query<Vec<(String, String)>>,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let existe = query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM marcas WHERE id = $1 AND deleted_at IS NULL)")
+        .bind(id)
+        .fetch_one(&data.db_pool)
+        .await;
+
+    match existe {
+        Ok(true) => {
+            let mut filtro = filtro.into_inner();
+            filtro.marca_id = Some(id);
+            listar_produtos(&data, filtro, &parametros).await
+        }
+        Ok(false) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Marca com ID {} não encontrada.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar marca {} para listar produtos: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar produtos da marca".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para atualizar nome e descrição de uma marca.
+/// Exige o cabeçalho `If-Match` com o ETag da marca (412 se ela foi alterada nesse meio tempo).
+#[put("/marcas/{id}")]
+pub async fn atualizar_marca(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<NovaMarca>,
+    req: HttpRequest,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let versao = match versao_do_if_match(&req) {
+        Ok(v) => v,
+        Err(resposta) => return resposta,
+    };
+    if item.nome.trim().is_empty() {
+        return nome_obrigatorio();
+    }
+
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT m.id, to_jsonb(m) AS antes FROM marcas m WHERE m.id = $3 AND m.deleted_at IS NULL FOR UPDATE)
+        UPDATE marcas SET nome = $1, descricao = $2, versao = versao + 1
+        FROM anterior
+        WHERE marcas.id = anterior.id AND marcas.versao = $4
+        RETURNING marcas.id, anterior.antes, to_jsonb(marcas) AS depois
+        "#
+    )
+    .bind(item.nome.trim())
+    .bind(&item.descricao)
+    .bind(id)
+    .bind(versao);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "marca", AcaoAuditoria::Atualizacao, comando).await {
+        Ok(Some(_)) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag_da_versao(versao + 1)))
+            .json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Marca com ID {} atualizada com sucesso.", id),
+                body: None,
+            }),
+        Ok(None) => responder_marca_nao_alterada(&data, id).await,
+        Err(e) if nome_repetido(&e) => HttpResponse::Conflict().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Já existe uma marca com o nome '{}'.", item.nome.trim()),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao atualizar marca com ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao atualizar marca".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Monta a resposta para uma atualização condicionada que não alterou nenhuma linha:
+/// 412 se a marca existe com outra versão, ou 404 se ela não existe.
+async fn responder_marca_nao_alterada(data: &web::Data<AppState>, id: i32) -> HttpResponse {
+    let versao_atual = query_scalar::<_, i32>("SELECT versao FROM marcas WHERE id = $1 AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_optional(&data.db_pool)
+        .await;
+
+    match versao_atual {
+        Ok(Some(versao_atual)) => conflito_de_versao(
+            format!("Marca com ID {} foi alterada por outra pessoa. Recarregue e tente novamente.", id),
+            versao_atual,
+        ),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Marca com ID {} não encontrada para atualização.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao verificar versão da marca com ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao atualizar marca.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para deletar uma marca, desde que nenhum produto ativo a use.
+/// A exclusão é lógica (soft delete) e pode ser desfeita por `/marcas/{id}/restaurar`.
+#[delete("/marcas/{id}")]
+pub async fn deletar_marca(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+
+    let produtos = query_scalar::<_, i64>("SELECT COUNT(*) FROM produtos WHERE marca_id = $1 AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_one(&data.db_pool)
+        .await;
+    match produtos {
+        Ok(0) => {},
+        Ok(produtos) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Não é possível deletar a marca: existem {} produto(s) ativos associados.", produtos),
+                body: None,
+            });
+        }
+        Err(e) => {
+            eprintln!("Erro ao verificar produtos da marca {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao verificar produtos da marca.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT m.id, to_jsonb(m) AS antes FROM marcas m WHERE m.id = $1 AND m.deleted_at IS NULL FOR UPDATE)
+        UPDATE marcas SET deleted_at = NOW(), versao = versao + 1
+        FROM anterior
+        WHERE marcas.id = anterior.id
+        RETURNING marcas.id, anterior.antes, to_jsonb(marcas) AS depois
+        "#
+    )
+    .bind(id);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "marca", AcaoAuditoria::Exclusao, comando).await {
+        Ok(Some(_)) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Marca com ID {} deletada com sucesso.", id),
+            body: None,
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Marca com ID {} não encontrada para exclusão.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao deletar marca com ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao deletar marca.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para restaurar uma marca excluída logicamente.
+/// Recusada com 409 se outra marca ativa passou a usar o mesmo nome.
+#[post("/marcas/{id}/restaurar")]
+pub async fn restaurar_marca(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT m.id, to_jsonb(m) AS antes FROM marcas m WHERE m.id = $1 AND m.deleted_at IS NOT NULL FOR UPDATE)
+        UPDATE marcas SET deleted_at = NULL, versao = versao + 1
+        FROM anterior
+        WHERE marcas.id = anterior.id
+        RETURNING marcas.id, anterior.antes, to_jsonb(marcas) AS depois
+        "#
+    )
+    .bind(id);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "marca", AcaoAuditoria::Restauracao, comando).await {
+        Ok(Some(_)) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Marca com ID {} restaurada com sucesso.", id),
+            body: None,
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Marca com ID {} não encontrada entre as excluídas.", id),
+            body: None,
+        }),
+        Err(e) if nome_repetido(&e) => HttpResponse::Conflict().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Já existe uma marca ativa com o mesmo nome. Renomeie-a antes de restaurar esta.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao restaurar marca com ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao restaurar marca.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para listar as marcas excluídas logicamente.
+/// Deve ser registrada antes de `/marcas/{id}` para não ser capturada por ela.
+#[get("/marcas/excluidas")]
+pub async fn buscar_marcas_excluidas(
+    data: web::Data<AppState>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let marcas_result = query_as::<_, MarcaExcluida>(
+        "SELECT id, nome, deleted_at FROM marcas WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"
+    )
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match marcas_result {
+        Ok(marcas) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Marcas excluídas listadas com sucesso!".to_string(),
+            body: Some(marcas),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar marcas excluídas: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar marcas excluídas".to_string(),
+                body: None,
+            })
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// src/marcas/marcas_structs.rs
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+/// Estrutura para receber dados de uma nova marca na requisição POST/PUT
+#[derive(Deserialize)]
+pub struct NovaMarca {
+    pub nome: String,
+    pub descricao: Option<String>,
+}
+
+/// Estrutura que representa uma marca no banco de dados
+#[derive(Serialize, FromRow)]
+pub struct Marca {
+    pub id: i32,
+    pub nome: String,
+    pub descricao: Option<String>,
+    pub versao: i32, // Versão para controle de concorrência (também enviada como ETag)
+}
+
+/// Estrutura para a visão administrativa de marcas excluídas (soft delete).
+#[derive(Serialize, FromRow)]
+pub struct MarcaExcluida {
+    pub id: i32,
+    pub nome: String,
+    pub deleted_at: DateTime<Utc>,
+}
@@ -0,0 +1,6 @@
+// src/marcas/mod.rs
+
+// Declara o submódulo que contém as definições das structs de marcas
+pub mod marcas_structs;
+// Declara o submódulo que contém as funções de rota relacionadas a marcas
+pub mod marcas_router;
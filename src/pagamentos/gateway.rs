@@ -0,0 +1,176 @@
+// src/pagamentos/gateway.rs
+
+use bigdecimal::BigDecimal;
+use futures::future::{self, BoxFuture};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use super::pagamentos_structs::{
+    Autorizacao, Cobranca, EventoPagamento, MetodoPagamento, NotificacaoPix, PixRecebido, ResultadoAutorizacao,
+    TipoEventoPagamento,
+};
+use super::pix::{gerar_payload, ConfiguracaoPix, TAMANHO_MAXIMO_TXID};
+use crate::shared::tokens::{assinatura_valida, gerar_token};
+
+/// Abstração do gateway de pagamento.
+/// Permite trocar o provedor sem alterar a venda; em desenvolvimento é usado o `GatewayMock`.
+pub trait GatewayPagamento: Send + Sync {
+    /// Nome do gateway, gravado junto de cada pagamento.
+    fn nome(&self) -> &str;
+    /// Cria a cobrança. Cartões são autorizados (ou recusados) na hora;
+    /// Pix e boleto ficam pendentes até o webhook confirmar o pagamento.
+    fn autorizar<'a>(&'a self, cobranca: &'a Cobranca) -> BoxFuture<'a, Result<Autorizacao, String>>;
+    /// Captura o valor de uma cobrança autorizada.
+    fn capturar<'a>(&'a self, referencia: &'a str, valor: &'a BigDecimal) -> BoxFuture<'a, Result<(), String>>;
+    /// Devolve ao cliente o valor de uma cobrança paga.
+    fn estornar<'a>(&'a self, referencia: &'a str, valor: &'a BigDecimal) -> BoxFuture<'a, Result<(), String>>;
+    /// Confere a assinatura de uma chamada de webhook e interpreta o evento.
+    fn verificar_webhook(&self, corpo: &[u8], assinatura: Option<&str>) -> Result<EventoPagamento, String>;
+    /// Confere a assinatura de uma notificação de Pix recebido (formato da API Pix do Banco Central)
+    /// e devolve os pagamentos informados. O `txid` de cada Pix é a referência da cobrança.
+    fn verificar_webhook_pix(&self, corpo: &[u8], assinatura: Option<&str>) -> Result<Vec<PixRecebido>, String>;
+}
+
+/// Situação de uma cobrança no gateway simulado.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum EstadoCobrancaMock {
+    Pendente,
+    Autorizada,
+    Paga,
+    Estornada,
+}
+
+/// Gateway simulado, em memória, para desenvolvimento e testes.
+///
+/// - Cartões com token terminado em "0002" são recusados; os demais são autorizados.
+///   Com token terminado em "0003", a captura falha (para simular a anulação da autorização).
+/// - Pix gera um payload "copia e cola" real, com o txid como referência da cobrança.
+/// - Pix e boleto ficam pendentes até um webhook `{"referencia": "...", "evento": "pago"}`
+///   (ou, para Pix, `{"pix": [{"endToEndId": "...", "txid": "...", "valor": "...", "horario": "..."}]}`),
+///   assinado com HMAC-SHA256 da chave do webhook no cabeçalho `X-Assinatura`.
+pub struct GatewayMock {
+    chave_webhook: String,
+    pix: ConfiguracaoPix,
+    cobrancas: Mutex<HashMap<String, EstadoCobrancaMock>>,
+    falhar_captura: Mutex<HashSet<String>>, // Referências cuja captura falha
+}
+
+impl GatewayMock {
+    pub fn new(chave_webhook: impl Into<String>, pix: ConfiguracaoPix) -> Self {
+        GatewayMock {
+            chave_webhook: chave_webhook.into(),
+            pix,
+            cobrancas: Mutex::new(HashMap::new()),
+            falhar_captura: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Confere o HMAC do corpo de um webhook e devolve o corpo como texto.
+    fn conferir_assinatura<'a>(&self, corpo: &'a [u8], assinatura: Option<&str>) -> Result<&'a str, String> {
+        let corpo = std::str::from_utf8(corpo).map_err(|_| "corpo do webhook não é UTF-8".to_string())?;
+        match assinatura {
+            Some(assinatura) if assinatura_valida(&self.chave_webhook, corpo, assinatura) => Ok(corpo),
+            _ => Err("assinatura do webhook inválida".to_string()),
+        }
+    }
+
+    /// Troca o estado de uma cobrança, se ela estiver em um dos estados esperados.
+    fn transicionar(&self, referencia: &str, de: &[EstadoCobrancaMock], para: EstadoCobrancaMock) -> Result<(), String> {
+        let mut cobrancas = self.cobrancas.lock().map_err(|e| e.to_string())?;
+        match cobrancas.get_mut(referencia) {
+            Some(estado) if de.contains(estado) => {
+                *estado = para;
+                Ok(())
+            },
+            Some(estado) => Err(format!("cobrança {} está {:?}", referencia, estado)),
+            None => Err(format!("cobrança {} desconhecida", referencia)),
+        }
+    }
+}
+
+impl GatewayPagamento for GatewayMock {
+    fn nome(&self) -> &str {
+        "mock"
+    }
+
+    fn autorizar<'a>(&'a self, cobranca: &'a Cobranca) -> BoxFuture<'a, Result<Autorizacao, String>> {
+        // O txid do Pix aceita apenas letras e dígitos
+        let referencia = match cobranca.metodo {
+            MetodoPagamento::Pix => gerar_token()[..TAMANHO_MAXIMO_TXID].to_string(),
+            _ => format!("mock_{}", &gerar_token()[..24]),
+        };
+        let (estado, resultado, instrucoes) = match cobranca.metodo {
+            MetodoPagamento::Cartao => match cobranca.token_cartao.as_deref() {
+                Some(token) if token.ends_with("0002") => {
+                    (None, ResultadoAutorizacao::Recusado("Cartão recusado pelo emissor.".to_string()), None)
+                },
+                Some(_) => (Some(EstadoCobrancaMock::Autorizada), ResultadoAutorizacao::Autorizado, None),
+                None => return Box::pin(future::ready(Err("token do cartão ausente".to_string()))),
+            },
+            MetodoPagamento::Pix => (
+                Some(EstadoCobrancaMock::Pendente),
+                ResultadoAutorizacao::Pendente,
+                Some(serde_json::json!({ "copia_e_cola": gerar_payload(&self.pix, &referencia, &cobranca.valor) })),
+            ),
+            MetodoPagamento::Boleto => (
+                Some(EstadoCobrancaMock::Pendente),
+                ResultadoAutorizacao::Pendente,
+                Some(serde_json::json!({
+                    "linha_digitavel": format!("00190.00009 {} 1 {}", &referencia[5..15], cobranca.venda_id),
+                    "vencimento": cobranca.expira_em.date_naive().to_string(),
+                })),
+            ),
+        };
+
+        if cobranca.token_cartao.as_deref().is_some_and(|token| token.ends_with("0003")) {
+            if let Ok(mut falhar_captura) = self.falhar_captura.lock() {
+                falhar_captura.insert(referencia.clone());
+            }
+        }
+        if let Some(estado) = estado {
+            match self.cobrancas.lock() {
+                Ok(mut cobrancas) => {
+                    cobrancas.insert(referencia.clone(), estado);
+                },
+                Err(e) => return Box::pin(future::ready(Err(e.to_string()))),
+            }
+        }
+        Box::pin(future::ready(Ok(Autorizacao { referencia, resultado, instrucoes })))
+    }
+
+    fn capturar<'a>(&'a self, referencia: &'a str, _valor: &'a BigDecimal) -> BoxFuture<'a, Result<(), String>> {
+        if self.falhar_captura.lock().map(|falhar| falhar.contains(referencia)).unwrap_or(false) {
+            return Box::pin(future::ready(Err(format!("tempo esgotado na captura da cobrança {}", referencia))));
+        }
+        let resultado = self.transicionar(referencia, &[EstadoCobrancaMock::Autorizada], EstadoCobrancaMock::Paga);
+        Box::pin(future::ready(resultado))
+    }
+
+    fn estornar<'a>(&'a self, referencia: &'a str, _valor: &'a BigDecimal) -> BoxFuture<'a, Result<(), String>> {
+        let resultado = self.transicionar(
+            referencia,
+            &[EstadoCobrancaMock::Autorizada, EstadoCobrancaMock::Paga],
+            EstadoCobrancaMock::Estornada,
+        );
+        Box::pin(future::ready(resultado))
+    }
+
+    fn verificar_webhook(&self, corpo: &[u8], assinatura: Option<&str>) -> Result<EventoPagamento, String> {
+        let corpo = self.conferir_assinatura(corpo, assinatura)?;
+        let evento: EventoPagamento = serde_json::from_str(corpo).map_err(|e| e.to_string())?;
+        if evento.evento == TipoEventoPagamento::Pago {
+            // Idempotente: um evento repetido encontra a cobrança já paga
+            let _ = self.transicionar(&evento.referencia, &[EstadoCobrancaMock::Pendente], EstadoCobrancaMock::Paga);
+        }
+        Ok(evento)
+    }
+
+    fn verificar_webhook_pix(&self, corpo: &[u8], assinatura: Option<&str>) -> Result<Vec<PixRecebido>, String> {
+        let corpo = self.conferir_assinatura(corpo, assinatura)?;
+        let notificacao: NotificacaoPix = serde_json::from_str(corpo).map_err(|e| e.to_string())?;
+        for pix in &notificacao.pix {
+            let _ = self.transicionar(&pix.txid, &[EstadoCobrancaMock::Pendente], EstadoCobrancaMock::Paga);
+        }
+        Ok(notificacao.pix)
+    }
+}
@@ -0,0 +1,14 @@
+// src/pagamentos/mod.rs
+
+// Declara o submódulo que contém as definições das structs de pagamentos
+pub mod pagamentos_structs;
+// Declara o submódulo com a abstração do gateway de pagamento e o gateway simulado
+pub mod gateway;
+// Declara o submódulo com a confirmação, o cancelamento e a liberação de reservas
+pub mod pagamentos_service;
+// Declara o submódulo que contém as funções de rota de pagamentos
+pub mod pagamentos_router;
+// Declara o submódulo que gera o QR code do "copia e cola" do Pix
+pub mod qrcode;
+// Declara o submódulo que monta o payload "copia e cola" do Pix
+pub mod pix;
@@ -0,0 +1,616 @@
+// src/pagamentos/pagamentos_router.rs
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use bigdecimal::BigDecimal;
+use sqlx::Row;
+use std::str::FromStr;
+
+// Importa as structs de pagamentos
+use super::pagamentos_structs::{MetodoPagamento, PixRecebido, ResultadoPixRecebido, SituacaoPagamento, TipoEventoPagamento};
+// Importa a confirmação, o cancelamento e a devolução de estoque
+use super::pagamentos_service::{
+    confirmar_pagamento, devolver_estoque, estornar_pagamento_tardio, liberar_reserva_estorno, registrar_end_to_end_pix,
+    registrar_falha_pagamento, ResultadoConfirmacao, ResultadoFalha,
+};
+// Importa o gerador de QR code
+use super::qrcode::QrCode;
+// Importa o extrator de administrador
+use crate::usuarios::auth_middleware::AdminUser;
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::registrar_auditoria;
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
+// Importa a situação dos pedidos
+use crate::vendas::vendas_structs::StatusVenda;
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+
+/// Rota chamada pelo gateway de pagamento para notificar pagamentos e falhas.
+/// A assinatura do corpo (cabeçalho `X-Assinatura`) é conferida pelo próprio gateway.
+/// Pagamentos que chegam depois do cancelamento do pedido são estornados automaticamente.
+#[post("/pagamentos/webhook")]
+pub async fn receber_webhook_pagamento(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    corpo: web::Bytes,
+) -> HttpResponse {
+    let assinatura = req.headers().get("X-Assinatura").and_then(|v| v.to_str().ok());
+    let evento = match data.gateway_pagamento.verificar_webhook(&corpo, assinatura) {
+        Ok(evento) => evento,
+        Err(e) => {
+            eprintln!("Webhook de pagamento rejeitado: {}", e);
+            return HttpResponse::Unauthorized().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Webhook inválido.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    match evento.evento {
+        TipoEventoPagamento::Pago => match confirmar_pagamento(&data.db_pool, &evento.referencia).await {
+            Ok(ResultadoConfirmacao::Confirmado(venda_id)) => HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Pagamento confirmado para o pedido {}.", venda_id),
+                body: None,
+            }),
+            Ok(ResultadoConfirmacao::JaProcessado) => HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: "Pagamento já processado.".to_string(),
+                body: None,
+            }),
+            Ok(ResultadoConfirmacao::VendaCancelada { valor }) => {
+                estornar_pagamento_de_pedido_cancelado(&data, &evento.referencia, &valor).await
+            },
+            Ok(ResultadoConfirmacao::NaoEncontrado) => HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Pagamento {} não encontrado.", evento.referencia),
+                body: None,
+            }),
+            Err(e) => {
+                eprintln!("Erro ao confirmar pagamento {}: {:?}", evento.referencia, e);
+                HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "Erro ao confirmar pagamento.".to_string(),
+                    body: None,
+                })
+            }
+        },
+        TipoEventoPagamento::Falhou => match registrar_falha_pagamento(&data.db_pool, &evento.referencia).await {
+            Ok(ResultadoFalha::Cancelado(venda_id)) => HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Falha de pagamento registrada; pedido {} cancelado.", venda_id),
+                body: None,
+            }),
+            Ok(ResultadoFalha::SemEfeito(situacao)) => {
+                // Respondido com 200 para o gateway não reenviar um evento que não se aplica mais
+                eprintln!("Falha notificada para o pagamento {} em situação '{}'; ignorada.", evento.referencia, situacao);
+                HttpResponse::Ok().json(GenericResponse::<()>{
+                    status: "success".to_string(),
+                    message: format!("Falha ignorada: o pagamento {} está '{}'.", evento.referencia, situacao),
+                    body: None,
+                })
+            },
+            Ok(ResultadoFalha::NaoEncontrado) => HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Pagamento {} não encontrado.", evento.referencia),
+                body: None,
+            }),
+            Err(e) => {
+                eprintln!("Erro ao registrar falha do pagamento {}: {:?}", evento.referencia, e);
+                HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "Erro ao registrar falha de pagamento.".to_string(),
+                    body: None,
+                })
+            }
+        },
+    }
+}
+
+/// Rota chamada pelo banco (PSP) a cada Pix recebido, no formato da API Pix do Banco Central.
+/// O `txid` identifica a cobrança; o valor pago precisa ser igual ao cobrado para confirmar o pedido.
+/// Reenvios são idempotentes, e Pix que chegam depois do cancelamento do pedido são estornados.
+#[post("/pagamentos/pix/webhook")]
+pub async fn receber_webhook_pix(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    corpo: web::Bytes,
+) -> HttpResponse {
+    let assinatura = req.headers().get("X-Assinatura").and_then(|v| v.to_str().ok());
+    let recebidos = match data.gateway_pagamento.verificar_webhook_pix(&corpo, assinatura) {
+        Ok(recebidos) => recebidos,
+        Err(e) => {
+            eprintln!("Webhook Pix rejeitado: {}", e);
+            return HttpResponse::Unauthorized().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Webhook inválido.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    let mut resultados = Vec::with_capacity(recebidos.len());
+    for pix in &recebidos {
+        match processar_pix_recebido(&data, pix).await {
+            Ok(resultado) => resultados.push(ResultadoPixRecebido { txid: pix.txid.clone(), resultado: resultado.to_string() }),
+            Err(e) => {
+                // Erro de banco: responde com falha para o banco reenviar a notificação
+                eprintln!("Erro ao processar Pix {} (txid {}): {:?}", pix.end_to_end_id, pix.txid, e);
+                return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "Erro ao processar notificação Pix.".to_string(),
+                    body: None,
+                });
+            }
+        }
+    }
+
+    HttpResponse::Ok().json(GenericResponse {
+        status: "success".to_string(),
+        message: format!("{} Pix processado(s).", resultados.len()),
+        body: Some(resultados),
+    })
+}
+
+/// Confere o valor de um Pix recebido e confirma o pedido correspondente.
+/// Retorna o resultado devolvido ao banco para aquele `txid`.
+async fn processar_pix_recebido(data: &web::Data<AppState>, pix: &PixRecebido) -> Result<&'static str, sqlx::Error> {
+    let cobrado = sqlx::query_scalar::<_, BigDecimal>("SELECT valor FROM pagamentos WHERE referencia = $1 AND metodo = $2")
+        .bind(&pix.txid)
+        .bind(MetodoPagamento::Pix.as_str())
+        .fetch_optional(&data.db_pool)
+        .await?;
+
+    let cobrado = match cobrado {
+        Some(cobrado) => cobrado,
+        None => return Ok("nao_encontrado"),
+    };
+    match BigDecimal::from_str(&pix.valor) {
+        Ok(pago) if pago == cobrado => {},
+        _ => {
+            // Não confirma o pedido; a diferença precisa ser tratada manualmente
+            eprintln!("Pix {} com valor {} diferente do cobrado ({}) no txid {}.", pix.end_to_end_id, pix.valor, cobrado, pix.txid);
+            return Ok("valor_divergente");
+        }
+    }
+
+    match confirmar_pagamento(&data.db_pool, &pix.txid).await? {
+        ResultadoConfirmacao::Confirmado(_) => {
+            registrar_end_to_end_pix(&data.db_pool, &pix.txid, &pix.end_to_end_id).await?;
+            Ok("confirmado")
+        },
+        ResultadoConfirmacao::JaProcessado => Ok("ja_processado"),
+        ResultadoConfirmacao::VendaCancelada { valor } => {
+            registrar_end_to_end_pix(&data.db_pool, &pix.txid, &pix.end_to_end_id).await?;
+            match estornar_pagamento_tardio(data, &pix.txid, &valor).await {
+                Ok(()) => Ok("estornado"),
+                Err(e) => {
+                    eprintln!("Erro ao estornar Pix {} de pedido cancelado: {}", pix.end_to_end_id, e);
+                    Ok("estorno_pendente")
+                }
+            }
+        },
+        ResultadoConfirmacao::NaoEncontrado => Ok("nao_encontrado"),
+    }
+}
+
+/// Rota que devolve o QR code (SVG) de uma cobrança Pix, para exibir ao cliente.
+/// A imagem é gerada a partir do "copia e cola" gravado no pagamento.
+#[get("/pagamentos/{referencia}/qrcode")]
+pub async fn qrcode_pix(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let referencia = path.into_inner();
+    let copia_e_cola = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT instrucoes->>'copia_e_cola' FROM pagamentos WHERE referencia = $1 AND metodo = $2"
+    )
+    .bind(&referencia)
+    .bind(MetodoPagamento::Pix.as_str())
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    let copia_e_cola = match copia_e_cola {
+        Ok(Some(Some(copia_e_cola))) => copia_e_cola,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Cobrança Pix {} não encontrada.", referencia),
+                body: None,
+            });
+        },
+        Err(e) => {
+            eprintln!("Erro ao buscar cobrança Pix {}: {:?}", referencia, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao gerar QR code.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    match QrCode::codificar(&copia_e_cola) {
+        Some(qr) => HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .insert_header(("Cache-Control", "private, max-age=1800"))
+            .body(qr.para_svg()),
+        None => {
+            eprintln!("Payload Pix da cobrança {} não cabe em um QR code.", referencia);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao gerar QR code.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Estorna um pagamento que chegou depois do pedido ser cancelado (ex.: reserva expirada).
+async fn estornar_pagamento_de_pedido_cancelado(data: &web::Data<AppState>, referencia: &str, valor: &BigDecimal) -> HttpResponse {
+    match estornar_pagamento_tardio(data, referencia, valor).await {
+        Ok(()) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Pedido já cancelado; pagamento estornado.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            // O pagamento fica como "pago" em um pedido cancelado, para estorno manual em /vendas/{id}/estorno
+            eprintln!("Erro ao estornar pagamento {} de pedido cancelado: {}", referencia, e);
+            HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: "Pedido já cancelado; o estorno automático falhou e deve ser feito manualmente.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Resultado da reserva do pagamento para estorno.
+enum ReservaEstorno {
+    Reservado { referencia: String, valor: BigDecimal },
+    NaoEncontrado,
+    NaoPermitido(String), // Status atual do pedido
+    SemPagamento,
+}
+
+/// Rota administrativa para estornar o pagamento de um pedido e cancelá-lo.
+/// Pedidos confirmados ou enviados são cancelados (o estoque volta se ainda não foi enviado);
+/// em pedidos já cancelados com pagamento tardio, só o pagamento é estornado. A alteração é auditada.
+#[post("/vendas/{id}/estorno")]
+pub async fn estornar_venda(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+
+    // 1. Confere o status do pedido e reserva o pagamento como "estornando", para que
+    //    chamadas simultâneas não estornem o mesmo valor duas vezes no gateway
+    let reserva: Result<ReservaEstorno, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let status = sqlx::query_scalar::<_, String>("SELECT status FROM vendas WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        let status = match status {
+            Some(status) => status,
+            None => return Ok(ReservaEstorno::NaoEncontrado),
+        };
+        match StatusVenda::de_texto(&status) {
+            Some(atual) if atual.cancelavel_por_estorno() || atual == StatusVenda::Cancelado => {},
+            _ => return Ok(ReservaEstorno::NaoPermitido(status)),
+        }
+
+        let row = sqlx::query(
+            "UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE venda_id = $2 AND situacao = $3 RETURNING referencia, valor"
+        )
+        .bind(SituacaoPagamento::Estornando.as_str())
+        .bind(id)
+        .bind(SituacaoPagamento::Pago.as_str())
+        .fetch_optional(&mut *transaction)
+        .await?;
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(ReservaEstorno::SemPagamento),
+        };
+        let reserva = ReservaEstorno::Reservado { referencia: row.try_get("referencia")?, valor: row.try_get("valor")? };
+
+        transaction.commit().await?;
+        Ok(reserva)
+    }
+    .await;
+
+    let (referencia, valor) = match reserva {
+        Ok(ReservaEstorno::Reservado { referencia, valor }) => (referencia, valor),
+        Ok(ReservaEstorno::NaoEncontrado) => {
+            return HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Pedido com ID {} não encontrado.", id),
+                body: None,
+            });
+        },
+        Ok(ReservaEstorno::NaoPermitido(atual)) => {
+            return HttpResponse::Conflict().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Não é possível estornar o pedido {} com status '{}'.", id, atual),
+                body: None,
+            });
+        },
+        Ok(ReservaEstorno::SemPagamento) => {
+            return HttpResponse::Conflict().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("O pedido {} não tem pagamento confirmado para estornar.", id),
+                body: None,
+            });
+        },
+        Err(e) => {
+            eprintln!("Erro ao reservar estorno do pedido {}: {:?}", id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao estornar pedido.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    // 2. Estorna no gateway; se falhar, o pagamento volta a "pago" para nova tentativa
+    if let Err(e) = data.gateway_pagamento.estornar(&referencia, &valor).await {
+        eprintln!("Erro ao estornar pagamento {} no gateway: {}", referencia, e);
+        liberar_reserva_estorno(&data.db_pool, &referencia).await;
+        return HttpResponse::BadGateway().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "O gateway de pagamento não concluiu o estorno.".to_string(),
+            body: None,
+        });
+    }
+
+    // 3. Conclui o estorno e, se o pedido ainda estava ativo, cancela-o, devolve o estoque
+    //    se ainda não foi enviado e audita. O status não muda enquanto o pagamento está
+    //    "estornando", pois a rota de status recusa alterações nesse intervalo.
+    let resultado: Result<bool, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let row = sqlx::query("SELECT status, to_jsonb(v) AS antes FROM vendas v WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_one(&mut *transaction)
+            .await?;
+        let status: String = row.try_get("status")?;
+        let antes: serde_json::Value = row.try_get("antes")?;
+
+        sqlx::query("UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE referencia = $2")
+            .bind(SituacaoPagamento::Estornado.as_str())
+            .bind(&referencia)
+            .execute(&mut *transaction)
+            .await?;
+
+        let cancelar = StatusVenda::de_texto(&status).is_some_and(StatusVenda::cancelavel_por_estorno);
+        if cancelar {
+            if status == StatusVenda::Confirmado.as_str() {
+                devolver_estoque(&mut transaction, id).await?;
+            }
+            let depois = sqlx::query_scalar::<_, serde_json::Value>(
+                "UPDATE vendas SET status = $1, reserva_expira_em = NULL WHERE id = $2 RETURNING to_jsonb(vendas)"
+            )
+            .bind(StatusVenda::Cancelado.as_str())
+            .bind(id)
+            .fetch_one(&mut *transaction)
+            .await?;
+            registrar_auditoria(&mut transaction, &admin.0, "venda", id, AcaoAuditoria::AlteracaoStatus, Some(antes), Some(depois)).await?;
+        }
+
+        transaction.commit().await?;
+        Ok(cancelar)
+    }
+    .await;
+
+    match resultado {
+        Ok(cancelado) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: if cancelado {
+                format!("Pagamento do pedido {} estornado e pedido cancelado.", id)
+            } else {
+                format!("Pagamento do pedido {} estornado; o pedido já estava cancelado.", id)
+            },
+            body: None,
+        }),
+        Err(e) => {
+            // O dinheiro já foi devolvido pelo gateway; o registro local precisa ser corrigido
+            eprintln!("Erro ao registrar estorno do pedido {} (estorno {} já feito no gateway): {:?}", id, referencia, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Estorno feito no gateway, mas houve erro ao atualizar o pedido.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use chrono::{Duration, Utc};
+
+    use crate::pagamentos::pagamentos_service::processar_pagamento;
+    use crate::pagamentos::pagamentos_structs::Cobranca;
+    use crate::shared::testes::{autorizacao_de_admin, criar_usuario, estado_de_teste, gateway_de_teste, CHAVE_WEBHOOK_TESTE};
+    use crate::shared::tokens::assinar;
+
+    /// Cria um pedido pendente, sem itens, com o método de pagamento informado.
+    async fn venda_pendente(data: &web::Data<AppState>, valor: i32, metodo: MetodoPagamento) -> Cobranca {
+        let usuario_id = criar_usuario(data).await;
+        let expira_em = Utc::now() + Duration::minutes(30);
+        let venda_id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO vendas (usuario_id, total, status, metodo_pagamento, reserva_expira_em) VALUES ($1, $2, $3, $4, $5) RETURNING id"
+        )
+        .bind(usuario_id)
+        .bind(BigDecimal::from(valor))
+        .bind(StatusVenda::Pendente.as_str())
+        .bind(metodo.as_str())
+        .bind(expira_em)
+        .fetch_one(&data.db_pool)
+        .await
+        .unwrap();
+        Cobranca { venda_id, valor: BigDecimal::from(valor), metodo, token_cartao: None, expira_em }
+    }
+
+    /// Cria um pedido pendente cobrado por Pix no `GatewayMock` e devolve o ID do pedido e o txid.
+    async fn pedido_pix_pendente(data: &web::Data<AppState>, valor: i32) -> (i32, String) {
+        let cobranca = venda_pendente(data, valor, MetodoPagamento::Pix).await;
+        match processar_pagamento(data, &cobranca).await {
+            Ok(pagamento) => (cobranca.venda_id, pagamento.referencia),
+            Err(_) => panic!("Falha ao criar a cobrança Pix do pedido {}", cobranca.venda_id),
+        }
+    }
+
+    /// Cria um pedido pago com cartão (capturado no `GatewayMock`) e devolve o ID do pedido.
+    async fn pedido_cartao_confirmado(data: &web::Data<AppState>, valor: i32) -> i32 {
+        let mut cobranca = venda_pendente(data, valor, MetodoPagamento::Cartao).await;
+        cobranca.token_cartao = Some("tok_4242".to_string());
+        match processar_pagamento(data, &cobranca).await {
+            Ok(_) => cobranca.venda_id,
+            Err(_) => panic!("Falha ao cobrar o cartão do pedido {}", cobranca.venda_id),
+        }
+    }
+
+    /// Notificação Pix no formato da API do Banco Central.
+    fn notificacao(txid: &str, valor: &str) -> String {
+        serde_json::json!({
+            "pix": [{ "endToEndId": format!("E2E{}", txid), "txid": txid, "valor": valor, "horario": "2026-10-18T12:00:00Z" }]
+        })
+        .to_string()
+    }
+
+    /// Status do pedido e situação do pagamento.
+    async fn situacao(data: &web::Data<AppState>, venda_id: i32) -> (String, String) {
+        let row = sqlx::query("SELECT v.status, p.situacao FROM vendas v JOIN pagamentos p ON p.venda_id = v.id WHERE v.id = $1")
+            .bind(venda_id)
+            .fetch_one(&data.db_pool)
+            .await
+            .unwrap();
+        (row.get("status"), row.get("situacao"))
+    }
+
+    #[actix_web::test]
+    async fn webhook_pix_confirma_o_pedido_e_rejeita_assinatura_ou_valor_errados() {
+        let data = match estado_de_teste(gateway_de_teste()).await {
+            Some(data) => data,
+            None => return,
+        };
+        let app = test::init_service(App::new().app_data(data.clone()).service(receber_webhook_pix)).await;
+        let (venda_id, txid) = pedido_pix_pendente(&data, 59).await;
+        let enviar = |corpo: String, assinatura: Option<String>| {
+            let mut req = test::TestRequest::post().uri("/pagamentos/pix/webhook").set_payload(corpo);
+            if let Some(assinatura) = assinatura {
+                req = req.insert_header(("X-Assinatura", assinatura));
+            }
+            req.to_request()
+        };
+        let resultado = |resposta: serde_json::Value| resposta["body"][0]["resultado"].as_str().unwrap().to_string();
+
+        // Assinatura de outra chave, ou ausente: 401 e nada muda
+        let corpo = notificacao(&txid, "59.00");
+        let resposta = test::call_service(&app, enviar(corpo.clone(), Some(assinar("outra_chave", &corpo)))).await;
+        assert_eq!(resposta.status(), 401);
+        let resposta = test::call_service(&app, enviar(corpo.clone(), None)).await;
+        assert_eq!(resposta.status(), 401);
+        assert_eq!(situacao(&data, venda_id).await, ("pendente".to_string(), "pendente".to_string()));
+
+        // Valor diferente do cobrado: aceito pelo banco, mas o pedido não é confirmado
+        let divergente = notificacao(&txid, "58.99");
+        let assinatura = assinar(CHAVE_WEBHOOK_TESTE, &divergente);
+        let resposta: serde_json::Value = test::call_and_read_body_json(&app, enviar(divergente, Some(assinatura))).await;
+        assert_eq!(resultado(resposta), "valor_divergente");
+        assert_eq!(situacao(&data, venda_id).await, ("pendente".to_string(), "pendente".to_string()));
+
+        // Valor e assinatura corretos: pedido pago; o reenvio é idempotente
+        let assinatura = assinar(CHAVE_WEBHOOK_TESTE, &corpo);
+        let resposta: serde_json::Value = test::call_and_read_body_json(&app, enviar(corpo.clone(), Some(assinatura.clone()))).await;
+        assert_eq!(resultado(resposta), "confirmado");
+        assert_eq!(situacao(&data, venda_id).await, ("confirmado".to_string(), "pago".to_string()));
+        let resposta: serde_json::Value = test::call_and_read_body_json(&app, enviar(corpo, Some(assinatura))).await;
+        assert_eq!(resultado(resposta), "ja_processado");
+    }
+
+    #[actix_web::test]
+    async fn webhook_de_falha_cancela_so_pagamentos_que_aguardam_o_gateway() {
+        let data = match estado_de_teste(gateway_de_teste()).await {
+            Some(data) => data,
+            None => return,
+        };
+        let app = test::init_service(App::new().app_data(data.clone()).service(receber_webhook_pagamento)).await;
+        let falhou = |referencia: &str| {
+            let corpo = serde_json::json!({ "referencia": referencia, "evento": "falhou" }).to_string();
+            let assinatura = assinar(CHAVE_WEBHOOK_TESTE, &corpo);
+            test::TestRequest::post().uri("/pagamentos/webhook").insert_header(("X-Assinatura", assinatura)).set_payload(corpo).to_request()
+        };
+        let mensagem = |resposta: serde_json::Value| resposta["message"].as_str().unwrap().to_string();
+
+        // Pix pendente: pedido cancelado; o reenvio do evento não tem efeito e diz isso
+        let (venda_id, txid) = pedido_pix_pendente(&data, 40).await;
+        let resposta: serde_json::Value = test::call_and_read_body_json(&app, falhou(&txid)).await;
+        assert!(mensagem(resposta).contains("cancelado"));
+        assert_eq!(situacao(&data, venda_id).await, ("cancelado".to_string(), "recusado".to_string()));
+        let resposta: serde_json::Value = test::call_and_read_body_json(&app, falhou(&txid)).await;
+        assert!(mensagem(resposta).starts_with("Falha ignorada"));
+
+        // Cartão em captura: o gateway confirma que não cobrou, e o pedido é cancelado
+        let (venda_id, referencia) = pedido_pix_pendente(&data, 40).await;
+        sqlx::query("UPDATE pagamentos SET situacao = $1 WHERE referencia = $2")
+            .bind(SituacaoPagamento::Capturando.as_str())
+            .bind(&referencia)
+            .execute(&data.db_pool)
+            .await
+            .unwrap();
+        let resposta: serde_json::Value = test::call_and_read_body_json(&app, falhou(&referencia)).await;
+        assert!(mensagem(resposta).contains("cancelado"));
+        assert_eq!(situacao(&data, venda_id).await, ("cancelado".to_string(), "recusado".to_string()));
+
+        // Pedido pago: a falha tardia é ignorada
+        let venda_id = pedido_cartao_confirmado(&data, 40).await;
+        let referencia = sqlx::query_scalar::<_, String>("SELECT referencia FROM pagamentos WHERE venda_id = $1")
+            .bind(venda_id)
+            .fetch_one(&data.db_pool)
+            .await
+            .unwrap();
+        let resposta: serde_json::Value = test::call_and_read_body_json(&app, falhou(&referencia)).await;
+        assert!(mensagem(resposta).starts_with("Falha ignorada"));
+        assert_eq!(situacao(&data, venda_id).await, ("confirmado".to_string(), "pago".to_string()));
+    }
+
+    #[actix_web::test]
+    async fn estorno_simultaneo_devolve_o_valor_uma_vez_e_respeita_o_status() {
+        let data = match estado_de_teste(gateway_de_teste()).await {
+            Some(data) => data,
+            None => return,
+        };
+        let app = test::init_service(App::new().app_data(data.clone()).service(estornar_venda)).await;
+        let admin = autorizacao_de_admin(&data).await;
+        let estornar = |venda_id: i32| {
+            test::TestRequest::post().uri(&format!("/vendas/{}/estorno", venda_id)).insert_header(admin.clone()).to_request()
+        };
+
+        // Duas chamadas ao mesmo tempo: só uma reserva o pagamento e chega ao gateway
+        let venda_id = pedido_cartao_confirmado(&data, 80).await;
+        let (a, b) = futures::join!(test::call_service(&app, estornar(venda_id)), test::call_service(&app, estornar(venda_id)));
+        let mut codigos = [a.status().as_u16(), b.status().as_u16()];
+        codigos.sort();
+        assert_eq!(codigos, [200, 409]);
+        assert_eq!(situacao(&data, venda_id).await, ("cancelado".to_string(), "estornado".to_string()));
+
+        // Pedido entregue: o estorno não reescreve o status e o pagamento continua pago
+        let venda_id = pedido_cartao_confirmado(&data, 80).await;
+        sqlx::query("UPDATE vendas SET status = $1 WHERE id = $2")
+            .bind(StatusVenda::Entregue.as_str())
+            .bind(venda_id)
+            .execute(&data.db_pool)
+            .await
+            .unwrap();
+        let resposta = test::call_service(&app, estornar(venda_id)).await;
+        assert_eq!(resposta.status(), 409);
+        assert_eq!(situacao(&data, venda_id).await, ("entregue".to_string(), "pago".to_string()));
+    }
+}
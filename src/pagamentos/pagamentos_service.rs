@@ -0,0 +1,440 @@
+// src/pagamentos/pagamentos_service.rs
+
+use actix_web::web;
+use bigdecimal::BigDecimal;
+use chrono::Duration;
+use sqlx::{query, PgConnection, Pool, Postgres, Row};
+
+use super::pagamentos_structs::{
+    Autorizacao, Cobranca, MetodoPagamento, PagamentoResponse, ResultadoAutorizacao, SituacaoPagamento,
+};
+use crate::vendas::vendas_structs::StatusVenda;
+use crate::estoque::estoque_service::enfileirar_avisos_estoque;
+use crate::AppState;
+
+/// Intervalo entre as varreduras de reservas expiradas.
+const INTERVALO_LIBERACAO_RESERVAS_SEGUNDOS: u64 = 60;
+
+/// Por quanto tempo o estoque fica reservado aguardando o pagamento, conforme o método.
+pub fn validade_reserva(metodo: MetodoPagamento) -> Duration {
+    match metodo {
+        MetodoPagamento::Pix => Duration::minutes(30),
+        MetodoPagamento::Boleto => Duration::days(3),
+        MetodoPagamento::Cartao => Duration::minutes(15),
+    }
+}
+
+/// Falhas ao processar o pagamento de um pedido recém-criado.
+#[derive(Debug)]
+pub enum FalhaPagamento {
+    Recusado(String),
+    Gateway(String),       // O pedido foi cancelado
+    EmConferencia(String), // A captura e a anulação falharam: o pedido fica pendente até a conferência
+    Banco(sqlx::Error),
+}
+
+impl From<sqlx::Error> for FalhaPagamento {
+    fn from(e: sqlx::Error) -> Self {
+        FalhaPagamento::Banco(e)
+    }
+}
+
+/// Resultado de uma falha de pagamento notificada pelo gateway.
+pub enum ResultadoFalha {
+    Cancelado(i32), // ID do pedido cancelado
+    /// O pagamento já não aguardava o gateway (pago, estornado, recusado...); nada muda.
+    SemEfeito(String),
+    NaoEncontrado,
+}
+
+/// Resultado da confirmação de um pagamento recebida do gateway.
+pub enum ResultadoConfirmacao {
+    Confirmado(i32),
+    JaProcessado,
+    /// O pedido foi cancelado (ex.: reserva expirada) antes do pagamento chegar; o valor deve ser estornado.
+    VendaCancelada { valor: BigDecimal },
+    NaoEncontrado,
+}
+
+/// Cobra o pedido no gateway logo após a venda ser registrada como pendente.
+///
+/// Cartões autorizados são capturados na hora e confirmam o pedido. Pix e boleto ficam
+/// pendentes até o webhook. Se a cobrança for recusada ou falhar, o pedido é cancelado
+/// e o estoque reservado é devolvido.
+///
+/// O cartão autorizado é gravado como "capturando" antes da captura. Se a captura falhar, a cobrança
+/// é anulada no gateway antes de cancelar o pedido; se a anulação também falhar, ou se a confirmação
+/// falhar depois da captura, o pedido continua pendente com o pagamento "capturando", que a liberação
+/// das reservas expiradas não cancela, até a conferência manual ou o webhook do gateway.
+pub async fn processar_pagamento(data: &web::Data<AppState>, cobranca: &Cobranca) -> Result<PagamentoResponse, FalhaPagamento> {
+    let gateway = &data.gateway_pagamento;
+
+    let mut autorizacao = match gateway.autorizar(cobranca).await {
+        Ok(autorizacao) => autorizacao,
+        Err(e) => {
+            cancelar_venda_pendente(&data.db_pool, cobranca.venda_id, SituacaoPagamento::Recusado).await?;
+            return Err(FalhaPagamento::Gateway(e));
+        }
+    };
+
+    // Para Pix, o cliente também recebe o endereço da imagem do QR code
+    if let (MetodoPagamento::Pix, Some(serde_json::Value::Object(instrucoes))) = (cobranca.metodo, autorizacao.instrucoes.as_mut()) {
+        let url = format!("{}/pagamentos/{}/qrcode", data.url_publica, autorizacao.referencia);
+        instrucoes.insert("qr_code_url".to_string(), serde_json::Value::String(url));
+    }
+
+    let situacao = match &autorizacao.resultado {
+        ResultadoAutorizacao::Autorizado => SituacaoPagamento::Capturando,
+        ResultadoAutorizacao::Pendente => SituacaoPagamento::Pendente,
+        ResultadoAutorizacao::Recusado(_) => SituacaoPagamento::Recusado,
+    };
+    if let Err(e) = registrar_pagamento(&data.db_pool, gateway.nome(), cobranca, &autorizacao, situacao).await {
+        // Sem o registro não há como conferir a cobrança depois: a autorização é desfeita, e o
+        // pedido, ainda pendente sem pagamento, é cancelado pela expiração da reserva
+        if situacao == SituacaoPagamento::Capturando {
+            anular_cobranca_cartao(data, &autorizacao.referencia, &cobranca.valor).await;
+        }
+        return Err(FalhaPagamento::Banco(e));
+    }
+
+    let situacao = match autorizacao.resultado {
+        ResultadoAutorizacao::Recusado(motivo) => {
+            cancelar_venda_pendente(&data.db_pool, cobranca.venda_id, SituacaoPagamento::Recusado).await?;
+            return Err(FalhaPagamento::Recusado(motivo));
+        },
+        ResultadoAutorizacao::Pendente => SituacaoPagamento::Pendente,
+        ResultadoAutorizacao::Autorizado => {
+            if let Err(e) = gateway.capturar(&autorizacao.referencia, &cobranca.valor).await {
+                // A captura pode ter falhado depois de cobrar (ex.: tempo esgotado); o estorno desfaz os dois casos
+                if !anular_cobranca_cartao(data, &autorizacao.referencia, &cobranca.valor).await {
+                    return Err(FalhaPagamento::EmConferencia(e));
+                }
+                atualizar_situacao_pagamento(&data.db_pool, &autorizacao.referencia, SituacaoPagamento::Estornado).await?;
+                cancelar_venda_pendente(&data.db_pool, cobranca.venda_id, SituacaoPagamento::Recusado).await?;
+                return Err(FalhaPagamento::Gateway(e));
+            }
+            if let ResultadoConfirmacao::VendaCancelada { valor } = confirmar_pagamento(&data.db_pool, &autorizacao.referencia).await? {
+                // O gateway notificou falha (webhook) durante a captura e o pedido foi cancelado
+                if let Err(e) = estornar_pagamento_tardio(data, &autorizacao.referencia, &valor).await {
+                    eprintln!("Erro ao estornar a captura {} de pedido já cancelado: {}", autorizacao.referencia, e);
+                    return Err(FalhaPagamento::EmConferencia(e));
+                }
+                return Err(FalhaPagamento::Gateway("pedido cancelado por falha notificada pelo gateway".to_string()));
+            }
+            SituacaoPagamento::Pago
+        },
+    };
+
+    Ok(PagamentoResponse {
+        metodo: cobranca.metodo,
+        situacao,
+        referencia: autorizacao.referencia,
+        instrucoes: autorizacao.instrucoes,
+        expira_em: cobranca.expira_em,
+    })
+}
+
+/// Desfaz no gateway a autorização (ou a captura) de um cartão cujo processamento não foi concluído.
+/// Retorna `false` se o gateway também falhar; o pagamento fica então para conferência manual.
+async fn anular_cobranca_cartao(data: &web::Data<AppState>, referencia: &str, valor: &BigDecimal) -> bool {
+    match data.gateway_pagamento.estornar(referencia, valor).await {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Erro ao anular a cobrança {} no gateway; confira e estorne manualmente: {}", referencia, e);
+            false
+        }
+    }
+}
+
+/// Grava a cobrança criada no gateway.
+async fn registrar_pagamento(
+    pool: &Pool<Postgres>,
+    gateway: &str,
+    cobranca: &Cobranca,
+    autorizacao: &Autorizacao,
+    situacao: SituacaoPagamento,
+) -> Result<(), sqlx::Error> {
+    query(
+        r#"
+        INSERT INTO pagamentos (venda_id, gateway, referencia, metodo, valor, situacao, instrucoes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#
+    )
+    .bind(cobranca.venda_id)
+    .bind(gateway)
+    .bind(&autorizacao.referencia)
+    .bind(cobranca.metodo.as_str())
+    .bind(&cobranca.valor)
+    .bind(situacao.as_str())
+    .bind(&autorizacao.instrucoes)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Marca o pagamento como pago e confirma o pedido, que passa a contar como vendido.
+/// Chamadas repetidas (webhooks reenviados) não têm efeito.
+pub async fn confirmar_pagamento(pool: &Pool<Postgres>, referencia: &str) -> Result<ResultadoConfirmacao, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let row = query(
+        r#"
+        SELECT p.venda_id, p.situacao, p.valor, v.status
+        FROM pagamentos p JOIN vendas v ON v.id = p.venda_id
+        WHERE p.referencia = $1
+        FOR UPDATE OF p, v
+        "#
+    )
+    .bind(referencia)
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(ResultadoConfirmacao::NaoEncontrado),
+    };
+    let venda_id: i32 = row.try_get("venda_id")?;
+    let situacao: String = row.try_get("situacao")?;
+    let status: String = row.try_get("status")?;
+
+    // Um pagamento em estorno já foi pago; confirmá-lo de novo desfaria a reserva do estorno
+    let ja_processado = [SituacaoPagamento::Pago, SituacaoPagamento::Estornando, SituacaoPagamento::Estornado];
+    if ja_processado.iter().any(|s| s.as_str() == situacao) {
+        return Ok(ResultadoConfirmacao::JaProcessado);
+    }
+
+    query("UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE referencia = $2")
+        .bind(SituacaoPagamento::Pago.as_str())
+        .bind(referencia)
+        .execute(&mut *transaction)
+        .await?;
+
+    let resultado = if status == StatusVenda::Pendente.as_str() {
+        query("UPDATE vendas SET status = $1, reserva_expira_em = NULL WHERE id = $2")
+            .bind(StatusVenda::Confirmado.as_str())
+            .bind(venda_id)
+            .execute(&mut *transaction)
+            .await?;
+        ResultadoConfirmacao::Confirmado(venda_id)
+    } else {
+        ResultadoConfirmacao::VendaCancelada { valor: row.try_get("valor")? }
+    };
+
+    transaction.commit().await?;
+    Ok(resultado)
+}
+
+/// Estorna um pagamento que chegou depois do pedido ser cancelado (ex.: reserva expirada).
+/// Se o gateway falhar, o pagamento fica como "pago" em um pedido cancelado, para estorno manual.
+/// O pagamento é reservado como "estornando" antes do gateway, para que um estorno manual
+/// simultâneo (/vendas/{id}/estorno) não devolva o mesmo valor duas vezes.
+pub async fn estornar_pagamento_tardio(data: &web::Data<AppState>, referencia: &str, valor: &BigDecimal) -> Result<(), String> {
+    let reservado = query("UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE referencia = $2 AND situacao = $3")
+        .bind(SituacaoPagamento::Estornando.as_str())
+        .bind(referencia)
+        .bind(SituacaoPagamento::Pago.as_str())
+        .execute(&data.db_pool)
+        .await
+        .map_err(|e| format!("Erro ao reservar estorno: {:?}", e))?;
+    if reservado.rows_affected() == 0 {
+        // Outro estorno já reservou ou concluiu este pagamento
+        return Ok(());
+    }
+
+    if let Err(e) = data.gateway_pagamento.estornar(referencia, valor).await {
+        liberar_reserva_estorno(&data.db_pool, referencia).await;
+        return Err(e);
+    }
+    if let Err(e) = atualizar_situacao_pagamento(&data.db_pool, referencia, SituacaoPagamento::Estornado).await {
+        eprintln!("Erro ao marcar pagamento {} como estornado: {:?}", referencia, e);
+    }
+    Ok(())
+}
+
+/// Devolve para "pago" um pagamento reservado para estorno que o gateway não estornou,
+/// para que o estorno possa ser tentado de novo.
+pub async fn liberar_reserva_estorno(pool: &Pool<Postgres>, referencia: &str) {
+    let resultado = query("UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE referencia = $2 AND situacao = $3")
+        .bind(SituacaoPagamento::Pago.as_str())
+        .bind(referencia)
+        .bind(SituacaoPagamento::Estornando.as_str())
+        .execute(pool)
+        .await;
+    if let Err(e) = resultado {
+        // O pagamento fica "estornando" e precisa ser conferido manualmente no gateway
+        eprintln!("Erro ao liberar reserva de estorno do pagamento {}: {:?}", referencia, e);
+    }
+}
+
+/// Guarda o identificador da transação Pix (endToEndId), usado na conciliação e em devoluções.
+pub async fn registrar_end_to_end_pix(pool: &Pool<Postgres>, referencia: &str, end_to_end_id: &str) -> Result<(), sqlx::Error> {
+    query("UPDATE pagamentos SET end_to_end_id = $1 WHERE referencia = $2")
+        .bind(end_to_end_id)
+        .bind(referencia)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Atualiza a situação de um pagamento pela referência do gateway.
+pub async fn atualizar_situacao_pagamento(
+    pool: &Pool<Postgres>,
+    referencia: &str,
+    situacao: SituacaoPagamento,
+) -> Result<(), sqlx::Error> {
+    query("UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE referencia = $2")
+        .bind(situacao.as_str())
+        .bind(referencia)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Registra uma falha notificada pelo gateway: o pagamento passa a "recusado" e o pedido
+/// pendente é cancelado, com o estoque devolvido. Vale também para cartões em "capturando",
+/// pois o gateway confirma que a captura não aconteceu; se a captura em andamento ainda
+/// concluir, `processar_pagamento` encontra o pedido cancelado e estorna o valor.
+pub async fn registrar_falha_pagamento(pool: &Pool<Postgres>, referencia: &str) -> Result<ResultadoFalha, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let row = query(
+        r#"
+        SELECT p.venda_id, p.situacao, v.status
+        FROM pagamentos p JOIN vendas v ON v.id = p.venda_id
+        WHERE p.referencia = $1
+        FOR UPDATE OF p, v
+        "#
+    )
+    .bind(referencia)
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(ResultadoFalha::NaoEncontrado),
+    };
+    let venda_id: i32 = row.try_get("venda_id")?;
+    let situacao: String = row.try_get("situacao")?;
+    let status: String = row.try_get("status")?;
+
+    let aguardando = situacao == SituacaoPagamento::Pendente.as_str() || situacao == SituacaoPagamento::Capturando.as_str();
+    if !aguardando || status != StatusVenda::Pendente.as_str() {
+        return Ok(ResultadoFalha::SemEfeito(situacao));
+    }
+
+    cancelar_na_transacao(&mut transaction, venda_id, SituacaoPagamento::Recusado).await?;
+    // cancelar_na_transacao só altera pagamentos pendentes; um cartão em captura é marcado aqui
+    query("UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE referencia = $2")
+        .bind(SituacaoPagamento::Recusado.as_str())
+        .bind(referencia)
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+    Ok(ResultadoFalha::Cancelado(venda_id))
+}
+
+/// Cancela um pedido ainda pendente e devolve o estoque reservado.
+/// Retorna `false` se o pedido não estava pendente (já pago ou já cancelado).
+pub async fn cancelar_venda_pendente(
+    pool: &Pool<Postgres>,
+    venda_id: i32,
+    situacao_pagamento: SituacaoPagamento,
+) -> Result<bool, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let cancelada = cancelar_na_transacao(&mut transaction, venda_id, situacao_pagamento).await?;
+    transaction.commit().await?;
+    Ok(cancelada)
+}
+
+/// Cancelamento de pedido pendente usando a transação de quem chama.
+pub(crate) async fn cancelar_na_transacao(
+    conn: &mut PgConnection,
+    venda_id: i32,
+    situacao_pagamento: SituacaoPagamento,
+) -> Result<bool, sqlx::Error> {
+    let res = query("UPDATE vendas SET status = $1, reserva_expira_em = NULL WHERE id = $2 AND status = $3")
+        .bind(StatusVenda::Cancelado.as_str())
+        .bind(venda_id)
+        .bind(StatusVenda::Pendente.as_str())
+        .execute(&mut *conn)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    devolver_estoque(conn, venda_id).await?;
+    query("UPDATE pagamentos SET situacao = $1, atualizado_em = NOW() WHERE venda_id = $2 AND situacao = 'pendente'")
+        .bind(situacao_pagamento.as_str())
+        .bind(venda_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(true)
+}
+
+/// Devolve ao estoque as quantidades dos itens de um pedido.
+/// Produtos que estavam esgotados enfileiram os avisos dos clientes inscritos no "avise-me".
+pub async fn devolver_estoque(conn: &mut PgConnection, venda_id: i32) -> Result<(), sqlx::Error> {
+    // Só os produtos que saíram de zero avisam; os que já tinham estoque não mudam de situação
+    let produto_ids = sqlx::query_scalar::<_, i32>(
+        r#"
+        WITH devolvido AS (
+            UPDATE produtos p SET estoque = p.estoque + vi.quantidade, versao = p.versao + 1
+            FROM vendas_itens vi
+            WHERE vi.venda_id = $1 AND vi.produto_id = p.id
+            RETURNING p.id, p.estoque - vi.quantidade AS estoque_anterior
+        )
+        SELECT id FROM devolvido WHERE estoque_anterior = 0
+        "#
+    )
+    .bind(venda_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    enfileirar_avisos_estoque(conn, &produto_ids).await?;
+    Ok(())
+}
+
+/// Cancela os pedidos cuja reserva expirou sem pagamento, devolvendo o estoque.
+/// `SKIP LOCKED` evita disputar com um webhook que esteja confirmando o mesmo pedido.
+/// Pedidos com cartão em captura ficam de fora: o valor pode já ter sido cobrado.
+pub async fn liberar_reservas_expiradas(pool: &Pool<Postgres>) -> Result<usize, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let ids = sqlx::query_scalar::<_, i32>(
+        r#"
+        SELECT id FROM vendas v
+        WHERE status = $1 AND reserva_expira_em < NOW()
+          AND NOT EXISTS (SELECT 1 FROM pagamentos p WHERE p.venda_id = v.id AND p.situacao = $2)
+        ORDER BY id
+        LIMIT 100
+        FOR UPDATE SKIP LOCKED
+        "#
+    )
+    .bind(StatusVenda::Pendente.as_str())
+    .bind(SituacaoPagamento::Capturando.as_str())
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    for venda_id in &ids {
+        cancelar_na_transacao(&mut transaction, *venda_id, SituacaoPagamento::Expirado).await?;
+    }
+
+    transaction.commit().await?;
+    Ok(ids.len())
+}
+
+/// Agenda a liberação periódica das reservas expiradas enquanto a aplicação estiver no ar.
+pub fn iniciar_liberacao_de_reservas(pool: Pool<Postgres>) {
+    actix_web::rt::spawn(async move {
+        let mut intervalo = actix_web::rt::time::interval(std::time::Duration::from_secs(INTERVALO_LIBERACAO_RESERVAS_SEGUNDOS));
+        loop {
+            intervalo.tick().await;
+            match liberar_reservas_expiradas(&pool).await {
+                Ok(0) => {},
+                Ok(quantidade) => println!("{} pedido(s) com reserva expirada cancelado(s) e estoque devolvido.", quantidade),
+                Err(e) => eprintln!("Erro ao liberar reservas expiradas: {:?}", e),
+            }
+        }
+    });
+}
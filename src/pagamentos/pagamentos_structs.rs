@@ -0,0 +1,128 @@
+// src/pagamentos/pagamentos_structs.rs
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Formas de pagamento aceitas no pedido.
+/// Persistidas como texto em `vendas.metodo_pagamento` e `pagamentos.metodo`.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MetodoPagamento {
+    Pix,
+    Boleto,
+    Cartao,
+}
+
+impl MetodoPagamento {
+    /// Valor gravado nas colunas de método de pagamento.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetodoPagamento::Pix => "pix",
+            MetodoPagamento::Boleto => "boleto",
+            MetodoPagamento::Cartao => "cartao",
+        }
+    }
+}
+
+/// Situações possíveis de um pagamento.
+/// Persistidas como texto na coluna `pagamentos.situacao`.
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SituacaoPagamento {
+    Pendente,   // Aguardando o cliente pagar (Pix, boleto)
+    Capturando, // Cartão autorizado e em captura: o valor pode já ter sido cobrado
+    Pago,
+    Recusado,
+    Estornando, // Estorno pedido ao gateway e ainda sem resposta
+    Estornado,
+    Expirado,   // Não foi pago dentro do prazo da reserva
+    Cancelado,  // O pedido foi cancelado pela loja antes do pagamento
+}
+
+impl SituacaoPagamento {
+    /// Valor gravado na coluna `pagamentos.situacao`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SituacaoPagamento::Pendente => "pendente",
+            SituacaoPagamento::Capturando => "capturando",
+            SituacaoPagamento::Pago => "pago",
+            SituacaoPagamento::Recusado => "recusado",
+            SituacaoPagamento::Estornando => "estornando",
+            SituacaoPagamento::Estornado => "estornado",
+            SituacaoPagamento::Expirado => "expirado",
+            SituacaoPagamento::Cancelado => "cancelado",
+        }
+    }
+}
+
+/// Dados de uma cobrança enviada ao gateway.
+pub struct Cobranca {
+    pub venda_id: i32,
+    pub valor: BigDecimal,
+    pub metodo: MetodoPagamento,
+    pub token_cartao: Option<String>, // Cartão tokenizado pelo gateway no navegador; nunca o número
+    pub expira_em: DateTime<Utc>,
+}
+
+/// Resultado da autorização de uma cobrança.
+pub enum ResultadoAutorizacao {
+    Autorizado,
+    Pendente,
+    Recusado(String), // Motivo informado pelo gateway
+}
+
+/// Resposta do gateway à autorização de uma cobrança.
+pub struct Autorizacao {
+    pub referencia: String, // Identificador da cobrança no gateway
+    pub resultado: ResultadoAutorizacao,
+    pub instrucoes: Option<serde_json::Value>, // Dados para o cliente pagar (código Pix, linha digitável)
+}
+
+/// Tipos de evento recebidos pelo webhook do gateway.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TipoEventoPagamento {
+    Pago,
+    Falhou,
+}
+
+/// Evento de pagamento já verificado, recebido pelo webhook.
+#[derive(Deserialize)]
+pub struct EventoPagamento {
+    pub referencia: String,
+    pub evento: TipoEventoPagamento,
+}
+
+/// Dados de pagamento devolvidos ao cliente ao finalizar a compra.
+#[derive(Serialize)]
+pub struct PagamentoResponse {
+    pub metodo: MetodoPagamento,
+    pub situacao: SituacaoPagamento,
+    pub referencia: String,
+    pub instrucoes: Option<serde_json::Value>,
+    pub expira_em: DateTime<Utc>,
+}
+
+/// Pix recebido, no formato do webhook da API Pix do Banco Central.
+#[derive(Deserialize)]
+pub struct PixRecebido {
+    #[serde(rename = "endToEndId")]
+    pub end_to_end_id: String, // Identificador da transação no SPI
+    pub txid: String,          // Identificador da cobrança, igual à referência do pagamento
+    pub valor: String,         // Valor pago, com duas casas decimais (ex.: "123.45")
+    pub horario: String,
+}
+
+/// Corpo do webhook Pix: uma notificação pode trazer vários pagamentos.
+#[derive(Deserialize)]
+pub struct NotificacaoPix {
+    pub pix: Vec<PixRecebido>,
+}
+
+/// Resultado do processamento de cada Pix de uma notificação.
+#[derive(Serialize)]
+pub struct ResultadoPixRecebido {
+    pub txid: String,
+    pub resultado: String,
+}
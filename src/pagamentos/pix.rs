@@ -0,0 +1,145 @@
+// src/pagamentos/pix.rs
+//
+// Payload "copia e cola" do Pix (BR Code), no formato EMV-MPM definido pelo Banco Central.
+// Cada campo é gravado como ID (2 dígitos) + tamanho (2 dígitos) + valor, e o payload
+// termina com o CRC16 de todo o conteúdo anterior.
+
+use bigdecimal::BigDecimal;
+
+/// Dados do recebedor, impressos no payload de todas as cobranças Pix.
+#[derive(Clone)]
+pub struct ConfiguracaoPix {
+    pub chave: String,          // Chave Pix da loja (e-mail, CNPJ, telefone ou aleatória)
+    pub nome_recebedor: String, // Até 25 caracteres
+    pub cidade: String,         // Até 15 caracteres
+}
+
+/// Tamanho máximo do txid aceito pelo BR Code (somente letras e dígitos).
+pub const TAMANHO_MAXIMO_TXID: usize = 25;
+
+/// Monta o payload "copia e cola" de uma cobrança Pix com valor fixo.
+/// O `txid` identifica a cobrança e volta no webhook quando o pagamento é recebido.
+pub fn gerar_payload(config: &ConfiguracaoPix, txid: &str, valor: &BigDecimal) -> String {
+    let conta = [campo("00", "br.gov.bcb.pix"), campo("01", &config.chave)].concat();
+    let txid: String = txid.chars().filter(char::is_ascii_alphanumeric).take(TAMANHO_MAXIMO_TXID).collect();
+
+    let mut payload = [
+        campo("00", "01"),                                         // Versão do payload
+        campo("01", "12"),                                         // QR de uso único
+        campo("26", &conta),                                       // Conta do recebedor
+        campo("52", "0000"),                                       // Categoria do comerciante
+        campo("53", "986"),                                        // Moeda: real
+        campo("54", &valor.with_scale(2).to_string()),             // Valor
+        campo("58", "BR"),                                         // País
+        campo("59", &normalizar(&config.nome_recebedor, 25)),      // Nome do recebedor
+        campo("60", &normalizar(&config.cidade, 15)),              // Cidade do recebedor
+        campo("62", &campo("05", &txid)),                          // Identificador da cobrança
+    ]
+    .concat();
+
+    payload.push_str("6304");
+    let crc = crc16(payload.as_bytes());
+    payload.push_str(&format!("{:04X}", crc));
+    payload
+}
+
+/// Grava um campo EMV: ID, tamanho com dois dígitos e valor.
+fn campo(id: &str, valor: &str) -> String {
+    format!("{}{:02}{}", id, valor.len(), valor)
+}
+
+/// Remove acentos e caracteres fora do ASCII e corta no tamanho máximo do campo.
+/// Alguns aplicativos bancários rejeitam nomes e cidades acentuados.
+fn normalizar(texto: &str, maximo: usize) -> String {
+    texto
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' | 'Á' | 'À' | 'Â' | 'Ã' | 'Ä' => 'A',
+            'é' | 'è' | 'ê' | 'ë' | 'É' | 'È' | 'Ê' | 'Ë' => 'E',
+            'í' | 'ì' | 'î' | 'ï' | 'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' | 'Ó' | 'Ò' | 'Ô' | 'Õ' | 'Ö' => 'O',
+            'ú' | 'ù' | 'û' | 'ü' | 'Ú' | 'Ù' | 'Û' | 'Ü' => 'U',
+            'ç' | 'Ç' => 'C',
+            'ñ' | 'Ñ' => 'N',
+            c => c.to_ascii_uppercase(),
+        })
+        .filter(|c| c.is_ascii() && !c.is_ascii_control())
+        .take(maximo)
+        .collect()
+}
+
+/// CRC16-CCITT (polinômio 0x1021, valor inicial 0xFFFF), exigido no campo 63 do BR Code.
+fn crc16(dados: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in dados {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Separa os campos de primeiro nível do payload (ID, valor).
+    fn campos(payload: &str) -> Vec<(String, String)> {
+        let mut resultado = Vec::new();
+        let mut resto = payload;
+        while !resto.is_empty() {
+            let tamanho: usize = resto[2..4].parse().unwrap();
+            resultado.push((resto[..2].to_string(), resto[4..4 + tamanho].to_string()));
+            resto = &resto[4 + tamanho..];
+        }
+        resultado
+    }
+
+    fn configuracao() -> ConfiguracaoPix {
+        ConfiguracaoPix {
+            chave: "pix@bellavibe.com.br".to_string(),
+            nome_recebedor: "Bellavibe Cosméticos e Perfumaria".to_string(),
+            cidade: "São José dos Campos".to_string(),
+        }
+    }
+
+    #[test]
+    fn crc16_confere_com_o_exemplo_do_manual_do_br_code() {
+        // Exemplo de QR estático do manual do BR Code (Banco Central), que termina em "63041D3D"
+        let exemplo = "00020126580014br.gov.bcb.pix0136123e4567-e12b-12d1-a456-4266554400005204000053039865802BR\
+                       5913Fulano de Tal6008BRASILIA62070503***6304";
+        assert_eq!(crc16(exemplo.as_bytes()), 0x1D3D);
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn payload_tem_os_campos_do_br_code_e_termina_com_o_crc() {
+        let payload = gerar_payload(&configuracao(), "venda-42_abc", &BigDecimal::from_str("89.9").unwrap());
+        let campos = campos(&payload);
+        let ids: Vec<&str> = campos.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["00", "01", "26", "52", "53", "54", "58", "59", "60", "62", "63"]);
+
+        let valor = |id: &str| campos.iter().find(|(campo, _)| campo == id).map(|(_, valor)| valor.as_str()).unwrap();
+        assert_eq!(valor("00"), "01");
+        assert_eq!(valor("26"), "0014br.gov.bcb.pix0120pix@bellavibe.com.br");
+        assert_eq!(valor("53"), "986");
+        assert_eq!(valor("54"), "89.90");
+        assert_eq!(valor("59"), "BELLAVIBE COSMETICOS E PE"); // Sem acentos e com 25 caracteres
+        assert_eq!(valor("60"), "SAO JOSE DOS CA");           // 15 caracteres
+        assert_eq!(valor("62"), "0510venda42abc");            // Só letras e dígitos no txid
+
+        let (conteudo, crc) = payload.split_at(payload.len() - 4);
+        assert!(conteudo.ends_with("6304"));
+        assert_eq!(crc, format!("{:04X}", crc16(conteudo.as_bytes())));
+    }
+
+    #[test]
+    fn txid_e_cortado_no_tamanho_maximo() {
+        let txid = "a".repeat(TAMANHO_MAXIMO_TXID + 10);
+        let payload = gerar_payload(&configuracao(), &txid, &BigDecimal::from(10));
+        let adicional = campos(&payload).into_iter().find(|(id, _)| id == "62").unwrap().1;
+        assert_eq!(adicional, format!("0525{}", "a".repeat(TAMANHO_MAXIMO_TXID)));
+    }
+}
@@ -0,0 +1,484 @@
+// src/pagamentos/qrcode.rs
+//
+// Geração de QR codes (ISO/IEC 18004) para o "copia e cola" do Pix.
+//
+// Implementação enxuta: modo byte, nível de correção M (o recomendado pelo manual do
+// BR Code), escolha automática da versão (1 a 40) e da máscara. A saída é um SVG,
+// que pode ser exibido direto no navegador.
+
+/// Nível de correção M: palavras de correção por bloco, por versão (índice 0 não usado).
+const CORRECAO_POR_BLOCO: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28, 28, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+/// Nível de correção M: quantidade de blocos, por versão (índice 0 não usado).
+const BLOCOS_DE_CORRECAO: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23, 25, 26, 28, 29,
+    31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+/// Bits do nível de correção M no campo de formato.
+const BITS_NIVEL_M: u32 = 0;
+
+/// Margem (em módulos) ao redor do símbolo, exigida pelos leitores.
+const MARGEM: usize = 4;
+
+/// Matriz de um QR code: `true` é módulo escuro.
+pub struct QrCode {
+    tamanho: usize,
+    modulos: Vec<Vec<bool>>,
+    funcao: Vec<Vec<bool>>, // Módulos de padrões fixos, que a máscara não altera
+}
+
+impl QrCode {
+    /// Codifica um texto. Retorna `None` se não couber na versão 40.
+    pub fn codificar(texto: &str) -> Option<QrCode> {
+        let dados = texto.as_bytes();
+        let versao = (1..=40).find(|&v| bits_necessarios(v, dados.len()) <= palavras_de_dados(v) * 8)?;
+
+        let mut qr = QrCode::vazio(versao);
+        qr.desenhar_padroes(versao);
+        let palavras = intercalar_com_correcao(versao, &montar_palavras_de_dados(versao, dados));
+        qr.desenhar_palavras(&palavras);
+
+        // Escolhe a máscara com menor penalidade
+        let mut melhor = (u32::MAX, 0);
+        for mascara in 0..8 {
+            qr.aplicar_mascara(mascara);
+            qr.desenhar_formato(mascara);
+            let penalidade = qr.penalidade();
+            if penalidade < melhor.0 {
+                melhor = (penalidade, mascara);
+            }
+            qr.aplicar_mascara(mascara); // XOR desfaz a máscara
+        }
+        qr.aplicar_mascara(melhor.1);
+        qr.desenhar_formato(melhor.1);
+        Some(qr)
+    }
+
+    /// Gera o SVG do QR code, com fundo branco e a margem obrigatória.
+    pub fn para_svg(&self) -> String {
+        let lado = self.tamanho + MARGEM * 2;
+        let mut caminho = String::new();
+        for (y, linha) in self.modulos.iter().enumerate() {
+            for (x, &escuro) in linha.iter().enumerate() {
+                if escuro {
+                    caminho.push_str(&format!("M{},{}h1v1h-1z", x + MARGEM, y + MARGEM));
+                }
+            }
+        }
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {lado} {lado}\" shape-rendering=\"crispEdges\">\
+             <rect width=\"100%\" height=\"100%\" fill=\"#FFFFFF\"/><path d=\"{caminho}\" fill=\"#000000\"/></svg>"
+        )
+    }
+
+    fn vazio(versao: usize) -> QrCode {
+        let tamanho = versao * 4 + 17;
+        QrCode { tamanho, modulos: vec![vec![false; tamanho]; tamanho], funcao: vec![vec![false; tamanho]; tamanho] }
+    }
+
+    fn definir_funcao(&mut self, x: usize, y: usize, escuro: bool) {
+        self.modulos[y][x] = escuro;
+        self.funcao[y][x] = true;
+    }
+
+    /// Desenha localizadores, temporização, alinhamento, versão e reserva a área de formato.
+    fn desenhar_padroes(&mut self, versao: usize) {
+        let tamanho = self.tamanho;
+        for i in 0..tamanho {
+            self.definir_funcao(6, i, i % 2 == 0);
+            self.definir_funcao(i, 6, i % 2 == 0);
+        }
+
+        for (cx, cy) in [(3, 3), (tamanho - 4, 3), (3, tamanho - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..tamanho as i32).contains(&x) && (0..tamanho as i32).contains(&y) {
+                        let distancia = dx.abs().max(dy.abs());
+                        self.definir_funcao(x as usize, y as usize, distancia != 2 && distancia != 4);
+                    }
+                }
+            }
+        }
+
+        let posicoes = posicoes_de_alinhamento(versao);
+        let ultima = posicoes.len().saturating_sub(1);
+        let cantos = [(0, 0), (0, ultima), (ultima, 0)];
+        for (i, &cy) in posicoes.iter().enumerate() {
+            for (j, &cx) in posicoes.iter().enumerate() {
+                // Os cantos já estão ocupados pelos localizadores
+                if cantos.contains(&(i, j)) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let escuro = dx.abs().max(dy.abs()) != 1;
+                        self.definir_funcao((cx as i32 + dx) as usize, (cy as i32 + dy) as usize, escuro);
+                    }
+                }
+            }
+        }
+
+        self.desenhar_formato(0); // Reserva a área; o valor final é gravado após escolher a máscara
+
+        if versao >= 7 {
+            let mut resto = versao as u32;
+            for _ in 0..12 {
+                resto = (resto << 1) ^ ((resto >> 11) * 0x1F25);
+            }
+            let bits = (versao as u32) << 12 | resto;
+            for i in 0..18 {
+                let escuro = (bits >> i) & 1 == 1;
+                let (a, b) = (tamanho - 11 + i % 3, i / 3);
+                self.definir_funcao(a, b, escuro);
+                self.definir_funcao(b, a, escuro);
+            }
+        }
+    }
+
+    /// Grava as duas cópias do campo de formato (nível de correção e máscara).
+    fn desenhar_formato(&mut self, mascara: u32) {
+        let dados = BITS_NIVEL_M << 3 | mascara;
+        let mut resto = dados;
+        for _ in 0..10 {
+            resto = (resto << 1) ^ ((resto >> 9) * 0x537);
+        }
+        let bits = (dados << 10 | resto) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+        let tamanho = self.tamanho;
+
+        for i in 0..6 {
+            self.definir_funcao(8, i, bit(i));
+        }
+        self.definir_funcao(8, 7, bit(6));
+        self.definir_funcao(8, 8, bit(7));
+        self.definir_funcao(7, 8, bit(8));
+        for i in 9..15 {
+            self.definir_funcao(14 - i, 8, bit(i));
+        }
+
+        for i in 0..8 {
+            self.definir_funcao(tamanho - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.definir_funcao(8, tamanho - 15 + i, bit(i));
+        }
+        self.definir_funcao(8, tamanho - 8, true); // Módulo sempre escuro
+    }
+
+    /// Distribui as palavras em zigue-zague, de baixo para cima, em colunas de duas.
+    fn desenhar_palavras(&mut self, palavras: &[u8]) {
+        let tamanho = self.tamanho;
+        let total_bits = palavras.len() * 8;
+        let mut i = 0;
+        let mut direita = tamanho - 1;
+        loop {
+            if direita == 6 {
+                direita = 5; // Pula a coluna de temporização
+            }
+            for vertical in 0..tamanho {
+                for j in 0..2 {
+                    let x = direita - j;
+                    let subindo = (direita + 1) & 2 == 0;
+                    let y = if subindo { tamanho - 1 - vertical } else { vertical };
+                    if !self.funcao[y][x] && i < total_bits {
+                        self.modulos[y][x] = (palavras[i >> 3] >> (7 - (i & 7))) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if direita < 2 {
+                break;
+            }
+            direita -= 2;
+        }
+    }
+
+    /// Inverte os módulos de dados que atendem à condição da máscara.
+    fn aplicar_mascara(&mut self, mascara: u32) {
+        for y in 0..self.tamanho {
+            for x in 0..self.tamanho {
+                let inverter = match mascara {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if inverter && !self.funcao[y][x] {
+                    self.modulos[y][x] = !self.modulos[y][x];
+                }
+            }
+        }
+    }
+
+    /// Penalidade da norma para a escolha da máscara: sequências, blocos 2x2,
+    /// padrões parecidos com localizadores e desequilíbrio entre claro e escuro.
+    fn penalidade(&self) -> u32 {
+        let tamanho = self.tamanho;
+        let mut total = 0;
+        let linhas: Vec<Vec<bool>> = self.modulos.clone();
+        let colunas: Vec<Vec<bool>> = (0..tamanho).map(|x| (0..tamanho).map(|y| self.modulos[y][x]).collect()).collect();
+        const LOCALIZADOR: [bool; 11] = [true, false, true, true, true, false, true, false, false, false, false];
+
+        for linha in linhas.iter().chain(colunas.iter()) {
+            let mut sequencia = 1;
+            for i in 1..=tamanho {
+                if i < tamanho && linha[i] == linha[i - 1] {
+                    sequencia += 1;
+                } else {
+                    if sequencia >= 5 {
+                        total += 3 + (sequencia - 5) as u32;
+                    }
+                    sequencia = 1;
+                }
+            }
+            for janela in linha.windows(11) {
+                if janela == LOCALIZADOR || janela.iter().rev().eq(LOCALIZADOR.iter()) {
+                    total += 40;
+                }
+            }
+        }
+
+        for y in 0..tamanho - 1 {
+            for x in 0..tamanho - 1 {
+                let cor = self.modulos[y][x];
+                if cor == self.modulos[y][x + 1] && cor == self.modulos[y + 1][x] && cor == self.modulos[y + 1][x + 1] {
+                    total += 3;
+                }
+            }
+        }
+
+        let escuros = self.modulos.iter().flatten().filter(|&&m| m).count();
+        let modulos = tamanho * tamanho;
+        let k = (escuros * 20).abs_diff(modulos * 10).div_ceil(modulos).saturating_sub(1);
+        total + k as u32 * 10
+    }
+}
+
+/// Bits do contador de caracteres no modo byte, conforme a versão.
+fn bits_do_contador(versao: usize) -> usize {
+    if versao <= 9 { 8 } else { 16 }
+}
+
+/// Bits ocupados pelos dados (modo, contador e conteúdo) em uma versão.
+fn bits_necessarios(versao: usize, bytes: usize) -> usize {
+    let contador_cabe = bytes < 1 << bits_do_contador(versao);
+    if contador_cabe { 4 + bits_do_contador(versao) + bytes * 8 } else { usize::MAX }
+}
+
+/// Módulos disponíveis para dados e correção, descontados os padrões fixos.
+fn modulos_de_dados(versao: usize) -> usize {
+    let mut resultado = (16 * versao + 128) * versao + 64;
+    if versao >= 2 {
+        let alinhamentos = versao / 7 + 2;
+        resultado -= (25 * alinhamentos - 10) * alinhamentos - 55;
+        if versao >= 7 {
+            resultado -= 36;
+        }
+    }
+    resultado
+}
+
+/// Palavras (bytes) de dados de uma versão no nível M.
+fn palavras_de_dados(versao: usize) -> usize {
+    modulos_de_dados(versao) / 8 - CORRECAO_POR_BLOCO[versao] * BLOCOS_DE_CORRECAO[versao]
+}
+
+/// Centros dos padrões de alinhamento, em ordem crescente.
+fn posicoes_de_alinhamento(versao: usize) -> Vec<usize> {
+    if versao == 1 {
+        return Vec::new();
+    }
+    let quantidade = versao / 7 + 2;
+    let passo = (versao * 8 + quantidade * 3 + 5) / (quantidade * 4 - 4) * 2;
+    let tamanho = versao * 4 + 17;
+    let mut posicoes: Vec<usize> = (0..quantidade - 1).map(|i| tamanho - 7 - i * passo).collect();
+    posicoes.push(6);
+    posicoes.reverse();
+    posicoes
+}
+
+/// Monta as palavras de dados: modo byte, contador, conteúdo, terminador e preenchimento.
+fn montar_palavras_de_dados(versao: usize, dados: &[u8]) -> Vec<u8> {
+    let capacidade = palavras_de_dados(versao) * 8;
+    let mut bits: Vec<bool> = Vec::with_capacity(capacidade);
+    let anexar = |valor: u32, quantidade: usize, bits: &mut Vec<bool>| {
+        for i in (0..quantidade).rev() {
+            bits.push((valor >> i) & 1 == 1);
+        }
+    };
+
+    anexar(0b0100, 4, &mut bits);
+    anexar(dados.len() as u32, bits_do_contador(versao), &mut bits);
+    for &byte in dados {
+        anexar(byte as u32, 8, &mut bits);
+    }
+    let terminador = (capacidade - bits.len()).min(4);
+    anexar(0, terminador, &mut bits);
+    let alinhamento = (8 - bits.len() % 8) % 8;
+    anexar(0, alinhamento, &mut bits);
+
+    let mut palavras: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, &bit| acc << 1 | bit as u8))
+        .collect();
+    for preenchimento in [0xEC, 0x11].into_iter().cycle() {
+        if palavras.len() * 8 >= capacidade {
+            break;
+        }
+        palavras.push(preenchimento);
+    }
+    palavras
+}
+
+/// Divide os dados em blocos, calcula a correção Reed-Solomon de cada um e intercala tudo.
+fn intercalar_com_correcao(versao: usize, dados: &[u8]) -> Vec<u8> {
+    let blocos = BLOCOS_DE_CORRECAO[versao];
+    let correcao = CORRECAO_POR_BLOCO[versao];
+    let total = modulos_de_dados(versao) / 8;
+    let blocos_curtos = blocos - total % blocos;
+    let tamanho_curto = total / blocos;
+    let divisor = divisor_reed_solomon(correcao);
+
+    let mut separados: Vec<Vec<u8>> = Vec::with_capacity(blocos);
+    let mut inicio = 0;
+    for i in 0..blocos {
+        let fim = inicio + tamanho_curto - correcao + usize::from(i >= blocos_curtos);
+        let mut bloco = dados[inicio..fim].to_vec();
+        inicio = fim;
+        let resto = resto_reed_solomon(&bloco, &divisor);
+        if i < blocos_curtos {
+            bloco.push(0); // Posição vazia, ignorada na intercalação
+        }
+        bloco.extend(resto);
+        separados.push(bloco);
+    }
+
+    let mut resultado = Vec::with_capacity(total);
+    for i in 0..separados[0].len() {
+        for (j, bloco) in separados.iter().enumerate() {
+            if i != tamanho_curto - correcao || j >= blocos_curtos {
+                resultado.push(bloco[i]);
+            }
+        }
+    }
+    resultado
+}
+
+/// Multiplicação no corpo GF(2^8) com o polinômio 0x11D.
+fn multiplicar_gf(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    z as u8
+}
+
+/// Polinômio gerador de Reed-Solomon com o grau informado (sem o termo de maior grau).
+fn divisor_reed_solomon(grau: usize) -> Vec<u8> {
+    let mut resultado = vec![0u8; grau];
+    resultado[grau - 1] = 1;
+    let mut raiz = 1u8;
+    for _ in 0..grau {
+        for j in 0..grau {
+            resultado[j] = multiplicar_gf(resultado[j], raiz);
+            if j + 1 < grau {
+                resultado[j] ^= resultado[j + 1];
+            }
+        }
+        raiz = multiplicar_gf(raiz, 0x02);
+    }
+    resultado
+}
+
+/// Resto da divisão dos dados pelo polinômio gerador: as palavras de correção.
+fn resto_reed_solomon(dados: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut resultado = vec![0u8; divisor.len()];
+    for &byte in dados {
+        let fator = byte ^ resultado.remove(0);
+        resultado.push(0);
+        for (r, &coeficiente) in resultado.iter_mut().zip(divisor) {
+            *r ^= multiplicar_gf(coeficiente, fator);
+        }
+    }
+    resultado
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Campo de formato do nível M para cada máscara, conforme a tabela da ISO/IEC 18004.
+    const FORMATO_NIVEL_M: [u32; 8] = [0x5412, 0x5125, 0x5E7C, 0x5B4B, 0x45F9, 0x40CE, 0x4F97, 0x4AA0];
+
+    /// Lê as duas cópias do campo de formato gravadas no símbolo.
+    fn ler_formato(qr: &QrCode) -> (u32, u32) {
+        let tamanho = qr.tamanho;
+        let modulo = |x: usize, y: usize| qr.modulos[y][x] as u32;
+        let mut posicoes_primeira: Vec<(usize, usize)> = (0..6).map(|i| (8, i)).collect();
+        posicoes_primeira.extend([(8, 7), (8, 8), (7, 8)]);
+        posicoes_primeira.extend((9..15).map(|i| (14 - i, 8)));
+        let mut posicoes_segunda: Vec<(usize, usize)> = (0..8).map(|i| (tamanho - 1 - i, 8)).collect();
+        posicoes_segunda.extend((8..15).map(|i| (8, tamanho - 15 + i)));
+
+        let ler = |posicoes: &[(usize, usize)]| posicoes.iter().enumerate().fold(0, |acc, (i, &(x, y))| acc | modulo(x, y) << i);
+        (ler(&posicoes_primeira), ler(&posicoes_segunda))
+    }
+
+    #[test]
+    fn capacidade_do_nivel_m_segue_a_norma() {
+        assert_eq!(palavras_de_dados(1), 16);
+        assert_eq!(palavras_de_dados(2), 28);
+        assert_eq!(palavras_de_dados(10), 216);
+        assert_eq!(palavras_de_dados(40), 2334);
+    }
+
+    #[test]
+    fn escolhe_a_menor_versao_que_comporta_o_texto() {
+        assert_eq!(QrCode::codificar(&"a".repeat(14)).unwrap().tamanho, 21); // Versão 1
+        assert_eq!(QrCode::codificar(&"a".repeat(15)).unwrap().tamanho, 25); // Versão 2
+        assert_eq!(QrCode::codificar(&"a".repeat(2331)).unwrap().tamanho, 177); // Versão 40
+        assert!(QrCode::codificar(&"a".repeat(2332)).is_none());
+    }
+
+    #[test]
+    fn correcao_reed_solomon_confere_com_o_exemplo_conhecido() {
+        // Palavras de dados de "HELLO WORLD" em 1-M e a correção correspondente
+        let dados = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+        let correcao = resto_reed_solomon(&dados, &divisor_reed_solomon(10));
+        assert_eq!(correcao, [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]);
+    }
+
+    #[test]
+    fn campo_de_formato_indica_nivel_m_nas_duas_copias() {
+        for texto in ["bellavibe", "00020126580014br.gov.bcb.pix0136123e4567-e12b-12d1-a456-426655440000"] {
+            let qr = QrCode::codificar(texto).unwrap();
+            let (primeira, segunda) = ler_formato(&qr);
+            assert_eq!(primeira, segunda);
+            assert!(FORMATO_NIVEL_M.contains(&primeira), "formato inesperado: {:015b}", primeira);
+            assert!(qr.modulos[qr.tamanho - 8][8]); // Módulo sempre escuro
+        }
+    }
+
+    #[test]
+    fn padroes_fixos_estao_no_lugar() {
+        let qr = QrCode::codificar("bellavibe").unwrap();
+        for i in 8..qr.tamanho - 8 {
+            assert_eq!(qr.modulos[6][i], i % 2 == 0); // Temporização horizontal
+            assert_eq!(qr.modulos[i][6], i % 2 == 0); // Temporização vertical
+        }
+        for (x, y) in [(0, 0), (qr.tamanho - 7, 0), (0, qr.tamanho - 7)] {
+            assert!(qr.modulos[y][x] && qr.modulos[y + 3][x + 3] && !qr.modulos[y + 1][x + 1]);
+        }
+        assert!(qr.para_svg().contains("viewBox=\"0 0 29 29\"")); // 21 módulos e a margem
+    }
+}
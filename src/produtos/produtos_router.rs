@@ -1,32 +1,72 @@
 // src/produtos/produtos_router.rs
 
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sqlx::{query_as, query, Row}; // Importa 'query' também para UPDATE/DELETE
+use actix_web::{get, post, put, patch, delete, http::header, web, HttpRequest, HttpResponse, Responder};
+use sqlx::{query_as, query}; // Importa 'query' também para UPDATE/DELETE
 use serde_json;
 
 // Importa as structs específicas de produtos
 use super::produtos_structs::{
     NovoProduto,    
+    AtualizacaoParcialProduto,
     ProdutoResponse,    
     ProdutoRawData,
+    ProdutoExcluido,
+    FiltroProdutos,
+    OrdenacaoProdutos,
+    ListagemProdutos,
 };
+// Importa os filtros por atributo e a contagem das facetas
+use crate::atributos::atributos_service::{contar_facetas, interpretar_filtros, CondicoesProdutos, FalhaFiltro};
 
 // Importa GenericResponse do novo módulo shared_structs
 use crate::shared::shared_structs::GenericResponse;
+// Importa os auxiliares de concorrência otimista (ETag/If-Match)
+use crate::shared::concorrencia::{etag_da_versao, versao_do_if_match, conflito_de_versao};
+// Importa a execução única por chave de idempotência
+use crate::shared::idempotencia::com_idempotencia;
+// Importa o redirecionamento de slugs antigos
+use crate::shared::slugs::redirecionar_para_slug;
 
 // Importa o AppState do módulo raiz (main.rs)
 use crate::AppState;
 
-// Importa o extrator de autenticação
-use crate::usuarios::auth_middleware::AuthenticatedUser; 
+// Importa os extratores de autenticação e autorização
+use crate::usuarios::auth_middleware::{AuthenticatedUser, AdminUser};
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::executar_com_auditoria;
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
 
+/// Consulta base dos produtos no formato de `ProdutoRawData`: junta os nomes da categoria
+/// e da marca (produtos de marca excluída aparecem sem marca), o resumo das avaliações aprovadas e os atributos. Quem usa acrescenta WHERE e ORDER BY
+/// (e pode acrescentar JOINs antes deles).
+pub(crate) const CONSULTA_PRODUTOS: &str = r#"
+    SELECT
+        p.id, p.nome, p.slug, p.descricao, p.preco, p.estoque, p.categoria_id,
+        c.nome AS categoria_nome, m.id AS marca_id, m.nome AS marca_nome, p.peso_gramas, p.altura_cm, p.largura_cm, p.comprimento_cm, p.versao,
+        av.media AS avaliacao_media, COALESCE(av.total, 0) AS total_avaliacoes,
+        COALESCE(at.atributos, '{}'::jsonb) AS atributos
+    FROM produtos p
+    JOIN categorias c ON p.categoria_id = c.id
+    LEFT JOIN marcas m ON m.id = p.marca_id AND m.deleted_at IS NULL
+    LEFT JOIN (
+        SELECT produto_id, ROUND(AVG(nota), 2) AS media, COUNT(*) AS total
+        FROM avaliacoes WHERE situacao = 'aprovada' GROUP BY produto_id
+    ) av ON av.produto_id = p.id
+    LEFT JOIN produtos_atributos_json at ON at.produto_id = p.id
+"#;
 
 /// Rota para buscar todos os produtos no banco de dados.
-/// Retorna uma GenericResponse com a lista de produtos, incluindo o nome da categoria.
+/// Retorna uma GenericResponse com a lista de produtos, incluindo o nome da categoria,
+/// a média e quantidade de avaliações aprovadas e os atributos, junto com as facetas.
+/// Aceita `ordenar=avaliacao` para listar primeiro os produtos mais bem avaliados,
+/// `categoria_id` e `marca_id` para restringir a uma categoria (e subcategorias) ou marca e filtros por atributo,
+/// como `?vegano=true&tipo_pele=oleosa&tipo_pele=mista&volume=100..300`.
 #[get("/produtos")]
 pub async fn buscar_produtos(
     data: web::Data<AppState>, 
     auth_user: AuthenticatedUser,
+    filtro: web::Query<FiltroProdutos>,
+    parametros: web::Query<Vec<(String, String)>>,
 ) -> impl Responder {
     
 
@@ -38,33 +78,67 @@ pub async fn buscar_produtos(
         // embora a validação do JWT já garanta a presença de sub, name e email.
         eprintln!("Aviso: Dados do usuário autenticado incompletos ou inválidos.");
     }
-    
-    let produtos_result = query_as::<_, ProdutoRawData>(
-        r#"
-        SELECT 
-            p.id, p.nome, p.descricao, p.preco, p.estoque, p.categoria_id,
-            c.nome AS categoria_nome
-        FROM produtos p
-        JOIN categorias c ON p.categoria_id = c.id
-        ORDER BY p.id
-        "#
-    )
-    .fetch_all(&data.db_pool)
-    .await;
+
+    listar_produtos(&data, filtro.into_inner(), &parametros).await
+}
+
+/// Monta a listagem filtrada de produtos com as facetas, respondendo 400 para filtros inválidos.
+/// Compartilhada pelas páginas do catálogo que fixam algum filtro, como a da marca.
+pub(crate) async fn listar_produtos(
+    data: &web::Data<AppState>,
+    filtro: FiltroProdutos,
+    parametros: &[(String, String)],
+) -> HttpResponse {
+    let filtros = match interpretar_filtros(&data.db_pool, parametros).await {
+        Ok(filtros) => filtros,
+        Err(FalhaFiltro::Invalido(mensagem)) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: mensagem,
+                body: None,
+            });
+        }
+        Err(FalhaFiltro::Banco(e)) => {
+            eprintln!("Erro ao interpretar filtros de produtos: {:?}", e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar produtos".to_string(),
+                body: None,
+            });
+        }
+    };
+    let mut condicoes = CondicoesProdutos::default();
+    if let Some(categoria_id) = filtro.categoria_id {
+        condicoes.categoria(categoria_id);
+    }
+    if let Some(marca_id) = filtro.marca_id {
+        condicoes.marca(marca_id);
+    }
+    for filtro_atributo in &filtros {
+        condicoes.atributo(filtro_atributo);
+    }
+
+    let ordenacao = filtro.ordenar.unwrap_or(OrdenacaoProdutos::Id);
+    let sql = format!(
+        "{} WHERE p.deleted_at IS NULL AND c.deleted_at IS NULL{} ORDER BY {}",
+        CONSULTA_PRODUTOS,
+        condicoes.sql(),
+        ordenacao.order_by_sql()
+    );
+    let produtos_result = condicoes.ligar(query_as::<_, ProdutoRawData>(&sql))
+        .fetch_all(&data.db_pool)
+        .await;
+    let produtos_result = match produtos_result {
+        Ok(produtos_raw) => contar_facetas(&data.db_pool, &condicoes).await.map(|facetas| (produtos_raw, facetas)),
+        Err(e) => Err(e),
+    };
 
     match produtos_result {
-        Ok(produtos_raw) => {
-            let response_body: Vec<ProdutoResponse> = produtos_raw.into_iter()
-                .map(|p_raw| ProdutoResponse { // Mapeia ProdutoRawData para ProdutoResponse
-                    id: p_raw.id,
-                    nome: p_raw.nome,
-                    descricao: p_raw.descricao,
-                    preco: p_raw.preco,
-                    estoque: p_raw.estoque,
-                    categoria_id: p_raw.categoria_id,
-                    categoria_nome: p_raw.categoria_nome, // Agora acessa diretamente de p_raw
-                })
-                .collect();
+        Ok((produtos_raw, facetas)) => {
+            let response_body = ListagemProdutos {
+                produtos: produtos_raw.into_iter().map(ProdutoResponse::from).collect(),
+                facetas,
+            };
             
             HttpResponse::Ok().json(GenericResponse {
                 status: "success".to_string(),
@@ -84,43 +158,30 @@ pub async fn buscar_produtos(
 }
 
 /// Rota para buscar um produto específico por ID.
-/// Retorna uma GenericResponse com os detalhes do produto, incluindo o nome da categoria.
+/// Retorna uma GenericResponse com os detalhes do produto, incluindo o nome da categoria,
+/// e a versão atual no cabeçalho `ETag` (a ser enviada em `If-Match` nas atualizações).
 #[get("/produtos/{id}")]
 pub async fn buscar_produto_por_id(
     data: web::Data<AppState>,
     path: web::Path<i32>,
 ) -> HttpResponse {
     let id = path.into_inner();
-    let produto_result = query_as::<_, ProdutoRawData>(
-        r#"
-        SELECT 
-            p.id, p.nome, p.descricao, p.preco, p.estoque, p.categoria_id,
-            c.nome AS categoria_nome
-        FROM produtos p
-        JOIN categorias c ON p.categoria_id = c.id
-        WHERE p.id = $1
-        "#
-    )
-    .bind(id)
-    .fetch_optional(&data.db_pool)
-    .await;
+    let sql = format!("{} WHERE p.id = $1 AND p.deleted_at IS NULL AND c.deleted_at IS NULL", CONSULTA_PRODUTOS);
+    let produto_result = query_as::<_, ProdutoRawData>(&sql)
+        .bind(id)
+        .fetch_optional(&data.db_pool)
+        .await;
 
     match produto_result {
         Ok(Some(p_raw)) => {
-            let response_body = ProdutoResponse {
-                id: p_raw.id,
-                nome: p_raw.nome,
-                descricao: p_raw.descricao,
-                preco: p_raw.preco,
-                estoque: p_raw.estoque,
-                categoria_id: p_raw.categoria_id,
-                categoria_nome: p_raw.categoria_nome,
-            };
-            HttpResponse::Ok().json(GenericResponse {
-                status: "success".to_string(),
-                message: format!("Produto com ID {} encontrado.", id),
-                body: Some(response_body),
-            })
+            let response_body = ProdutoResponse::from(p_raw);
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag_da_versao(response_body.versao)))
+                .json(GenericResponse {
+                    status: "success".to_string(),
+                    message: format!("Produto com ID {} encontrado.", id),
+                    body: Some(response_body),
+                })
         },
         Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
             status: "error".to_string(),
@@ -139,49 +200,114 @@ pub async fn buscar_produto_por_id(
 }
 
 
+/// Rota para buscar um produto pelo slug, como `/produtos/slug/serum-facial`.
+/// Um slug antigo (de antes de uma troca de nome) responde 301 com o endereço atual.
+/// Deve ser registrada antes das rotas `/produtos/{id}/...` para não ser capturada por elas.
+#[get("/produtos/slug/{slug}")]
+pub async fn buscar_produto_por_slug(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let slug = path.into_inner();
+    let sql = format!(
+        "{} JOIN slugs s ON s.entidade = 'produto' AND s.entidade_id = p.id WHERE s.slug = $1 AND p.deleted_at IS NULL AND c.deleted_at IS NULL",
+        CONSULTA_PRODUTOS
+    );
+    let produto_result = query_as::<_, ProdutoRawData>(&sql)
+        .bind(&slug)
+        .fetch_optional(&data.db_pool)
+        .await;
+
+    match produto_result {
+        Ok(Some(p_raw)) if p_raw.slug != slug => redirecionar_para_slug("/produtos/slug", &p_raw.slug),
+        Ok(Some(p_raw)) => {
+            let response_body = ProdutoResponse::from(p_raw);
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag_da_versao(response_body.versao)))
+                .json(GenericResponse {
+                    status: "success".to_string(),
+                    message: format!("Produto '{}' encontrado.", slug),
+                    body: Some(response_body),
+                })
+        },
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Produto '{}' não encontrado.", slug),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar produto pelo slug '{}': {:?}", slug, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar produto".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
 /// Rota para inserir um novo produto no banco de dados.
+/// A criação é registrada na auditoria com o snapshot do produto criado.
 /// Retorna uma GenericResponse com o ID do produto criado.
+/// Com o cabeçalho `Idempotency-Key`, uma repetição devolve o mesmo ID em vez de duplicar o produto.
 #[post("/produtos")]
 pub async fn cadastrar_produto(
     data: web::Data<AppState>,
     item: web::Json<NovoProduto>,
+    admin: AdminUser,
+    req: HttpRequest,
 ) -> HttpResponse {
-    // A query SQL agora inclui o categoria_id
-    let result = sqlx::query(
-        "INSERT INTO produtos (nome, descricao, preco, estoque, categoria_id) VALUES ($1, $2, $3, $4, $5) RETURNING id"
+    let cadastro = inserir_produto(&data, &item, &admin);
+    com_idempotencia(&data.db_pool, &req, Some(admin.0.user_id), &*item, cadastro).await
+}
+
+/// Insere o produto e registra a criação na auditoria.
+async fn inserir_produto(data: &web::Data<AppState>, item: &NovoProduto, admin: &AdminUser) -> HttpResponse {
+    if [item.peso_gramas, item.altura_cm, item.largura_cm, item.comprimento_cm].iter().any(|medida| *medida <= 0) {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Peso e dimensões do produto devem ser maiores que zero.".to_string(),
+            body: None,
+        });
+    }
+
+    // A query SQL agora inclui o categoria_id. A chave estrangeira não enxerga a exclusão lógica,
+    // então a categoria e a marca (se informada) precisam estar ativas para a linha ser inserida.
+    let comando = query(
+        r#"
+        INSERT INTO produtos (nome, descricao, preco, estoque, categoria_id, peso_gramas, altura_cm, largura_cm, comprimento_cm, marca_id)
+        SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10
+        WHERE EXISTS (SELECT 1 FROM categorias WHERE id = $5 AND deleted_at IS NULL)
+          AND ($10::int IS NULL OR EXISTS (SELECT 1 FROM marcas WHERE id = $10 AND deleted_at IS NULL))
+        RETURNING id, NULL::jsonb AS antes, to_jsonb(produtos) AS depois
+        "#
     )
     .bind(&item.nome)
     .bind(&item.descricao)
     .bind(&item.preco)
     .bind(item.estoque)
-    .bind(item.categoria_id) 
-    .fetch_one(&data.db_pool)
-    .await;
+    .bind(item.categoria_id)
+    .bind(item.peso_gramas)
+    .bind(item.altura_cm)
+    .bind(item.largura_cm)
+    .bind(item.comprimento_cm)
+    .bind(item.marca_id);
 
-    match result {
-        Ok(row) => {
-            match row.try_get::<i32, &str>("id") {
-                Ok(id) => {
-                    HttpResponse::Ok().json(GenericResponse {
-                        status: "success".to_string(),
-                        message: format!("Produto cadastrado com sucesso! ID: {}", id),
-                        body: Some(serde_json::json!({ "id": id })),
-                    })
-                },
-                Err(e) => {
-                    eprintln!("Erro ao obter id do novo produto: {:?}", e);
-                    HttpResponse::InternalServerError().json(GenericResponse::<()>{
-                        status: "error".to_string(),
-                        message: "Erro ao processar resposta do cadastro".to_string(),
-                        body: None,
-                    })
-                }
-            }
+    match executar_com_auditoria(&data.db_pool, &admin.0, "produto", AcaoAuditoria::Criacao, comando).await {
+        Ok(Some(id)) => {
+            HttpResponse::Ok().json(GenericResponse {
+                status: "success".to_string(),
+                message: format!("Produto cadastrado com sucesso! ID: {}", id),
+                body: Some(serde_json::json!({ "id": id })),
+            })
         }
+        Ok(None) => referencia_invalida(data, Some(item.categoria_id), item.marca_id).await,
         Err(e) => {
             eprintln!("Erro ao inserir produto: {:?}", e);
             // Melhorar a mensagem de erro para o cliente, se for uma violação de FK
-            let error_message = if e.to_string().contains("foreign key constraint") {
+            let error_message = if violou_marca(&e) {
+                "Erro ao inserir produto: Marca não encontrada. Verifique o marca_id.".to_string()
+            } else if e.to_string().contains("foreign key constraint") {
                 "Erro ao inserir produto: Categoria não encontrada. Verifique o categoria_id.".to_string()
             } else {
                 "Erro ao inserir produto.".to_string()
@@ -195,17 +321,165 @@ pub async fn cadastrar_produto(
     }
 }
 
+/// Resposta 400 para o cadastro ou a atualização recusados por apontar para uma categoria
+/// ou marca inexistente ou excluída logicamente.
+async fn referencia_invalida(data: &web::Data<AppState>, categoria_id: Option<i32>, marca_id: Option<i32>) -> HttpResponse {
+    let referencias_invalidas = sqlx::query_as::<_, (bool, bool)>(
+        r#"
+        SELECT $1::int IS NOT NULL AND NOT EXISTS (SELECT 1 FROM categorias WHERE id = $1 AND deleted_at IS NULL),
+               $2::int IS NOT NULL AND NOT EXISTS (SELECT 1 FROM marcas WHERE id = $2 AND deleted_at IS NULL)
+        "#
+    )
+    .bind(categoria_id)
+    .bind(marca_id)
+    .fetch_one(&data.db_pool)
+    .await;
+
+    match referencias_invalidas {
+        Ok((true, _)) => HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Categoria não encontrada ou excluída. Verifique o categoria_id.".to_string(),
+            body: None,
+        }),
+        Ok((false, true)) => HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Marca não encontrada ou excluída. Verifique o marca_id.".to_string(),
+            body: None,
+        }),
+        Ok((false, false)) => {
+            eprintln!("Erro ao gravar produto: nenhuma linha retornada");
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao gravar produto.".to_string(),
+                body: None,
+            })
+        }
+        Err(e) => {
+            eprintln!("Erro ao verificar a categoria e a marca do produto: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao verificar a categoria e a marca do produto.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Indica se o erro é a chave estrangeira da marca do produto (marca inexistente).
+fn violou_marca(e: &sqlx::Error) -> bool {
+    e.as_database_error().and_then(|d| d.constraint()) == Some("produtos_marca_id_fkey")
+}
+
 /// Rota para atualizar um produto existente por ID.
+/// Exige o cabeçalho `If-Match` com o ETag do produto: se outra pessoa alterou o produto
+/// nesse meio tempo, a atualização é recusada com 412 em vez de sobrescrever a alteração.
+/// A atualização é registrada na auditoria com os snapshots anterior e posterior.
 /// Retorna uma GenericResponse de sucesso ou erro.
 #[put("/produtos/{id}")]
 pub async fn atualizar_produto(
     data: web::Data<AppState>,
     path: web::Path<i32>,
     item: web::Json<NovoProduto>,
+    req: HttpRequest,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let versao = match versao_do_if_match(&req) {
+        Ok(v) => v,
+        Err(resposta) => return resposta,
+    };
+
+    aplicar_atualizacao_produto(&data, id, versao, item.into_inner().into(), &admin).await
+}
+
+/// Rota para atualizar parcialmente um produto existente por ID.
+/// Apenas os campos presentes no corpo são alterados (ex.: somente `estoque`).
+/// Exige o cabeçalho `If-Match`, assim como a atualização completa.
+#[patch("/produtos/{id}")]
+pub async fn atualizar_produto_parcial(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<AtualizacaoParcialProduto>,
+    req: HttpRequest,
+    admin: AdminUser,
 ) -> HttpResponse {
     let id = path.into_inner();
-    let result = query(
-        "UPDATE produtos SET nome = $1, descricao = $2, preco = $3, estoque = $4, categoria_id = $5 WHERE id = $6"
+    let versao = match versao_do_if_match(&req) {
+        Ok(v) => v,
+        Err(resposta) => return resposta,
+    };
+
+    if item.is_vazia() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Informe ao menos um campo para atualizar.".to_string(),
+            body: None,
+        });
+    }
+
+    aplicar_atualizacao_produto(&data, id, versao, item.into_inner(), &admin).await
+}
+
+/// Aplica a atualização (completa ou parcial) de um produto, condicionada à versão esperada.
+/// Campos `None` são mantidos via COALESCE e a versão é incrementada a cada alteração.
+async fn aplicar_atualizacao_produto(
+    data: &web::Data<AppState>,
+    id: i32,
+    versao_esperada: i32,
+    item: AtualizacaoParcialProduto,
+    admin: &AdminUser,
+) -> HttpResponse {
+    if item.possui_medida_invalida() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Peso e dimensões do produto devem ser maiores que zero.".to_string(),
+            body: None,
+        });
+    }
+
+    // A CTE bloqueia a linha e captura o estado anterior na mesma instrução do UPDATE.
+    // O FROM garante que o snapshot seja lido antes da alteração.
+    // Uma redução de preço de produto favoritado fica registrada em `quedas_preco`, e o estoque
+    // que sai de zero enfileira os avisos dos clientes inscritos no "avise-me".
+    let comando = query(
+        r#"
+        WITH anterior AS (
+            SELECT p.id, p.preco, p.estoque, to_jsonb(p) AS antes FROM produtos p WHERE p.id = $6 AND p.deleted_at IS NULL FOR UPDATE
+        ),
+        atualizado AS (
+            UPDATE produtos SET
+                nome = COALESCE($1, nome),
+                descricao = COALESCE($2, descricao),
+                preco = COALESCE($3, produtos.preco),
+                estoque = COALESCE($4, produtos.estoque),
+                categoria_id = COALESCE($5, categoria_id),
+                peso_gramas = COALESCE($8, peso_gramas),
+                altura_cm = COALESCE($9, altura_cm),
+                largura_cm = COALESCE($10, largura_cm),
+                comprimento_cm = COALESCE($11, comprimento_cm),
+                marca_id = CASE WHEN $12 THEN $13 ELSE marca_id END,
+                versao = versao + 1
+            FROM anterior
+            WHERE produtos.id = anterior.id AND produtos.versao = $7
+              AND ($5::int IS NULL OR EXISTS (SELECT 1 FROM categorias WHERE id = $5 AND deleted_at IS NULL))
+              AND ($13::int IS NULL OR EXISTS (SELECT 1 FROM marcas WHERE id = $13 AND deleted_at IS NULL))
+            RETURNING produtos.id, produtos.preco, produtos.estoque, anterior.preco AS preco_anterior,
+                      anterior.estoque AS estoque_anterior, anterior.antes, to_jsonb(produtos) AS depois
+        ),
+        queda_preco AS (
+            INSERT INTO quedas_preco (produto_id, preco_anterior, preco_novo)
+            SELECT a.id, a.preco_anterior, a.preco
+            FROM atualizado a
+            WHERE a.preco < a.preco_anterior AND EXISTS (SELECT 1 FROM favoritos f WHERE f.produto_id = a.id)
+        ),
+        avisos_estoque AS (
+            UPDATE inscricoes_estoque i SET enfileirada_em = NOW()
+            FROM atualizado a
+            WHERE i.produto_id = a.id AND a.estoque_anterior = 0 AND a.estoque > 0
+              AND i.enfileirada_em IS NULL AND i.expira_em > NOW()
+        )
+        SELECT id, antes, depois FROM atualizado
+        "#
     )
     .bind(&item.nome)
     .bind(&item.descricao)
@@ -213,28 +487,58 @@ pub async fn atualizar_produto(
     .bind(item.estoque)
     .bind(item.categoria_id)
     .bind(id)
-    .execute(&data.db_pool)
-    .await;
+    .bind(versao_esperada)
+    .bind(item.peso_gramas)
+    .bind(item.altura_cm)
+    .bind(item.largura_cm)
+    .bind(item.comprimento_cm)
+    .bind(item.marca_id.is_some())
+    .bind(item.marca_id.flatten());
 
-    match result {
-        Ok(res) => {
-            if res.rows_affected() > 0 {
-                HttpResponse::Ok().json(GenericResponse::<()>{
+    match executar_com_auditoria(&data.db_pool, &admin.0, "produto", AcaoAuditoria::Atualizacao, comando).await {
+        Ok(Some(_)) => {
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag_da_versao(versao_esperada + 1)))
+                .json(GenericResponse::<()>{
                     status: "success".to_string(),
                     message: format!("Produto com ID {} atualizado com sucesso.", id),
                     body: None,
                 })
-            } else {
-                HttpResponse::NotFound().json(GenericResponse::<()>{
+        },
+        Ok(None) => {
+            // Nenhuma linha alterada: o produto não existe, a versão informada está desatualizada
+            // ou a nova categoria ou marca não está ativa
+            let versao_atual = sqlx::query_scalar::<_, i32>("SELECT versao FROM produtos WHERE id = $1 AND deleted_at IS NULL")
+                .bind(id)
+                .fetch_optional(&data.db_pool)
+                .await;
+
+            match versao_atual {
+                Ok(Some(versao_atual)) if versao_atual == versao_esperada => referencia_invalida(data, item.categoria_id, item.marca_id.flatten()).await,
+                Ok(Some(versao_atual)) => conflito_de_versao(
+                    format!("Produto com ID {} foi alterado por outra pessoa. Recarregue e tente novamente.", id),
+                    versao_atual,
+                ),
+                Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
                     status: "error".to_string(),
                     message: format!("Produto com ID {} não encontrado para atualização.", id),
                     body: None,
-                })
+                }),
+                Err(e) => {
+                    eprintln!("Erro ao verificar versão do produto com ID {}: {:?}", id, e);
+                    HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                        status: "error".to_string(),
+                        message: "Erro ao atualizar produto.".to_string(),
+                        body: None,
+                    })
+                }
             }
         },
         Err(e) => {
             eprintln!("Erro ao atualizar produto com ID {}: {:?}", id, e);
-            let error_message = if e.to_string().contains("foreign key constraint") {
+            let error_message = if violou_marca(&e) {
+                "Erro ao atualizar produto: Marca não encontrada. Verifique o marca_id.".to_string()
+            } else if e.to_string().contains("foreign key constraint") {
                 "Erro ao atualizar produto: Categoria não encontrada. Verifique o categoria_id.".to_string()
             } else {
                 "Erro ao atualizar produto.".to_string()
@@ -249,49 +553,130 @@ pub async fn atualizar_produto(
 }
 
 /// Rota para deletar um produto por ID.
+/// A exclusão é lógica (soft delete): a linha recebe `deleted_at` e deixa de aparecer
+/// no catálogo, preservando o histórico de vendas que referencia o produto.
 /// Retorna uma GenericResponse de sucesso ou erro.
 #[delete("/produtos/{id}")]
 pub async fn deletar_produto(
     data: web::Data<AppState>,
     path: web::Path<i32>,
+    admin: AdminUser,
 ) -> HttpResponse {
     let id = path.into_inner();
-    let result = query("DELETE FROM produtos WHERE id = $1")
-        .bind(id)
-        .execute(&data.db_pool)
-        .await;
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT to_jsonb(p) AS antes FROM produtos p WHERE p.id = $1 FOR UPDATE)
+        UPDATE produtos SET deleted_at = NOW(), versao = versao + 1 WHERE id = $1 AND deleted_at IS NULL
+        RETURNING id, (SELECT antes FROM anterior) AS antes, to_jsonb(produtos) AS depois
+        "#
+    )
+    .bind(id);
 
-    match result {
-        Ok(res) => {
-            if res.rows_affected() > 0 {
-                HttpResponse::Ok().json(GenericResponse::<()>{
-                    status: "success".to_string(),
-                    message: format!("Produto com ID {} deletado com sucesso.", id),
-                    body: None,
-                })
-            } else {
-                HttpResponse::NotFound().json(GenericResponse::<()>{
-                    status: "error".to_string(),
-                    message: format!("Produto com ID {} não encontrado para exclusão.", id),
-                    body: None,
-                })
-            }
+    match executar_com_auditoria(&data.db_pool, &admin.0, "produto", AcaoAuditoria::Exclusao, comando).await {
+        Ok(Some(_)) => {
+            HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Produto com ID {} deletado com sucesso.", id),
+                body: None,
+            })
+        },
+        Ok(None) => {
+            HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Produto com ID {} não encontrado para exclusão.", id),
+                body: None,
+            })
         },
         Err(e) => {
             eprintln!("Erro ao deletar produto com ID {}: {:?}", id, e);
-            // Adicionar tratamento para erro de chave estrangeira se o produto estiver associado a vendas/carrinhos persistentes
-            let error_message = if e.to_string().contains("foreign key constraint") {
-                "Não é possível deletar o produto: ele está associado a vendas ou carrinhos existentes.".to_string()
-            } else {
-                "Erro ao deletar produto.".to_string()
-            };
             HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: error_message,
+                message: "Erro ao deletar produto.".to_string(),
                 body: None,
             })
         }
     }
 }
 
+/// Rota administrativa para listar os produtos excluídos logicamente.
+/// Deve ser registrada antes de `/produtos/{id}` para não ser capturada por ela.
+#[get("/produtos/excluidos")]
+pub async fn buscar_produtos_excluidos(
+    data: web::Data<AppState>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let produtos_result = query_as::<_, ProdutoExcluido>(
+        r#"
+        SELECT id, nome, descricao, preco, estoque, categoria_id, deleted_at
+        FROM produtos
+        WHERE deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC
+        "#
+    )
+    .fetch_all(&data.db_pool)
+    .await;
 
+    match produtos_result {
+        Ok(produtos) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Produtos excluídos listados com sucesso!".to_string(),
+            body: Some(produtos),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar produtos excluídos: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar produtos excluídos".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para restaurar um produto excluído logicamente.
+/// A restauração é recusada enquanto a categoria ou a marca do produto continuar excluída.
+#[post("/produtos/{id}/restaurar")]
+pub async fn restaurar_produto(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let comando = query(
+        r#"
+        WITH anterior AS (SELECT p.id, to_jsonb(p) AS antes FROM produtos p WHERE p.id = $1 FOR UPDATE)
+        UPDATE produtos SET deleted_at = NULL, versao = produtos.versao + 1
+        FROM anterior
+        WHERE produtos.id = anterior.id AND produtos.deleted_at IS NOT NULL
+          AND EXISTS (SELECT 1 FROM categorias c WHERE c.id = produtos.categoria_id AND c.deleted_at IS NULL)
+          AND (produtos.marca_id IS NULL OR EXISTS (SELECT 1 FROM marcas m WHERE m.id = produtos.marca_id AND m.deleted_at IS NULL))
+        RETURNING produtos.id, anterior.antes, to_jsonb(produtos) AS depois
+        "#
+    )
+    .bind(id);
+
+    match executar_com_auditoria(&data.db_pool, &admin.0, "produto", AcaoAuditoria::Restauracao, comando).await {
+        Ok(Some(_)) => {
+            HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Produto com ID {} restaurado com sucesso.", id),
+                body: None,
+            })
+        },
+        Ok(None) => {
+            HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Produto com ID {} não encontrado entre os excluídos ou sua categoria ou marca ainda está excluída.", id),
+                body: None,
+            })
+        },
+        Err(e) => {
+            eprintln!("Erro ao restaurar produto com ID {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao restaurar produto.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
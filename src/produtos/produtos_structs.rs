@@ -3,15 +3,83 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use bigdecimal::BigDecimal; // Importa BigDecimal
+use chrono::{DateTime, Utc};
+
+use crate::atributos::atributos_structs::Faceta;
+use crate::shared::shared_structs::campo_anulavel;
 
 /// Estrutura para receber dados do novo produto na requisição POST
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct NovoProduto {
     pub nome: String,
     pub descricao: String,
     pub preco: BigDecimal,
     pub estoque: i32,
     pub categoria_id: i32,
+    pub peso_gramas: i32,    // Peso com embalagem, usado no cálculo do frete
+    pub altura_cm: i32,      // Dimensões da embalagem, usadas no peso cubado
+    pub largura_cm: i32,
+    pub comprimento_cm: i32,
+    #[serde(default)]
+    pub marca_id: Option<i32>, // Produto sem marca quando ausente
+}
+
+/// Estrutura para receber uma atualização parcial de produto na requisição PATCH.
+/// Apenas os campos informados são alterados; os demais permanecem como estão.
+#[derive(Deserialize)]
+pub struct AtualizacaoParcialProduto {
+    pub nome: Option<String>,
+    pub descricao: Option<String>,
+    pub preco: Option<BigDecimal>,
+    pub estoque: Option<i32>,
+    pub categoria_id: Option<i32>,
+    pub peso_gramas: Option<i32>,
+    pub altura_cm: Option<i32>,
+    pub largura_cm: Option<i32>,
+    pub comprimento_cm: Option<i32>,
+    #[serde(default, deserialize_with = "campo_anulavel")]
+    pub marca_id: Option<Option<i32>>, // Ausente: mantém; `null`: remove a marca
+}
+
+impl AtualizacaoParcialProduto {
+    /// Indica se a requisição não informou nenhum campo para alterar.
+    pub fn is_vazia(&self) -> bool {
+        self.nome.is_none()
+            && self.descricao.is_none()
+            && self.preco.is_none()
+            && self.estoque.is_none()
+            && self.categoria_id.is_none()
+            && self.peso_gramas.is_none()
+            && self.altura_cm.is_none()
+            && self.largura_cm.is_none()
+            && self.comprimento_cm.is_none()
+            && self.marca_id.is_none()
+    }
+
+    /// Indica se algum peso ou dimensão informado não é positivo.
+    pub fn possui_medida_invalida(&self) -> bool {
+        [self.peso_gramas, self.altura_cm, self.largura_cm, self.comprimento_cm]
+            .iter()
+            .any(|medida| matches!(medida, Some(valor) if *valor <= 0))
+    }
+}
+
+impl From<NovoProduto> for AtualizacaoParcialProduto {
+    /// Uma atualização completa (PUT) é uma atualização parcial com todos os campos informados.
+    fn from(produto: NovoProduto) -> Self {
+        AtualizacaoParcialProduto {
+            nome: Some(produto.nome),
+            descricao: Some(produto.descricao),
+            preco: Some(produto.preco),
+            estoque: Some(produto.estoque),
+            categoria_id: Some(produto.categoria_id),
+            peso_gramas: Some(produto.peso_gramas),
+            altura_cm: Some(produto.altura_cm),
+            largura_cm: Some(produto.largura_cm),
+            comprimento_cm: Some(produto.comprimento_cm),
+            marca_id: Some(produto.marca_id),
+        }
+    }
 }
 
 /// Estrutura que representa um produto no banco de dados
@@ -32,11 +100,22 @@ pub struct Produto {
 pub struct ProdutoResponse {
     pub id: i32,
     pub nome: String,
+    pub slug: String, // Endereço amigável, gerado a partir do nome
     pub descricao: String,
     pub preco: BigDecimal,
     pub estoque: i32,
     pub categoria_id: i32,     
     pub categoria_nome: String,
+    pub marca_id: Option<i32>,
+    pub marca_nome: Option<String>,
+    pub peso_gramas: i32,
+    pub altura_cm: i32,
+    pub largura_cm: i32,
+    pub comprimento_cm: i32,
+    pub versao: i32, // Versão para controle de concorrência (também enviada como ETag)
+    pub avaliacao_media: Option<BigDecimal>, // Média das avaliações aprovadas; ausente se não houver nenhuma
+    pub total_avaliacoes: i64,               // Quantidade de avaliações aprovadas
+    pub atributos: serde_json::Value,        // Valores dos atributos, por código
 }
 
 
@@ -46,9 +125,93 @@ pub struct ProdutoResponse {
 pub struct ProdutoRawData {
     pub id: i32,
     pub nome: String,
+    pub slug: String,
     pub descricao: String,
     pub preco: BigDecimal,
     pub estoque: i32,
     pub categoria_id: i32,
     pub categoria_nome: String, // Corresponde a 'c.nome AS categoria_nome' na query
+    pub marca_id: Option<i32>,
+    pub marca_nome: Option<String>, // Corresponde a 'm.nome AS marca_nome' na query
+    pub peso_gramas: i32,
+    pub altura_cm: i32,
+    pub largura_cm: i32,
+    pub comprimento_cm: i32,
+    pub versao: i32,
+    pub avaliacao_media: Option<BigDecimal>,
+    pub total_avaliacoes: i64,
+    pub atributos: serde_json::Value,
+}
+
+impl From<ProdutoRawData> for ProdutoResponse {
+    fn from(p_raw: ProdutoRawData) -> Self {
+        ProdutoResponse {
+            id: p_raw.id,
+            nome: p_raw.nome,
+            slug: p_raw.slug,
+            descricao: p_raw.descricao,
+            preco: p_raw.preco,
+            estoque: p_raw.estoque,
+            categoria_id: p_raw.categoria_id,
+            categoria_nome: p_raw.categoria_nome,
+            marca_id: p_raw.marca_id,
+            marca_nome: p_raw.marca_nome,
+            peso_gramas: p_raw.peso_gramas,
+            altura_cm: p_raw.altura_cm,
+            largura_cm: p_raw.largura_cm,
+            comprimento_cm: p_raw.comprimento_cm,
+            versao: p_raw.versao,
+            avaliacao_media: p_raw.avaliacao_media,
+            total_avaliacoes: p_raw.total_avaliacoes,
+            atributos: p_raw.atributos,
+        }
+    }
+}
+
+/// Filtros aceitos na query string de `GET /produtos`.
+/// Os demais parâmetros são filtros por atributo (ver `atributos_service::interpretar_filtros`).
+#[derive(Deserialize)]
+pub struct FiltroProdutos {
+    pub ordenar: Option<OrdenacaoProdutos>,
+    pub categoria_id: Option<i32>, // Inclui as subcategorias
+    pub marca_id: Option<i32>,
+}
+
+/// Corpo da resposta de `GET /produtos`: os produtos filtrados e as contagens
+/// por valor de atributo para montar os filtros da loja.
+#[derive(Serialize)]
+pub struct ListagemProdutos {
+    pub produtos: Vec<ProdutoResponse>,
+    pub facetas: Vec<Faceta>,
+}
+
+/// Ordenação da listagem de produtos.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OrdenacaoProdutos {
+    Id,        // Ordem de cadastro (padrão)
+    Avaliacao, // Maior média primeiro; produtos sem avaliações por último
+}
+
+impl OrdenacaoProdutos {
+    /// Cláusula ORDER BY correspondente, sobre as colunas da listagem.
+    pub fn order_by_sql(&self) -> &'static str {
+        match self {
+            OrdenacaoProdutos::Id => "p.id",
+            OrdenacaoProdutos::Avaliacao => "avaliacao_media DESC NULLS LAST, total_avaliacoes DESC, p.id",
+        }
+    }
+}
+
+/// Estrutura para a visão administrativa de produtos excluídos (soft delete).
+/// Inclui o momento da exclusão para auxiliar na decisão de restauração.
+#[derive(Serialize, FromRow)]
+pub struct ProdutoExcluido {
+    pub id: i32,
+    pub nome: String,
+    pub descricao: String,
+    pub preco: BigDecimal,
+    pub estoque: i32,
+    pub categoria_id: i32,
+    pub deleted_at: DateTime<Utc>,
 }
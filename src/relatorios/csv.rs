@@ -0,0 +1,48 @@
+// src/relatorios/csv.rs
+
+use actix_web::{http::header, HttpResponse};
+
+/// Linha de um relatório exportável em CSV.
+pub trait LinhaCsv {
+    /// Nomes das colunas, na ordem de `campos`.
+    const CABECALHO: &'static [&'static str];
+    /// Valores da linha, já formatados.
+    fn campos(&self) -> Vec<String>;
+}
+
+/// Gera o CSV (RFC 4180: vírgula como separador e ponto nas casas decimais).
+/// Começa com o BOM do UTF-8 para que planilhas exibam os acentos corretamente.
+pub fn gerar_csv<T: LinhaCsv>(linhas: &[T]) -> String {
+    let mut csv = String::from('\u{FEFF}');
+    csv.push_str(&T::CABECALHO.join(","));
+    csv.push_str("\r\n");
+    for linha in linhas {
+        let campos: Vec<String> = linha.campos().iter().map(|campo| escapar(campo)).collect();
+        csv.push_str(&campos.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Resposta de download do CSV com o nome de arquivo indicado.
+pub fn resposta_csv<T: LinhaCsv>(nome_arquivo: &str, linhas: &[T]) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.csv\"", nome_arquivo)))
+        .body(gerar_csv(linhas))
+}
+
+/// Coloca o campo entre aspas se ele tiver separador, aspas ou quebra de linha.
+/// Campos iniciados por `=`, `+`, `-` ou `@` recebem um apóstrofo, para que a planilha
+/// não os execute como fórmula (ex.: nome de produto cadastrado com `=HYPERLINK(...)`).
+fn escapar(campo: &str) -> String {
+    let campo = match campo.chars().next() {
+        Some('=' | '+' | '-' | '@') if campo.parse::<f64>().is_err() => format!("'{}", campo),
+        _ => campo.to_string(),
+    };
+    if campo.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", campo.replace('"', "\"\""))
+    } else {
+        campo
+    }
+}
@@ -0,0 +1,8 @@
+// src/relatorios/mod.rs
+
+// Declara o submódulo que contém as definições das structs dos relatórios
+pub mod relatorios_structs;
+// Declara o submódulo que exporta os relatórios em CSV
+pub mod csv;
+// Declara o submódulo que contém as funções de rota dos relatórios
+pub mod relatorios_router;
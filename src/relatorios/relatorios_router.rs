@@ -0,0 +1,289 @@
+// src/relatorios/relatorios_router.rs
+
+use actix_web::{get, web, HttpResponse};
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use sqlx::query_as;
+
+// Importa as structs dos relatórios
+use super::relatorios_structs::{
+    AgrupamentoCategorias, FaturamentoPeriodo, FiltroRelatorio, FormatoRelatorio, OrdemProdutos, Periodicidade,
+    ProdutoMaisVendido, ResumoVendas, VendasPorCategoria,
+};
+// Importa a exportação em CSV
+use super::csv::{resposta_csv, LinhaCsv};
+// Importa a situação dos pedidos
+use crate::vendas::vendas_structs::StatusVenda;
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+// Importa o extrator de autorização para as rotas administrativas
+use crate::usuarios::auth_middleware::AdminUser;
+
+/// Fuso horário da loja: os dias dos relatórios vão da meia-noite à meia-noite de Brasília.
+const FUSO_HORARIO: &str = "America/Sao_Paulo";
+/// Dias cobertos quando o período não é informado.
+const DIAS_PADRAO: i64 = 30;
+/// Quantidade de produtos retornada quando `limite` não é informado.
+const LIMITE_PADRAO: i64 = 10;
+/// Quantidade máxima de produtos retornada por consulta.
+const LIMITE_MAXIMO: i64 = 100;
+
+/// Situações em que o pedido conta como venda. Pedidos pendentes (aguardando pagamento)
+/// e cancelados ficam de fora de todos os relatórios.
+fn status_vendidos() -> Vec<&'static str> {
+    vec![StatusVenda::Confirmado.as_str(), StatusVenda::Enviado.as_str(), StatusVenda::Entregue.as_str()]
+}
+
+/// Rota administrativa com o faturamento por dia, semana ou mês (`periodo`, padrão `dia`).
+/// Cada período traz a quantidade de pedidos, o faturamento (itens mais frete) e o ticket médio.
+#[get("/relatorios/faturamento")]
+pub async fn relatorio_faturamento(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroRelatorio>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let (inicio, fim) = match intervalo(&data, &filtro).await {
+        Ok(intervalo) => intervalo,
+        Err(resposta) => return resposta,
+    };
+    let unidade = filtro.periodo.unwrap_or(Periodicidade::Dia).unidade_sql();
+
+    let linhas_result = query_as::<_, FaturamentoPeriodo>(
+        r#"
+        WITH periodos AS (
+            SELECT generate_series(
+                date_trunc($3, $1::date::timestamp), date_trunc($3, $2::date::timestamp), ('1 ' || $3)::interval
+            )::date AS periodo
+        )
+        SELECT p.periodo,
+               COUNT(v.id) AS pedidos,
+               COALESCE(SUM(v.total), 0) AS faturamento,
+               COALESCE(ROUND(AVG(v.total), 2), 0) AS ticket_medio
+        FROM periodos p
+        LEFT JOIN vendas v
+               ON date_trunc($3, v.criado_em AT TIME ZONE $4)::date = p.periodo
+              AND v.status = ANY($5)
+              AND v.criado_em >= $1::date::timestamp AT TIME ZONE $4
+              AND v.criado_em < ($2::date + 1)::timestamp AT TIME ZONE $4
+        GROUP BY p.periodo
+        ORDER BY p.periodo
+        "#
+    )
+    .bind(inicio)
+    .bind(fim)
+    .bind(unidade)
+    .bind(FUSO_HORARIO)
+    .bind(status_vendidos())
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match linhas_result {
+        Ok(linhas) => responder(&filtro, "faturamento", inicio, fim, "Faturamento por período.", linhas),
+        Err(e) => erro_relatorio("faturamento", e),
+    }
+}
+
+/// Rota administrativa com o ranking dos produtos mais vendidos no período,
+/// por unidades (padrão) ou por faturamento (`ordem=faturamento`).
+#[get("/relatorios/produtos-mais-vendidos")]
+pub async fn relatorio_produtos_mais_vendidos(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroRelatorio>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let (inicio, fim) = match intervalo(&data, &filtro).await {
+        Ok(intervalo) => intervalo,
+        Err(resposta) => return resposta,
+    };
+    let por_faturamento = filtro.ordem == Some(OrdemProdutos::Faturamento);
+    let limite = filtro.limite.unwrap_or(LIMITE_PADRAO).clamp(1, LIMITE_MAXIMO);
+
+    let linhas_result = query_as::<_, ProdutoMaisVendido>(
+        r#"
+        SELECT p.id AS produto_id, p.nome,
+               SUM(vi.quantidade)::bigint AS unidades,
+               SUM(vi.quantidade * vi.preco_unitario) AS faturamento
+        FROM vendas_itens vi
+        JOIN vendas v ON v.id = vi.venda_id
+        JOIN produtos p ON p.id = vi.produto_id
+        WHERE v.status = ANY($4)
+          AND v.criado_em >= $1::date::timestamp AT TIME ZONE $3
+          AND v.criado_em < ($2::date + 1)::timestamp AT TIME ZONE $3
+        GROUP BY p.id, p.nome
+        ORDER BY CASE WHEN $5 THEN SUM(vi.quantidade * vi.preco_unitario) ELSE SUM(vi.quantidade) END DESC, p.id
+        LIMIT $6
+        "#
+    )
+    .bind(inicio)
+    .bind(fim)
+    .bind(FUSO_HORARIO)
+    .bind(status_vendidos())
+    .bind(por_faturamento)
+    .bind(limite)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match linhas_result {
+        Ok(linhas) => responder(&filtro, "produtos_mais_vendidos", inicio, fim, "Produtos mais vendidos.", linhas),
+        Err(e) => erro_relatorio("produtos mais vendidos", e),
+    }
+}
+
+/// Rota administrativa com as vendas por sessão (padrão) ou por categoria (`agrupamento=categoria`).
+/// Na visão por sessão, as vendas das subcategorias, em qualquer nível, somam na sessão raiz.
+#[get("/relatorios/vendas-por-categoria")]
+pub async fn relatorio_vendas_por_categoria(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroRelatorio>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let (inicio, fim) = match intervalo(&data, &filtro).await {
+        Ok(intervalo) => intervalo,
+        Err(resposta) => return resposta,
+    };
+    let por_sessao = filtro.agrupamento.unwrap_or(AgrupamentoCategorias::Sessao) == AgrupamentoCategorias::Sessao;
+
+    let linhas_result = query_as::<_, VendasPorCategoria>(
+        r#"
+        WITH RECURSIVE arvore AS (
+            SELECT id, id AS sessao_id FROM categorias WHERE parent_id IS NULL
+            UNION ALL
+            SELECT c.id, a.sessao_id FROM categorias c JOIN arvore a ON c.parent_id = a.id
+        )
+        SELECT c.id AS categoria_id, c.nome,
+               COUNT(DISTINCT v.id) AS pedidos,
+               SUM(vi.quantidade)::bigint AS unidades,
+               SUM(vi.quantidade * vi.preco_unitario) AS faturamento
+        FROM vendas_itens vi
+        JOIN vendas v ON v.id = vi.venda_id
+        JOIN produtos p ON p.id = vi.produto_id
+        JOIN arvore a ON a.id = p.categoria_id
+        JOIN categorias c ON c.id = CASE WHEN $5 THEN a.sessao_id ELSE p.categoria_id END
+        WHERE v.status = ANY($4)
+          AND v.criado_em >= $1::date::timestamp AT TIME ZONE $3
+          AND v.criado_em < ($2::date + 1)::timestamp AT TIME ZONE $3
+        GROUP BY c.id, c.nome
+        ORDER BY faturamento DESC, c.id
+        "#
+    )
+    .bind(inicio)
+    .bind(fim)
+    .bind(FUSO_HORARIO)
+    .bind(status_vendidos())
+    .bind(por_sessao)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match linhas_result {
+        Ok(linhas) => responder(&filtro, "vendas_por_categoria", inicio, fim, "Vendas por categoria.", linhas),
+        Err(e) => erro_relatorio("vendas por categoria", e),
+    }
+}
+
+/// Rota administrativa com os indicadores gerais do período: pedidos, faturamento, ticket médio
+/// e conversão de sacola em compra (sacolas iniciadas no período que viraram pedido pago).
+#[get("/relatorios/resumo")]
+pub async fn relatorio_resumo(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroRelatorio>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let (inicio, fim) = match intervalo(&data, &filtro).await {
+        Ok(intervalo) => intervalo,
+        Err(resposta) => return resposta,
+    };
+
+    let resumo_result = query_as::<_, ResumoVendas>(
+        r#"
+        WITH vendidos AS (
+            SELECT total FROM vendas
+            WHERE status = ANY($4)
+              AND criado_em >= $1::date::timestamp AT TIME ZONE $3
+              AND criado_em < ($2::date + 1)::timestamp AT TIME ZONE $3
+        ),
+        funil AS (
+            SELECT COUNT(*) AS sacolas, COUNT(*) FILTER (WHERE v.status = ANY($4)) AS sacolas_convertidas
+            FROM sacolas s
+            LEFT JOIN vendas v ON v.id = s.venda_id
+            WHERE s.criada_em >= $1::date::timestamp AT TIME ZONE $3
+              AND s.criada_em < ($2::date + 1)::timestamp AT TIME ZONE $3
+        )
+        SELECT $1::date AS inicio, $2::date AS fim,
+               (SELECT COUNT(*) FROM vendidos) AS pedidos,
+               (SELECT COALESCE(SUM(total), 0) FROM vendidos) AS faturamento,
+               (SELECT COALESCE(ROUND(AVG(total), 2), 0) FROM vendidos) AS ticket_medio,
+               f.sacolas, f.sacolas_convertidas,
+               ROUND(100.0 * f.sacolas_convertidas / NULLIF(f.sacolas, 0), 2) AS taxa_conversao
+        FROM funil f
+        "#
+    )
+    .bind(inicio)
+    .bind(fim)
+    .bind(FUSO_HORARIO)
+    .bind(status_vendidos())
+    .fetch_one(&data.db_pool)
+    .await;
+
+    match resumo_result {
+        Ok(resumo) => responder(&filtro, "resumo", inicio, fim, "Resumo de vendas.", vec![resumo]),
+        Err(e) => erro_relatorio("resumo de vendas", e),
+    }
+}
+
+/// Resolve o período do relatório. Sem `fim`, usa o dia de hoje no fuso da loja;
+/// sem `inicio`, os 30 dias que terminam em `fim`.
+async fn intervalo(data: &web::Data<AppState>, filtro: &FiltroRelatorio) -> Result<(NaiveDate, NaiveDate), HttpResponse> {
+    let fim = match filtro.fim {
+        Some(fim) => fim,
+        None => {
+            let hoje = sqlx::query_scalar::<_, NaiveDate>("SELECT (NOW() AT TIME ZONE $1)::date")
+                .bind(FUSO_HORARIO)
+                .fetch_one(&data.db_pool)
+                .await;
+            match hoje {
+                Ok(hoje) => hoje,
+                Err(e) => return Err(erro_relatorio("período", e)),
+            }
+        }
+    };
+    let inicio = filtro.inicio.unwrap_or(fim - Duration::days(DIAS_PADRAO - 1));
+
+    if inicio > fim {
+        return Err(HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "A data de início deve ser anterior ou igual à data de fim.".to_string(),
+            body: None,
+        }));
+    }
+    Ok((inicio, fim))
+}
+
+/// Responde em JSON ou, com `formato=csv`, com o arquivo `<nome>_<inicio>_<fim>.csv`.
+fn responder<T: Serialize + LinhaCsv>(
+    filtro: &FiltroRelatorio,
+    nome: &str,
+    inicio: NaiveDate,
+    fim: NaiveDate,
+    mensagem: &str,
+    linhas: Vec<T>,
+) -> HttpResponse {
+    if filtro.formato == Some(FormatoRelatorio::Csv) {
+        return resposta_csv(&format!("{}_{}_{}", nome, inicio, fim), &linhas);
+    }
+    HttpResponse::Ok().json(GenericResponse {
+        status: "success".to_string(),
+        message: mensagem.to_string(),
+        body: Some(linhas),
+    })
+}
+
+fn erro_relatorio(relatorio: &str, e: sqlx::Error) -> HttpResponse {
+    eprintln!("Erro ao gerar relatório de {}: {:?}", relatorio, e);
+    HttpResponse::InternalServerError().json(GenericResponse::<()>{
+        status: "error".to_string(),
+        message: format!("Erro ao gerar relatório de {}.", relatorio),
+        body: None,
+    })
+}
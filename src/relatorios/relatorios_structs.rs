@@ -0,0 +1,156 @@
+// src/relatorios/relatorios_structs.rs
+
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::csv::LinhaCsv;
+
+/// Filtros aceitos na query string das rotas de relatório.
+/// `inicio` e `fim` são datas no fuso da loja (inclusive); sem elas, valem os últimos 30 dias.
+/// Os demais campos só são usados pelas rotas indicadas.
+#[derive(Deserialize)]
+pub struct FiltroRelatorio {
+    pub inicio: Option<NaiveDate>,
+    pub fim: Option<NaiveDate>,
+    pub formato: Option<FormatoRelatorio>,
+    pub periodo: Option<Periodicidade>,             // /relatorios/faturamento
+    pub ordem: Option<OrdemProdutos>,               // /relatorios/produtos-mais-vendidos
+    pub limite: Option<i64>,                        // /relatorios/produtos-mais-vendidos
+    pub agrupamento: Option<AgrupamentoCategorias>, // /relatorios/vendas-por-categoria
+}
+
+/// Formato da resposta: JSON (padrão) ou arquivo CSV.
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatoRelatorio {
+    Json,
+    Csv,
+}
+
+/// Agrupamento do faturamento no tempo.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Periodicidade {
+    Dia,
+    Semana, // Semanas começam na segunda-feira
+    Mes,
+}
+
+impl Periodicidade {
+    /// Unidade usada em `date_trunc` e no passo de `generate_series`.
+    pub fn unidade_sql(&self) -> &'static str {
+        match self {
+            Periodicidade::Dia => "day",
+            Periodicidade::Semana => "week",
+            Periodicidade::Mes => "month",
+        }
+    }
+}
+
+/// Critério de ordenação dos produtos mais vendidos.
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrdemProdutos {
+    Unidades,
+    Faturamento,
+}
+
+/// Nível da hierarquia de `categorias` usado no agrupamento das vendas.
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgrupamentoCategorias {
+    Sessao,    // Categoria raiz (sem parent_id), somando todas as subcategorias
+    Categoria, // Categoria do próprio produto
+}
+
+/// Faturamento de um dia, semana ou mês. Períodos sem vendas aparecem zerados.
+#[derive(Serialize, FromRow)]
+pub struct FaturamentoPeriodo {
+    pub periodo: NaiveDate, // Primeiro dia do período
+    pub pedidos: i64,
+    pub faturamento: BigDecimal, // Itens mais frete
+    pub ticket_medio: BigDecimal,
+}
+
+impl LinhaCsv for FaturamentoPeriodo {
+    const CABECALHO: &'static [&'static str] = &["periodo", "pedidos", "faturamento", "ticket_medio"];
+
+    fn campos(&self) -> Vec<String> {
+        vec![self.periodo.to_string(), self.pedidos.to_string(), self.faturamento.to_string(), self.ticket_medio.to_string()]
+    }
+}
+
+/// Produto no ranking de mais vendidos.
+#[derive(Serialize, FromRow)]
+pub struct ProdutoMaisVendido {
+    pub produto_id: i32,
+    pub nome: String,
+    pub unidades: i64,
+    pub faturamento: BigDecimal, // Soma dos itens ao preço praticado, sem frete
+}
+
+impl LinhaCsv for ProdutoMaisVendido {
+    const CABECALHO: &'static [&'static str] = &["produto_id", "nome", "unidades", "faturamento"];
+
+    fn campos(&self) -> Vec<String> {
+        vec![self.produto_id.to_string(), self.nome.clone(), self.unidades.to_string(), self.faturamento.to_string()]
+    }
+}
+
+/// Vendas de uma sessão ou categoria.
+#[derive(Serialize, FromRow)]
+pub struct VendasPorCategoria {
+    pub categoria_id: i32,
+    pub nome: String,
+    pub pedidos: i64,
+    pub unidades: i64,
+    pub faturamento: BigDecimal, // Soma dos itens ao preço praticado, sem frete
+}
+
+impl LinhaCsv for VendasPorCategoria {
+    const CABECALHO: &'static [&'static str] = &["categoria_id", "nome", "pedidos", "unidades", "faturamento"];
+
+    fn campos(&self) -> Vec<String> {
+        vec![
+            self.categoria_id.to_string(),
+            self.nome.clone(),
+            self.pedidos.to_string(),
+            self.unidades.to_string(),
+            self.faturamento.to_string(),
+        ]
+    }
+}
+
+/// Indicadores gerais do período: pedidos, faturamento, ticket médio e conversão de sacola em compra.
+#[derive(Serialize, FromRow)]
+pub struct ResumoVendas {
+    pub inicio: NaiveDate,
+    pub fim: NaiveDate,
+    pub pedidos: i64,
+    pub faturamento: BigDecimal, // Itens mais frete
+    pub ticket_medio: BigDecimal,
+    pub sacolas: i64,             // Sacolas iniciadas no período
+    pub sacolas_convertidas: i64, // Das quais viraram pedido pago
+    pub taxa_conversao: Option<BigDecimal>, // Percentual; ausente se nenhuma sacola foi iniciada
+}
+
+impl LinhaCsv for ResumoVendas {
+    const CABECALHO: &'static [&'static str] = &[
+        "inicio", "fim", "pedidos", "faturamento", "ticket_medio", "sacolas", "sacolas_convertidas", "taxa_conversao",
+    ];
+
+    fn campos(&self) -> Vec<String> {
+        vec![
+            self.inicio.to_string(),
+            self.fim.to_string(),
+            self.pedidos.to_string(),
+            self.faturamento.to_string(),
+            self.ticket_medio.to_string(),
+            self.sacolas.to_string(),
+            self.sacolas_convertidas.to_string(),
+            self.taxa_conversao.as_ref().map(|taxa| taxa.to_string()).unwrap_or_default(),
+        ]
+    }
+}
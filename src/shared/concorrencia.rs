@@ -0,0 +1,57 @@
+// src/shared/concorrencia.rs
+
+use actix_web::{http::header, HttpRequest, HttpResponse};
+
+use super::shared_structs::GenericResponse;
+
+/// Monta o valor do cabeçalho `ETag` a partir da versão do registro (ex.: `"3"`).
+pub fn etag_da_versao(versao: i32) -> String {
+    format!("\"{}\"", versao)
+}
+
+/// Extrai do cabeçalho `If-Match` a versão sobre a qual o cliente fez a edição.
+///
+/// O cabeçalho é obrigatório nas atualizações: sem ele retorna 428 (Precondition Required),
+/// e com um valor que não seja um ETag emitido pela API retorna 400.
+pub fn versao_do_if_match(req: &HttpRequest) -> Result<i32, HttpResponse> {
+    let valor = match req.headers().get(header::IF_MATCH).and_then(|v| v.to_str().ok()) {
+        Some(v) if v.trim() != "*" => v.trim(),
+        _ => {
+            return Err(HttpResponse::PreconditionRequired().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "O cabeçalho If-Match com o ETag do registro é obrigatório para atualizações.".to_string(),
+                body: None,
+            }));
+        }
+    };
+
+    match valor.trim_matches('"').parse::<i32>() {
+        Ok(versao) => Ok(versao),
+        Err(_) => Err(HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Valor de If-Match inválido. Use o ETag retornado pela consulta do registro.".to_string(),
+            body: None,
+        })),
+    }
+}
+
+/// Resposta 412 (Precondition Failed) para quando o registro foi alterado por outra pessoa.
+/// Devolve o ETag atual para que o cliente possa recarregar e reaplicar a edição.
+pub fn conflito_de_versao(message: String, versao_atual: i32) -> HttpResponse {
+    HttpResponse::PreconditionFailed()
+        .insert_header((header::ETAG, etag_da_versao(versao_atual)))
+        .json(GenericResponse {
+            status: "error".to_string(),
+            message,
+            body: Some(serde_json::json!({ "versao_atual": versao_atual })),
+        })
+}
+
+/// Indica se o erro é um deadlock (40P01) ou uma falha de serialização (40001).
+/// Nesses casos o banco desfaz a transação, que pode ser repetida do início.
+pub fn erro_transitorio(e: &sqlx::Error) -> bool {
+    match e.as_database_error().and_then(|erro| erro.code()) {
+        Some(codigo) => codigo == "40P01" || codigo == "40001",
+        None => false,
+    }
+}
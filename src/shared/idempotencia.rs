@@ -0,0 +1,309 @@
+// src/shared/idempotencia.rs
+
+use actix_web::{body::to_bytes, http::StatusCode, HttpRequest, HttpResponse};
+use serde::Serialize;
+use sqlx::{query, Pool, Postgres, Row};
+use std::future::Future;
+
+use super::shared_structs::GenericResponse;
+use super::tokens::hash_sha256;
+
+/// Cabeçalho com a chave escolhida pelo cliente para identificar a operação.
+const CABECALHO_CHAVE: &str = "Idempotency-Key";
+
+/// Tamanho máximo aceito para a chave.
+const TAMANHO_MAXIMO_CHAVE: usize = 255;
+
+/// Por quanto tempo uma chave concluída continua valendo (em horas).
+const VALIDADE_CHAVE_HORAS: i32 = 24;
+
+/// Depois de quantos minutos uma requisição que não terminou (ex.: queda do servidor)
+/// deixa de bloquear a chave.
+const ABANDONO_EM_ANDAMENTO_MINUTOS: i32 = 5;
+
+/// Intervalo entre as limpezas de chaves vencidas.
+const INTERVALO_LIMPEZA_SEGUNDOS: u64 = 3600;
+
+/// Executa `processar` uma única vez por chave de idempotência.
+///
+/// Sem o cabeçalho `Idempotency-Key`, a requisição é processada normalmente. Com ele:
+/// - a primeira requisição é processada e a resposta é guardada por 24 horas;
+/// - repetições com o mesmo corpo recebem a resposta guardada (cabeçalho `Idempotent-Replayed: true`);
+/// - a mesma chave com outro corpo é recusada com 422;
+/// - uma repetição que chega enquanto a primeira ainda está em andamento recebe 409.
+///
+/// Respostas 5xx não são guardadas, para que o cliente possa tentar de novo com a mesma chave,
+/// exceto as marcadas com `com_efeito_gravado`: a operação já teve efeito e não pode ser refeita.
+/// A chave vale por rota e por usuário (`usuario_id` é `None` nas rotas sem login).
+pub async fn com_idempotencia<T, F>(
+    pool: &Pool<Postgres>,
+    req: &HttpRequest,
+    usuario_id: Option<i32>,
+    corpo: &T,
+    processar: F,
+) -> HttpResponse
+where
+    T: Serialize,
+    F: Future<Output = HttpResponse>,
+{
+    let chave = match req.headers().get(CABECALHO_CHAVE) {
+        None => return processar.await,
+        Some(valor) => match valor.to_str() {
+            Ok(chave) if !chave.is_empty() && chave.len() <= TAMANHO_MAXIMO_CHAVE => chave.to_string(),
+            _ => {
+                return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: format!("{} deve ter de 1 a {} caracteres ASCII.", CABECALHO_CHAVE, TAMANHO_MAXIMO_CHAVE),
+                    body: None,
+                });
+            }
+        },
+    };
+
+    let corpo_serializado = match serde_json::to_string(corpo) {
+        Ok(corpo) => corpo,
+        Err(e) => {
+            eprintln!("Erro ao serializar corpo para idempotência: {:?}", e);
+            return erro_interno();
+        }
+    };
+    let registro = Registro {
+        rota: req.match_pattern().unwrap_or_else(|| req.path().to_string()),
+        dono: usuario_id.unwrap_or(0),
+        chave,
+        hash_requisicao: hash_sha256(&format!("{} {}", req.method(), corpo_serializado)),
+    };
+
+    match reservar(pool, &registro).await {
+        Ok(None) => {},
+        Ok(Some(resposta)) => return resposta,
+        Err(e) => {
+            eprintln!("Erro ao reservar chave de idempotência {}: {:?}", registro.chave, e);
+            return erro_interno();
+        }
+    }
+
+    let resposta = processar.await;
+    concluir(pool, &registro, resposta).await
+}
+
+/// Marca de uma resposta dada depois que a operação já teve efeito (ex.: o pedido foi gravado).
+struct EfeitoGravado;
+
+/// Marca a resposta como dada depois que a operação já teve efeito. Mesmo sendo um erro 5xx,
+/// ela é guardada, e as repetições com a mesma chave recebem essa resposta em vez de refazer a operação.
+pub fn com_efeito_gravado(mut resposta: HttpResponse) -> HttpResponse {
+    resposta.extensions_mut().insert(EfeitoGravado);
+    resposta
+}
+
+/// Identificação de uma requisição idempotente.
+struct Registro {
+    rota: String,
+    dono: i32, // ID do usuário, ou 0 nas rotas sem login
+    chave: String,
+    hash_requisicao: String,
+}
+
+/// Reserva a chave para esta requisição. Se a chave já existir, devolve a resposta a enviar
+/// ao cliente (a guardada, ou um erro de conflito).
+async fn reservar(pool: &Pool<Postgres>, registro: &Registro) -> Result<Option<HttpResponse>, sqlx::Error> {
+    // Libera a chave se ela venceu ou se a requisição anterior foi abandonada
+    query(
+        r#"
+        DELETE FROM idempotencia
+        WHERE rota = $1 AND dono = $2 AND chave = $3
+          AND (criado_em < NOW() - make_interval(hours => $4)
+               OR (status_http IS NULL AND criado_em < NOW() - make_interval(mins => $5)))
+        "#
+    )
+    .bind(&registro.rota)
+    .bind(registro.dono)
+    .bind(&registro.chave)
+    .bind(VALIDADE_CHAVE_HORAS)
+    .bind(ABANDONO_EM_ANDAMENTO_MINUTOS)
+    .execute(pool)
+    .await?;
+
+    let reservada = query(
+        r#"
+        INSERT INTO idempotencia (rota, dono, chave, hash_requisicao)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .bind(&registro.rota)
+    .bind(registro.dono)
+    .bind(&registro.chave)
+    .bind(&registro.hash_requisicao)
+    .execute(pool)
+    .await?;
+
+    if reservada.rows_affected() == 1 {
+        return Ok(None);
+    }
+
+    let existente = query(
+        "SELECT hash_requisicao, status_http, corpo_resposta FROM idempotencia WHERE rota = $1 AND dono = $2 AND chave = $3"
+    )
+    .bind(&registro.rota)
+    .bind(registro.dono)
+    .bind(&registro.chave)
+    .fetch_optional(pool)
+    .await?;
+
+    let existente = match existente {
+        Some(row) => row,
+        // A chave foi liberada entre o INSERT e o SELECT; o cliente pode simplesmente repetir
+        None => return Ok(Some(em_andamento())),
+    };
+
+    let hash: String = existente.try_get("hash_requisicao")?;
+    if hash != registro.hash_requisicao {
+        return Ok(Some(HttpResponse::UnprocessableEntity().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("A chave {} já foi usada com outra requisição.", CABECALHO_CHAVE),
+            body: None,
+        })));
+    }
+
+    let status_http: Option<i16> = existente.try_get("status_http")?;
+    let corpo: Option<Vec<u8>> = existente.try_get("corpo_resposta")?;
+    match (status_http.and_then(|s| StatusCode::from_u16(s as u16).ok()), corpo) {
+        (Some(status), Some(corpo)) => Ok(Some(
+            HttpResponse::build(status)
+                .content_type("application/json")
+                .insert_header(("Idempotent-Replayed", "true"))
+                .body(corpo),
+        )),
+        _ => Ok(Some(em_andamento())),
+    }
+}
+
+/// Guarda a resposta da requisição para as repetições e a devolve ao cliente.
+async fn concluir(pool: &Pool<Postgres>, registro: &Registro, resposta: HttpResponse) -> HttpResponse {
+    let status = resposta.status();
+    if status.is_server_error() && resposta.extensions().get::<EfeitoGravado>().is_none() {
+        liberar(pool, registro).await;
+        return resposta;
+    }
+
+    let (resposta, corpo) = resposta.into_parts();
+    let corpo = match to_bytes(corpo).await {
+        Ok(corpo) => corpo,
+        Err(e) => {
+            eprintln!("Erro ao ler resposta para idempotência: {:?}", e);
+            liberar(pool, registro).await;
+            return erro_interno();
+        }
+    };
+
+    let gravacao = query(
+        r#"
+        UPDATE idempotencia SET status_http = $4, corpo_resposta = $5, concluido_em = NOW()
+        WHERE rota = $1 AND dono = $2 AND chave = $3
+        "#
+    )
+    .bind(&registro.rota)
+    .bind(registro.dono)
+    .bind(&registro.chave)
+    .bind(status.as_u16() as i16)
+    .bind(corpo.as_ref())
+    .execute(pool)
+    .await;
+
+    if let Err(e) = gravacao {
+        // A operação já foi feita; só a repetição deixará de devolver a mesma resposta
+        eprintln!("Erro ao guardar resposta da chave de idempotência {}: {:?}", registro.chave, e);
+    }
+    resposta.set_body(corpo).map_into_boxed_body()
+}
+
+/// Remove a reserva da chave, permitindo uma nova tentativa.
+async fn liberar(pool: &Pool<Postgres>, registro: &Registro) {
+    let resultado = query("DELETE FROM idempotencia WHERE rota = $1 AND dono = $2 AND chave = $3")
+        .bind(&registro.rota)
+        .bind(registro.dono)
+        .bind(&registro.chave)
+        .execute(pool)
+        .await;
+    if let Err(e) = resultado {
+        eprintln!("Erro ao liberar chave de idempotência {}: {:?}", registro.chave, e);
+    }
+}
+
+fn em_andamento() -> HttpResponse {
+    HttpResponse::Conflict()
+        .insert_header(("Retry-After", "1"))
+        .json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Uma requisição com esta chave ainda está em andamento. Tente novamente em instantes.".to_string(),
+            body: None,
+        })
+}
+
+fn erro_interno() -> HttpResponse {
+    HttpResponse::InternalServerError().json(GenericResponse::<()>{
+        status: "error".to_string(),
+        message: "Erro interno ao processar a requisição.".to_string(),
+        body: None,
+    })
+}
+
+/// Agenda a remoção periódica das chaves vencidas enquanto a aplicação estiver no ar.
+pub fn iniciar_limpeza_idempotencia(pool: Pool<Postgres>) {
+    actix_web::rt::spawn(async move {
+        let mut intervalo = actix_web::rt::time::interval(std::time::Duration::from_secs(INTERVALO_LIMPEZA_SEGUNDOS));
+        loop {
+            intervalo.tick().await;
+            let resultado = query("DELETE FROM idempotencia WHERE criado_em < NOW() - make_interval(hours => $1)")
+                .bind(VALIDADE_CHAVE_HORAS)
+                .execute(&pool)
+                .await;
+            if let Err(e) = resultado {
+                eprintln!("Erro ao remover chaves de idempotência vencidas: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use std::cell::Cell;
+
+    use crate::shared::testes::{estado_de_teste, gateway_de_teste};
+    use crate::shared::tokens::gerar_token;
+
+    #[actix_web::test]
+    async fn erro_depois_do_efeito_fica_guardado_e_os_demais_liberam_a_chave() {
+        let data = match estado_de_teste(gateway_de_teste()).await {
+            Some(data) => data,
+            None => return,
+        };
+        let execucoes = Cell::new(0);
+        let falhar = |gravado: bool| {
+            let execucoes = &execucoes;
+            async move {
+                execucoes.set(execucoes.get() + 1);
+                let resposta = HttpResponse::InternalServerError().finish();
+                if gravado { com_efeito_gravado(resposta) } else { resposta }
+            }
+        };
+
+        // Erro antes de qualquer efeito: a chave é liberada e a repetição executa de novo
+        let req = TestRequest::post().uri("/teste").insert_header((CABECALHO_CHAVE, gerar_token())).to_http_request();
+        assert_eq!(com_idempotencia(&data.db_pool, &req, Some(1), &"corpo", falhar(false)).await.status(), 500);
+        assert_eq!(com_idempotencia(&data.db_pool, &req, Some(1), &"corpo", falhar(false)).await.status(), 500);
+        assert_eq!(execucoes.get(), 2);
+
+        // Erro depois do efeito: a repetição recebe a resposta guardada sem executar de novo
+        let req = TestRequest::post().uri("/teste").insert_header((CABECALHO_CHAVE, gerar_token())).to_http_request();
+        assert_eq!(com_idempotencia(&data.db_pool, &req, Some(1), &"corpo", falhar(true)).await.status(), 500);
+        let repeticao = com_idempotencia(&data.db_pool, &req, Some(1), &"corpo", falhar(true)).await;
+        assert_eq!(repeticao.status(), 500);
+        assert_eq!(repeticao.headers().get("Idempotent-Replayed").unwrap(), "true");
+        assert_eq!(execucoes.get(), 3);
+    }
+}
@@ -2,3 +2,16 @@
 
 // Declara o submódulo que contém as structs compartilhadas
 pub mod shared_structs;
+// Declara o submódulo com os auxiliares de controle de concorrência otimista (ETag/If-Match)
+pub mod concorrencia;
+// Declara o submódulo com validações de dados brasileiros (CPF, telefone)
+pub mod validacoes;
+// Declara o submódulo com a geração e o hash de tokens de uso único
+pub mod tokens;
+// Declara o submódulo que garante uma única execução por chave de idempotência (Idempotency-Key)
+pub mod idempotencia;
+// Declara o submódulo com o redirecionamento de slugs antigos para o endereço atual
+pub mod slugs;
+// Declara o submódulo com os auxiliares dos testes que usam o banco
+#[cfg(test)]
+pub mod testes;
@@ -1,6 +1,6 @@
 // src/shared/shared_structs.rs
 
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// Estrutura genérica para padronizar as respostas da API.
 /// 'T' é o tipo do corpo da resposta, que pode ser opcional.
@@ -11,3 +11,13 @@ pub struct GenericResponse<T> {
     #[serde(skip_serializing_if = "Option::is_none")] // Não serializa 'body' se for None
     pub body: Option<T>,
 }
+
+/// Diferencia um campo ausente (`None`) de um campo enviado como `null` (`Some(None)`)
+/// nas atualizações parciais. Uso: `#[serde(default, deserialize_with = "campo_anulavel")]`.
+pub fn campo_anulavel<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,19 @@
+// src/shared/slugs.rs
+
+use actix_web::{http::header, HttpResponse};
+
+use super::shared_structs::GenericResponse;
+
+/// Resposta 301 para um slug antigo: aponta no cabeçalho `Location` o endereço atual,
+/// que também vai no corpo para os clientes que não seguem redirecionamentos.
+/// Os slugs são gerados e guardados pelo banco (ver `migrations/0020_slugs.sql`).
+pub fn redirecionar_para_slug(caminho_base: &str, slug_atual: &str) -> HttpResponse {
+    let endereco = format!("{}/{}", caminho_base, slug_atual);
+    HttpResponse::MovedPermanently()
+        .insert_header((header::LOCATION, endereco.clone()))
+        .json(GenericResponse {
+            status: "success".to_string(),
+            message: "Este endereço mudou. Use o slug atual.".to_string(),
+            body: Some(serde_json::json!({ "slug": slug_atual, "endereco": endereco })),
+        })
+}
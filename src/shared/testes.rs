@@ -0,0 +1,111 @@
+// src/shared/testes.rs
+//
+// Auxiliares dos testes que usam o banco. Esses testes rodam contra o banco apontado por
+// BELLAVIBE_TESTE_DATABASE_URL, que deve ter o mesmo esquema da aplicação (com todas as
+// migrações); sem a variável, eles são pulados. Cada teste cria os próprios dados.
+
+use actix_web::web;
+use bigdecimal::BigDecimal;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+use crate::email::mailer::LogMailer;
+use crate::frete::calculadora::modalidades_padrao;
+use crate::pagamentos::gateway::{GatewayMock, GatewayPagamento};
+use crate::pagamentos::pix::ConfiguracaoPix;
+use crate::shared::tokens::gerar_token;
+use crate::usuarios::chaves_jwt::ChavesJwt;
+use crate::usuarios::usuario_structs::Claims;
+use crate::AppState;
+
+/// Chave do webhook do `GatewayMock` usado nos testes.
+pub const CHAVE_WEBHOOK_TESTE: &str = "chave_de_webhook_dos_testes";
+
+/// Gateway simulado com a chave de webhook dos testes.
+pub fn gateway_de_teste() -> Arc<GatewayMock> {
+    let pix = ConfiguracaoPix {
+        chave: "pix@bellavibe.com.br".to_string(),
+        nome_recebedor: "BellaVibe".to_string(),
+        cidade: "São Paulo".to_string(),
+    };
+    Arc::new(GatewayMock::new(CHAVE_WEBHOOK_TESTE, pix))
+}
+
+/// Estado da aplicação ligado ao banco de testes, ou `None` (com aviso) se ele não estiver configurado.
+pub async fn estado_de_teste(gateway_pagamento: Arc<dyn GatewayPagamento>) -> Option<web::Data<AppState>> {
+    let database_url = match std::env::var("BELLAVIBE_TESTE_DATABASE_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("BELLAVIBE_TESTE_DATABASE_URL não definida; teste com banco pulado.");
+            return None;
+        }
+    };
+    let db_pool = PgPoolOptions::new()
+        .max_connections(20)
+        .connect(&database_url)
+        .await
+        .expect("Falha ao conectar ao banco de testes");
+    let chaves_jwt = ChavesJwt::carregar(std::env::temp_dir().join("bellavibe_testes_chaves_jwt"))
+        .expect("Falha ao carregar as chaves JWT dos testes");
+
+    Some(web::Data::new(AppState {
+        db_pool,
+        chaves_jwt,
+        mailer: Arc::new(LogMailer),
+        url_publica: "http://127.0.0.1:8080".to_string(),
+        chave_links: "chave_de_links_dos_testes".to_string(),
+        calculadoras_frete: modalidades_padrao(),
+        gateway_pagamento,
+    }))
+}
+
+/// Cria um cliente com e-mail único e devolve o ID.
+pub async fn criar_usuario(data: &web::Data<AppState>) -> i32 {
+    sqlx::query_scalar::<_, i32>("INSERT INTO usuarios (nome, email, senha_hash) VALUES ('Cliente de teste', $1, '-') RETURNING id")
+        .bind(format!("teste-{}@bellavibe.invalid", &gerar_token()[..16]))
+        .fetch_one(&data.db_pool)
+        .await
+        .expect("Falha ao criar usuário de teste")
+}
+
+/// Cria um administrador e devolve um cabeçalho `Authorization` com o JWT dele.
+pub async fn autorizacao_de_admin(data: &web::Data<AppState>) -> (&'static str, String) {
+    let email = format!("admin-{}@bellavibe.invalid", &gerar_token()[..16]);
+    let id = sqlx::query_scalar::<_, i32>("INSERT INTO usuarios (nome, email, senha_hash, perfil) VALUES ('Admin de teste', $1, '-', 'admin') RETURNING id")
+        .bind(&email)
+        .fetch_one(&data.db_pool)
+        .await
+        .expect("Falha ao criar administrador de teste");
+    let claims = Claims {
+        sub: id,
+        name: "Admin de teste".to_string(),
+        email,
+        perfil: "admin".to_string(),
+        exp: (chrono::Utc::now() + chrono::Duration::minutes(10)).timestamp(),
+    };
+    let token = data.chaves_jwt.assinar(&claims).expect("Falha ao assinar o JWT de teste");
+    ("Authorization", format!("Bearer {}", token))
+}
+/// Cria uma categoria e um produto nela, com o preço e o estoque informados, e devolve o ID do produto.
+pub async fn criar_produto(data: &web::Data<AppState>, preco: i32, estoque: i32) -> i32 {
+    let sufixo = &gerar_token()[..12];
+    let categoria_id = sqlx::query_scalar::<_, i32>("INSERT INTO categorias (nome, slug) VALUES ($1, $1) RETURNING id")
+        .bind(format!("teste-{}", sufixo))
+        .fetch_one(&data.db_pool)
+        .await
+        .expect("Falha ao criar categoria de teste");
+    sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO produtos (nome, slug, preco, estoque, categoria_id, peso_gramas, altura_cm, largura_cm, comprimento_cm)
+        VALUES ($1, $1, $2, $3, $4, 200, 10, 10, 10)
+        RETURNING id
+        "#
+    )
+    .bind(format!("produto-teste-{}", sufixo))
+    .bind(BigDecimal::from(preco))
+    .bind(estoque)
+    .bind(categoria_id)
+    .fetch_one(&data.db_pool)
+    .await
+    .expect("Falha ao criar produto de teste")
+}
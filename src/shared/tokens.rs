@@ -0,0 +1,41 @@
+// src/shared/tokens.rs
+
+use rand::RngCore;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Gera um token aleatório de 256 bits, codificado em hexadecimal (64 caracteres).
+/// Usado em links enviados por e-mail; apenas o hash é armazenado no banco.
+pub fn gerar_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Calcula o hash SHA-256 (em hexadecimal) de um token ou de qualquer conteúdo textual.
+/// Tokens de alta entropia dispensam salt: o hash basta para que um vazamento do banco
+/// não permita usar os tokens ainda válidos.
+pub fn hash_sha256(valor: &str) -> String {
+    hex::encode(Sha256::digest(valor.as_bytes()))
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Assina um conteúdo com HMAC-SHA256, retornando a assinatura em hexadecimal.
+/// Usado em links enviados por e-mail, que carregam os próprios dados e dispensam estado no banco.
+pub fn assinar(chave: &str, conteudo: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(chave.as_bytes()).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(conteudo.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Confere a assinatura HMAC-SHA256 de um conteúdo em tempo constante.
+pub fn assinatura_valida(chave: &str, conteudo: &str, assinatura: &str) -> bool {
+    let assinatura = match hex::decode(assinatura) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    let mut mac = HmacSha256::new_from_slice(chave.as_bytes()).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(conteudo.as_bytes());
+    mac.verify_slice(&assinatura).is_ok()
+}
@@ -0,0 +1,82 @@
+// src/shared/validacoes.rs
+
+/// Remove tudo o que não for dígito (pontuação de CPF, telefone, CEP etc.).
+pub fn somente_digitos(valor: &str) -> String {
+    valor.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// Valida um CPF (com ou sem pontuação) pelos dígitos verificadores.
+/// Sequências repetidas como "111.111.111-11" são rejeitadas, embora passem no cálculo.
+pub fn cpf_valido(cpf: &str) -> bool {
+    let digitos: Vec<u32> = somente_digitos(cpf).chars().filter_map(|c| c.to_digit(10)).collect();
+    if digitos.len() != 11 || digitos.iter().all(|&d| d == digitos[0]) {
+        return false;
+    }
+
+    let verificador = |quantidade: usize| -> u32 {
+        let soma: u32 = digitos[..quantidade]
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| d * (quantidade as u32 + 1 - i as u32))
+            .sum();
+        let resto = (soma * 10) % 11;
+        if resto == 10 { 0 } else { resto }
+    };
+
+    verificador(9) == digitos[9] && verificador(10) == digitos[10]
+}
+
+/// Valida um telefone brasileiro com DDD: 10 dígitos (fixo) ou 11 dígitos (celular).
+pub fn telefone_valido(telefone: &str) -> bool {
+    let digitos = somente_digitos(telefone);
+    digitos.len() == 10 || digitos.len() == 11
+}
+
+/// Valida o formato de um CEP: "12345-678" ou "12345678".
+/// O CEP "00000-000" não existe e é rejeitado.
+pub fn cep_valido(cep: &str) -> bool {
+    let cep = cep.trim();
+    // O tamanho é contado em bytes: sem esta verificação, um acento cortaria um caractere ao meio
+    if !cep.is_ascii() {
+        return false;
+    }
+    let formato_ok = match cep.len() {
+        8 => cep.chars().all(|c| c.is_ascii_digit()),
+        9 => cep[..5].chars().all(|c| c.is_ascii_digit()) && &cep[5..6] == "-" && cep[6..].chars().all(|c| c.is_ascii_digit()),
+        _ => false,
+    };
+    formato_ok && somente_digitos(cep) != "00000000"
+}
+
+/// Siglas das unidades federativas brasileiras.
+const UFS: [&str; 27] = [
+    "AC", "AL", "AP", "AM", "BA", "CE", "DF", "ES", "GO", "MA", "MT", "MS", "MG", "PA",
+    "PB", "PR", "PE", "PI", "RJ", "RN", "RS", "RO", "RR", "SC", "SP", "SE", "TO",
+];
+
+/// Valida a sigla de uma unidade federativa (maiúsculas ou minúsculas).
+pub fn uf_valida(uf: &str) -> bool {
+    UFS.contains(&uf.trim().to_uppercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cep_aceita_os_dois_formatos() {
+        assert!(cep_valido("01310-100"));
+        assert!(cep_valido(" 01310100 "));
+        assert!(!cep_valido("00000-000"));
+        assert!(!cep_valido("0131-0100"));
+        assert!(!cep_valido("0131010"));
+    }
+
+    #[test]
+    fn cep_com_caractere_fora_do_ascii_e_recusado_sem_panico() {
+        // 9 bytes, com o caractere de 2 bytes em posições que cortariam o fatiamento
+        assert!(!cep_valido("1234é678"));
+        assert!(!cep_valido("12345-é6"));
+        assert!(!cep_valido("12345é78"));
+    }
+}
@@ -2,12 +2,11 @@
 
 use actix_web::{
     dev::Payload,
-    error::ErrorUnauthorized,
+    error::{ErrorForbidden, ErrorUnauthorized},
     FromRequest, HttpRequest, web
 };
 
 use futures::future::{ready, Ready};
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 
 // Importa as Claims do módulo de structs de usuário
 use super::usuario_structs::Claims;
@@ -21,8 +20,21 @@ pub struct AuthenticatedUser {
     pub user_id: i32,
     pub user_name: String,
     pub user_email: String,    
+    pub perfil: String,
 }
 
+impl AuthenticatedUser {
+    /// Indica se o usuário autenticado possui perfil de administrador.
+    pub fn is_admin(&self) -> bool {
+        self.perfil == "admin"
+    }
+}
+
+/// Struct que representa um usuário autenticado com perfil de administrador.
+/// Usada nas rotas restritas à administração da loja.
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub AuthenticatedUser);
+
 /// Extrator de autenticação para Actix Web.
 /// Este extrator tenta validar um token JWT presente no cabeçalho Authorization.
 impl FromRequest for AuthenticatedUser {
@@ -30,13 +42,11 @@ impl FromRequest for AuthenticatedUser {
     type Future = Ready<Result<Self, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        // Acessa o AppState para obter a chave secreta JWT
-        let app_state = req.app_data::<web::Data<AppState>>();
-
-        let jwt_secret = match app_state {
-            Some(state) => state.jwt_secret.clone(),
+        // Acessa o AppState para obter as chaves de verificação do JWT
+        let app_state = match req.app_data::<web::Data<AppState>>() {
+            Some(state) => state,
             None => {
-                eprintln!("Erro: AppState ou jwt_secret não disponível no extrator.");
+                eprintln!("Erro: AppState não disponível no extrator.");
                 return ready(Err(ErrorUnauthorized("Erro de configuração do servidor.")));
             }
         };
@@ -63,18 +73,9 @@ impl FromRequest for AuthenticatedUser {
             }
         };
 
-        // Configuração de validação do JWT
-        let validation = Validation::new(Algorithm::HS256);
-        // Você pode adicionar mais validações aqui, como 'iss' (issuer) ou 'aud' (audience)
-        // validation.validate_exp = true; // Já é true por padrão
-        // validation.leeway = 60; // Permite uma pequena margem de erro no tempo de expiração (60 segundos)
-
-        // Decodifica e valida o token
-        let token_data = match decode::<Claims>(
-            &token,
-            &DecodingKey::from_secret(jwt_secret.as_ref()),
-            &validation,
-        ) {
+        // Decodifica e valida o token (EdDSA, com a chave indicada pelo 'kid' do cabeçalho).
+        // A expiração ('exp') é validada por padrão.
+        let token_data = match app_state.chaves_jwt.verificar::<Claims>(&token) {
             Ok(data) => data,
             Err(e) => {
                 eprintln!("Erro ao decodificar/validar JWT: {:?}", e);
@@ -93,9 +94,25 @@ impl FromRequest for AuthenticatedUser {
             user_id: token_data.claims.sub,
             user_name: token_data.claims.name,
             user_email: token_data.claims.email,
+            perfil: token_data.claims.perfil,
         };
         
 
         ready(Ok(authenticated_user))
     }
 }
+
+/// Extrator de autorização para rotas administrativas.
+/// Reaproveita a validação do JWT de `AuthenticatedUser` e exige o perfil "admin".
+impl FromRequest for AdminUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        match AuthenticatedUser::from_request(req, payload).into_inner() {
+            Ok(user) if user.is_admin() => ready(Ok(AdminUser(user))),
+            Ok(_) => ready(Err(ErrorForbidden("Acesso restrito a administradores."))),
+            Err(e) => ready(Err(e)),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// src/usuarios/chaves_jwt.rs
+//
+// Chaves de assinatura dos tokens de acesso (EdDSA / Ed25519).
+//
+// Cada chave é identificada por um `kid` e fica em um arquivo `<kid>.pk8` (PKCS#8 DER)
+// no diretório configurado. A chave mais recente assina os novos tokens; as anteriores
+// continuam valendo para verificação até serem removidas, o que permite a rotação sem
+// derrubar as sessões abertas. As chaves públicas são publicadas em `/.well-known/jwks.json`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use rand::Rng;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Extensão dos arquivos de chave privada.
+const EXTENSAO_CHAVE: &str = "pk8";
+
+/// Uma chave de assinatura carregada em memória.
+struct ChaveJwt {
+    kid: String,
+    privada: EncodingKey,
+    publica: Vec<u8>,
+}
+
+/// Conjunto de chaves de assinatura dos tokens de acesso.
+/// A última chave da lista (maior `kid`, que começa pela data de criação) é a ativa.
+pub struct ChavesJwt {
+    diretorio: PathBuf,
+    chaves: RwLock<Vec<ChaveJwt>>,
+}
+
+/// Resumo de uma chave, usado na listagem administrativa.
+#[derive(Debug, Serialize)]
+pub struct ResumoChaveJwt {
+    pub kid: String,
+    pub ativa: bool,
+}
+
+impl ChavesJwt {
+    /// Carrega as chaves do diretório informado, criando-o com uma chave nova se estiver vazio.
+    pub fn carregar(diretorio: impl Into<PathBuf>) -> io::Result<Self> {
+        let diretorio = diretorio.into();
+        fs::create_dir_all(&diretorio)?;
+
+        let mut chaves = Vec::new();
+        for entrada in fs::read_dir(&diretorio)? {
+            let caminho = entrada?.path();
+            if caminho.extension().and_then(|e| e.to_str()) != Some(EXTENSAO_CHAVE) {
+                continue;
+            }
+            let kid = match caminho.file_stem().and_then(|s| s.to_str()) {
+                Some(kid) => kid.to_string(),
+                None => continue,
+            };
+            chaves.push(ler_chave(&caminho, kid)?);
+        }
+        chaves.sort_by(|a, b| a.kid.cmp(&b.kid));
+
+        let conjunto = ChavesJwt { diretorio, chaves: RwLock::new(chaves) };
+        if conjunto.chaves.read().unwrap().is_empty() {
+            let kid = conjunto.rotacionar()?;
+            println!("Nenhuma chave JWT encontrada. Chave {} criada.", kid);
+        }
+        Ok(conjunto)
+    }
+
+    /// Gera uma nova chave, grava no diretório e passa a usá-la para assinar os tokens.
+    /// As chaves anteriores continuam aceitas na verificação. Retorna o `kid` da nova chave.
+    pub fn rotacionar(&self) -> io::Result<String> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| io::Error::other("falha ao gerar chave Ed25519"))?;
+        let kid = format!("{}-{:04x}", Utc::now().format("%Y%m%d%H%M%S%3f"), rand::thread_rng().gen::<u16>());
+        let caminho = self.diretorio.join(format!("{}.{}", kid, EXTENSAO_CHAVE));
+        gravar_chave_privada(&caminho, pkcs8.as_ref())?;
+
+        let chave = ler_chave(&caminho, kid.clone())?;
+        let mut chaves = self.chaves.write().unwrap();
+        chaves.push(chave);
+        chaves.sort_by(|a, b| a.kid.cmp(&b.kid));
+        Ok(kid)
+    }
+
+    /// Remove uma chave antiga. Tokens assinados por ela deixam de ser aceitos.
+    /// Retorna `Ok(false)` se a chave não existe e erro se for a chave ativa.
+    pub fn remover(&self, kid: &str) -> io::Result<bool> {
+        let mut chaves = self.chaves.write().unwrap();
+        let posicao = match chaves.iter().position(|c| c.kid == kid) {
+            Some(posicao) => posicao,
+            None => return Ok(false),
+        };
+        if posicao == chaves.len() - 1 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "a chave ativa não pode ser removida"));
+        }
+        fs::remove_file(self.diretorio.join(format!("{}.{}", kid, EXTENSAO_CHAVE)))?;
+        chaves.remove(posicao);
+        Ok(true)
+    }
+
+    /// Lista as chaves carregadas, da mais antiga para a mais recente.
+    pub fn listar(&self) -> Vec<ResumoChaveJwt> {
+        let chaves = self.chaves.read().unwrap();
+        let ultima = chaves.len().saturating_sub(1);
+        chaves
+            .iter()
+            .enumerate()
+            .map(|(i, c)| ResumoChaveJwt { kid: c.kid.clone(), ativa: i == ultima })
+            .collect()
+    }
+
+    /// Assina as claims com a chave ativa, informando o `kid` no cabeçalho do token.
+    pub fn assinar<T: Serialize>(&self, claims: &T) -> Result<String, Error> {
+        let chaves = self.chaves.read().unwrap();
+        let ativa = chaves.last().ok_or_else(|| Error::from(ErrorKind::InvalidKeyFormat))?;
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(ativa.kid.clone());
+        encode(&header, claims, &ativa.privada)
+    }
+
+    /// Valida um token de acesso assinado por qualquer uma das chaves carregadas, escolhida pelo `kid`.
+    /// Tokens com a claim `aud` têm outra finalidade (ex.: desafio do login com dois fatores) e são recusados.
+    pub fn verificar<T: DeserializeOwned>(&self, token: &str) -> Result<TokenData<T>, Error> {
+        self.verificar_com_audiencia(token, None)
+    }
+
+    /// Valida um token emitido para a finalidade `audiencia`, que precisa vir na claim `aud`.
+    pub fn verificar_para<T: DeserializeOwned>(&self, token: &str, audiencia: &str) -> Result<TokenData<T>, Error> {
+        self.verificar_com_audiencia(token, Some(audiencia))
+    }
+
+    fn verificar_com_audiencia<T: DeserializeOwned>(&self, token: &str, audiencia: Option<&str>) -> Result<TokenData<T>, Error> {
+        let kid = decode_header(token)?.kid.ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+        let chaves = self.chaves.read().unwrap();
+        let chave = chaves
+            .iter()
+            .find(|c| c.kid == kid)
+            .ok_or_else(|| Error::from(ErrorKind::InvalidSignature))?;
+
+        let mut validacao = Validation::new(Algorithm::EdDSA);
+        if let Some(audiencia) = audiencia {
+            validacao.set_audience(&[audiencia]);
+            validacao.set_required_spec_claims(&["exp", "aud"]);
+        }
+        let dados = decode::<serde_json::Value>(token, &DecodingKey::from_ed_der(&chave.publica), &validacao)?;
+        if audiencia.is_none() && dados.claims.get("aud").is_some() {
+            return Err(ErrorKind::InvalidAudience.into());
+        }
+        Ok(TokenData { header: dados.header, claims: serde_json::from_value(dados.claims)? })
+    }
+
+    /// Monta o JWKS com as chaves públicas de todas as chaves aceitas na verificação.
+    pub fn jwks(&self) -> JwkSet {
+        let chaves = self.chaves.read().unwrap();
+        JwkSet {
+            keys: chaves
+                .iter()
+                .map(|c| Jwk {
+                    common: CommonParameters {
+                        public_key_use: Some(PublicKeyUse::Signature),
+                        algorithm: Some(Algorithm::EdDSA),
+                        key_id: Some(c.kid.clone()),
+                        ..Default::default()
+                    },
+                    algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: URL_SAFE_NO_PAD.encode(&c.publica),
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Lê um arquivo PKCS#8 e extrai a chave pública correspondente.
+fn ler_chave(caminho: &Path, kid: String) -> io::Result<ChaveJwt> {
+    let pkcs8 = fs::read(caminho)?;
+    let par = Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("chave inválida em {}: {}", caminho.display(), e))
+    })?;
+    Ok(ChaveJwt {
+        kid,
+        privada: EncodingKey::from_ed_der(&pkcs8),
+        publica: par.public_key().as_ref().to_vec(),
+    })
+}
+
+/// Grava a chave privada com permissão restrita ao dono do processo.
+fn gravar_chave_privada(caminho: &Path, conteudo: &[u8]) -> io::Result<()> {
+    let mut opcoes = fs::OpenOptions::new();
+    opcoes.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opcoes.mode(0o600);
+    }
+    io::Write::write_all(&mut opcoes.open(caminho)?, conteudo)
+}
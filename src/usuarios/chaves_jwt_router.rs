@@ -0,0 +1,95 @@
+// src/usuarios/chaves_jwt_router.rs
+
+use actix_web::{delete, get, http::header, post, web, HttpResponse};
+
+// Importa o extrator de administrador
+use super::auth_middleware::AdminUser;
+// Importa o resumo das chaves de assinatura
+use super::chaves_jwt::ResumoChaveJwt;
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+
+/// Rota pública com as chaves que assinam os tokens de acesso, no formato JWKS (RFC 7517).
+/// Outros serviços usam o `kid` do cabeçalho do token para escolher a chave de verificação.
+#[get("/.well-known/jwks.json")]
+pub async fn buscar_jwks(data: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .json(data.chaves_jwt.jwks())
+}
+
+/// Rota administrativa para listar as chaves de assinatura carregadas.
+#[get("/chaves-jwt")]
+pub async fn buscar_chaves_jwt(data: web::Data<AppState>, _admin: AdminUser) -> HttpResponse {
+    HttpResponse::Ok().json(GenericResponse::<Vec<ResumoChaveJwt>>{
+        status: "success".to_string(),
+        message: "Chaves de assinatura encontradas.".to_string(),
+        body: Some(data.chaves_jwt.listar()),
+    })
+}
+
+/// Rota administrativa para rotacionar a chave de assinatura.
+/// A nova chave passa a assinar os tokens; as anteriores seguem aceitas até serem removidas.
+#[post("/chaves-jwt/rotacionar")]
+pub async fn rotacionar_chave_jwt(data: web::Data<AppState>, admin: AdminUser) -> HttpResponse {
+    match data.chaves_jwt.rotacionar() {
+        Ok(kid) => {
+            println!("Chave JWT {} criada pelo administrador {}.", kid, admin.0.user_email);
+            HttpResponse::Created().json(GenericResponse::<Vec<ResumoChaveJwt>>{
+                status: "success".to_string(),
+                message: format!("Chave {} ativada. Remova as anteriores após a expiração dos tokens emitidos com elas.", kid),
+                body: Some(data.chaves_jwt.listar()),
+            })
+        },
+        Err(e) => {
+            eprintln!("Erro ao rotacionar chave JWT: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao rotacionar chave de assinatura.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota administrativa para remover uma chave antiga.
+/// Os tokens assinados por ela deixam de ser aceitos. A chave ativa não pode ser removida.
+#[delete("/chaves-jwt/{kid}")]
+pub async fn remover_chave_jwt(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let kid = path.into_inner();
+
+    match data.chaves_jwt.remover(&kid) {
+        Ok(true) => {
+            println!("Chave JWT {} removida pelo administrador {}.", kid, admin.0.user_email);
+            HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: format!("Chave {} removida.", kid),
+                body: None,
+            })
+        },
+        Ok(false) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Chave {} não encontrada.", kid),
+            body: None,
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => HttpResponse::Conflict().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "A chave ativa não pode ser removida. Rotacione antes de removê-la.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao remover chave JWT {}: {:?}", kid, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao remover chave de assinatura.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
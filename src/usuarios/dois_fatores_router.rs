@@ -0,0 +1,359 @@
+// src/usuarios/dois_fatores_router.rs
+
+use actix_web::{delete, http::header, post, web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use sqlx::{query, query_as, Row};
+
+// Importa as structs do módulo de usuários
+use super::usuario_structs::{Ativacao2fa, DesafioClaims, AUDIENCIA_DESAFIO_2FA, Desativacao2fa, Inscricao2faResponse, Login2faRequest, Usuario};
+// Importa o extrator de autenticação
+use super::auth_middleware::AuthenticatedUser;
+// Importa o cálculo dos códigos TOTP
+use super::totp::{gerar_codigos_recuperacao, gerar_segredo, normalizar_codigo_recuperacao, uri_provisionamento, verificar_codigo};
+// Importa a proteção contra força bruta, que também cobre o segundo passo do login
+use super::protecao_login::{
+    chave_email, chave_ip, registrar_evento, registrar_falha, registrar_sucesso, segundos_de_bloqueio, ResultadoLogin,
+};
+// Importa a conferência de senha e a emissão do token de acesso
+use super::usuario_router::{conferir_senha_atual, responder_login};
+// Importa o hash dos códigos de recuperação
+use crate::shared::tokens::hash_sha256;
+// Importa GenericResponse do módulo shared_structs
+use crate::shared::shared_structs::GenericResponse;
+// Importa o AppState do módulo raiz (main.rs)
+use crate::AppState;
+
+/// Quantidade de códigos de recuperação gerados na ativação.
+const QUANTIDADE_CODIGOS_RECUPERACAO: usize = 10;
+
+/// Rota para iniciar a inscrição em dois fatores.
+/// Gera um novo segredo TOTP (ainda inativo) e retorna a URI `otpauth://` para o QR code.
+/// A autenticação só passa a ser exigida após a confirmação em `/usuarios/me/2fa/ativar`.
+#[post("/usuarios/me/2fa")]
+pub async fn inscrever_2fa(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+) -> HttpResponse {
+    let segredo = gerar_segredo();
+    let result = query(
+        "UPDATE usuarios SET totp_segredo = $1 WHERE id = $2 AND totp_ativado_em IS NULL AND anonimizado_em IS NULL"
+    )
+    .bind(&segredo)
+    .bind(auth_user.user_id)
+    .execute(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Cadastre o segredo no autenticador e confirme com um código em /usuarios/me/2fa/ativar.".to_string(),
+            body: Some(Inscricao2faResponse {
+                uri_provisionamento: uri_provisionamento(&segredo, &auth_user.user_email),
+                segredo,
+            }),
+        }),
+        Ok(_) => HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "A autenticação em dois fatores já está ativa nesta conta.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao iniciar inscrição 2FA do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao iniciar autenticação em dois fatores.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para ativar a autenticação em dois fatores com o primeiro código do autenticador.
+/// Retorna os códigos de recuperação, que são exibidos apenas nesta resposta.
+#[post("/usuarios/me/2fa/ativar")]
+pub async fn ativar_2fa(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    item: web::Json<Ativacao2fa>,
+) -> HttpResponse {
+    // 1. Busca o segredo pendente de ativação
+    let segredo_result = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT totp_segredo FROM usuarios WHERE id = $1 AND totp_ativado_em IS NULL AND anonimizado_em IS NULL"
+    )
+    .bind(auth_user.user_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    let segredo = match segredo_result {
+        Ok(Some(Some(segredo))) => segredo,
+        Ok(_) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Não há inscrição em dois fatores pendente. Inicie por /usuarios/me/2fa.".to_string(),
+                body: None,
+            });
+        },
+        Err(e) => {
+            eprintln!("Erro ao buscar segredo 2FA do usuário {}: {:?}", auth_user.user_id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao ativar autenticação em dois fatores.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    // 2. Confere o código informado
+    let passo = match verificar_codigo(&segredo, &item.codigo, Utc::now().timestamp()) {
+        Some(passo) => passo,
+        None => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Código inválido. Confira o horário do dispositivo e tente novamente.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    // 3. Ativa e grava os hashes dos códigos de recuperação
+    let codigos = gerar_codigos_recuperacao(QUANTIDADE_CODIGOS_RECUPERACAO);
+    let ativacao: Result<(), sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+        query("UPDATE usuarios SET totp_ativado_em = NOW(), totp_ultimo_passo = $1 WHERE id = $2")
+            .bind(passo)
+            .bind(auth_user.user_id)
+            .execute(&mut *transaction)
+            .await?;
+        query("DELETE FROM codigos_recuperacao_2fa WHERE usuario_id = $1")
+            .bind(auth_user.user_id)
+            .execute(&mut *transaction)
+            .await?;
+        for codigo in &codigos {
+            query("INSERT INTO codigos_recuperacao_2fa (usuario_id, codigo_hash) VALUES ($1, $2)")
+                .bind(auth_user.user_id)
+                .bind(hash_sha256(&normalizar_codigo_recuperacao(codigo)))
+                .execute(&mut *transaction)
+                .await?;
+        }
+        transaction.commit().await
+    }
+    .await;
+
+    match ativacao {
+        Ok(()) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Autenticação em dois fatores ativada. Guarde os códigos de recuperação em local seguro.".to_string(),
+            body: Some(serde_json::json!({ "codigos_recuperacao": codigos })),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao ativar 2FA do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao ativar autenticação em dois fatores.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para desativar a autenticação em dois fatores.
+/// Exige a senha atual e um código válido do autenticador.
+#[delete("/usuarios/me/2fa")]
+pub async fn desativar_2fa(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    item: web::Json<Desativacao2fa>,
+) -> HttpResponse {
+    if let Err(resposta) = conferir_senha_atual(&data, auth_user.user_id, &item.senha).await {
+        return resposta;
+    }
+
+    match consumir_codigo_totp(&data, auth_user.user_id, &item.codigo).await {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Código inválido.".to_string(),
+                body: None,
+            });
+        },
+        Err(e) => {
+            eprintln!("Erro ao conferir código 2FA do usuário {}: {:?}", auth_user.user_id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao desativar autenticação em dois fatores.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    let desativacao: Result<(), sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+        query("UPDATE usuarios SET totp_segredo = NULL, totp_ativado_em = NULL, totp_ultimo_passo = NULL WHERE id = $1")
+            .bind(auth_user.user_id)
+            .execute(&mut *transaction)
+            .await?;
+        query("DELETE FROM codigos_recuperacao_2fa WHERE usuario_id = $1")
+            .bind(auth_user.user_id)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await
+    }
+    .await;
+
+    match desativacao {
+        Ok(()) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Autenticação em dois fatores desativada.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao desativar 2FA do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao desativar autenticação em dois fatores.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota do segundo passo do login com dois fatores.
+/// Troca o token de desafio emitido por `/usuarios/login` e um código TOTP (ou de recuperação)
+/// pelo token de acesso. Falhas contam para o mesmo bloqueio por e-mail e IP do login com senha.
+#[post("/usuarios/login/2fa")]
+pub async fn login_2fa(
+    data: web::Data<AppState>,
+    item: web::Json<Login2faRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let nao_autorizado = |mensagem: &str| HttpResponse::Unauthorized().json(GenericResponse::<()>{
+        status: "error".to_string(),
+        message: mensagem.to_string(),
+        body: None,
+    });
+    let erro_interno = || HttpResponse::InternalServerError().json(GenericResponse::<()>{
+        status: "error".to_string(),
+        message: "Erro interno ao processar login.".to_string(),
+        body: None,
+    });
+
+    // 1. Valida o token de desafio
+    let desafio = match data.chaves_jwt.verificar_para::<DesafioClaims>(&item.desafio_token, AUDIENCIA_DESAFIO_2FA) {
+        Ok(token_data) => token_data.claims,
+        _ => return nao_autorizado("Desafio de login inválido ou expirado. Faça o login novamente."),
+    };
+
+    // 2. Busca o usuário do desafio
+    let user_result = query_as::<_, Usuario>(
+        "SELECT id, nome, email, senha_hash, perfil, totp_ativado_em IS NOT NULL AS totp_ativo FROM usuarios WHERE id = $1 AND anonimizado_em IS NULL"
+    )
+    .bind(desafio.sub)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    let user = match user_result {
+        Ok(Some(u)) if u.totp_ativo => u,
+        Ok(_) => return nao_autorizado("Desafio de login inválido ou expirado. Faça o login novamente."),
+        Err(e) => {
+            eprintln!("Erro ao buscar usuário para login 2FA: {:?}", e);
+            return erro_interno();
+        }
+    };
+
+    // 3. Respeita o bloqueio por força bruta
+    let ip = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_else(|| "desconhecido".to_string());
+    match segundos_de_bloqueio(&data.db_pool, &[chave_email(&user.email), chave_ip(&ip)]).await {
+        Ok(Some(segundos)) => {
+            if let Err(e) = registrar_evento(&data.db_pool, &user.email, &ip, Some(user.id), ResultadoLogin::Bloqueado).await {
+                eprintln!("Erro ao registrar evento de login: {:?}", e);
+            }
+            return HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, segundos.max(1).to_string()))
+                .json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: format!("Muitas tentativas de login. Tente novamente em {} segundos.", segundos.max(1)),
+                    body: None,
+                });
+        },
+        Ok(None) => {},
+        Err(e) => {
+            eprintln!("Erro ao verificar bloqueio de login: {:?}", e);
+            return erro_interno();
+        }
+    }
+
+    // 4. Confere o código TOTP ou consome um código de recuperação
+    let conferencia = match (&item.codigo, &item.codigo_recuperacao) {
+        (Some(codigo), _) => consumir_codigo_totp(&data, user.id, codigo).await,
+        (None, Some(codigo_recuperacao)) => consumir_codigo_recuperacao(&data, user.id, codigo_recuperacao).await,
+        (None, None) => Ok(false),
+    };
+
+    match conferencia {
+        Ok(true) => {
+            if let Err(e) = registrar_sucesso(&data.db_pool, &user.email).await {
+                eprintln!("Erro ao zerar falhas de login: {:?}", e);
+            }
+            if let Err(e) = registrar_evento(&data.db_pool, &user.email, &ip, Some(user.id), ResultadoLogin::Sucesso).await {
+                eprintln!("Erro ao registrar evento de login: {:?}", e);
+            }
+            responder_login(&data, user)
+        },
+        Ok(false) => {
+            if let Err(e) = registrar_falha(&data.db_pool, &user.email, &ip).await {
+                eprintln!("Erro ao registrar falha de login: {:?}", e);
+            }
+            if let Err(e) = registrar_evento(&data.db_pool, &user.email, &ip, Some(user.id), ResultadoLogin::Falha).await {
+                eprintln!("Erro ao registrar evento de login: {:?}", e);
+            }
+            nao_autorizado("Código de verificação inválido.")
+        },
+        Err(e) => {
+            eprintln!("Erro ao conferir código 2FA do usuário {}: {:?}", user.id, e);
+            erro_interno()
+        }
+    }
+}
+
+/// Confere um código TOTP do usuário e o consome, gravando o passo aceito.
+/// A atualização condicional impede que o mesmo código (ou um anterior) seja usado duas vezes.
+async fn consumir_codigo_totp(data: &web::Data<AppState>, user_id: i32, codigo: &str) -> Result<bool, sqlx::Error> {
+    let row = query("SELECT totp_segredo FROM usuarios WHERE id = $1 AND totp_ativado_em IS NOT NULL")
+        .bind(user_id)
+        .fetch_optional(&data.db_pool)
+        .await?;
+
+    let segredo: String = match row {
+        Some(row) => row.try_get("totp_segredo")?,
+        None => return Ok(false),
+    };
+
+    let passo = match verificar_codigo(&segredo, codigo, Utc::now().timestamp()) {
+        Some(passo) => passo,
+        None => return Ok(false),
+    };
+
+    let res = query(
+        "UPDATE usuarios SET totp_ultimo_passo = $1 WHERE id = $2 AND (totp_ultimo_passo IS NULL OR totp_ultimo_passo < $1)"
+    )
+    .bind(passo)
+    .bind(user_id)
+    .execute(&data.db_pool)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+/// Consome um código de recuperação ainda não usado do usuário.
+async fn consumir_codigo_recuperacao(data: &web::Data<AppState>, user_id: i32, codigo: &str) -> Result<bool, sqlx::Error> {
+    let res = query(
+        "UPDATE codigos_recuperacao_2fa SET usado_em = NOW() WHERE usuario_id = $1 AND codigo_hash = $2 AND usado_em IS NULL"
+    )
+    .bind(user_id)
+    .bind(hash_sha256(&normalizar_codigo_recuperacao(codigo)))
+    .execute(&data.db_pool)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
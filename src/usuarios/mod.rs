@@ -6,3 +6,13 @@ pub mod usuario_structs;
 pub mod usuario_router;
 // Declara o novo submódulo para o middleware de autenticação
 pub mod auth_middleware; 
+// Declara o submódulo de proteção contra força bruta no login
+pub mod protecao_login;
+// Declara o submódulo com o cálculo dos códigos TOTP da autenticação em dois fatores
+pub mod totp;
+// Declara o submódulo com as rotas de autenticação em dois fatores
+pub mod dois_fatores_router;
+// Declara o submódulo com as chaves de assinatura dos tokens de acesso
+pub mod chaves_jwt;
+// Declara o submódulo com as rotas de JWKS e rotação de chaves
+pub mod chaves_jwt_router;
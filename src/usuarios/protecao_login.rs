@@ -0,0 +1,242 @@
+// src/usuarios/protecao_login.rs
+
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{query, Pool, Postgres};
+use std::sync::OnceLock;
+
+/// Falhas consecutivas por e-mail antes do bloqueio temporário da conta.
+const LIMITE_FALHAS_EMAIL: i32 = 5;
+/// Falhas por IP antes do bloqueio (mais tolerante, pois vários clientes podem compartilhar um IP).
+const LIMITE_FALHAS_IP: i32 = 20;
+/// Duração do primeiro bloqueio, em segundos; cada falha adicional dobra o tempo.
+const BLOQUEIO_INICIAL_SEGUNDOS: i64 = 60;
+/// Duração máxima de um bloqueio, em segundos.
+const BLOQUEIO_MAXIMO_SEGUNDOS: i64 = 60 * 60;
+/// Janela, em minutos, após a qual falhas antigas deixam de contar (contada a partir do fim do último bloqueio, se houver).
+const JANELA_FALHAS_MINUTOS: i64 = 15;
+
+/// Resultado de uma tentativa de login, gravado em `eventos_login`.
+pub enum ResultadoLogin {
+    Sucesso,
+    Falha,
+    Bloqueado,
+    DoisFatoresPendente, // Senha correta, aguardando o código do segundo fator
+}
+
+impl ResultadoLogin {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ResultadoLogin::Sucesso => "sucesso",
+            ResultadoLogin::Falha => "falha",
+            ResultadoLogin::Bloqueado => "bloqueado",
+            ResultadoLogin::DoisFatoresPendente => "2fa_pendente",
+        }
+    }
+}
+
+/// Chave de controle de falhas por e-mail (normalizado em minúsculas).
+pub fn chave_email(email: &str) -> String {
+    format!("email:{}", email.trim().to_lowercase())
+}
+
+/// Chave de controle de falhas por endereço IP.
+pub fn chave_ip(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+/// Hash bcrypt fictício, conferido quando o e-mail não existe para que a resposta
+/// leve o mesmo tempo de uma senha errada e não revele quais e-mails estão cadastrados.
+pub fn hash_ficticio() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash("senha-ficticia-para-tempo-constante", DEFAULT_COST).unwrap_or_default())
+}
+
+/// Retorna quantos segundos ainda faltam no bloqueio mais longo entre as chaves informadas,
+/// ou `None` se nenhuma delas estiver bloqueada.
+pub async fn segundos_de_bloqueio(pool: &Pool<Postgres>, chaves: &[String]) -> Result<Option<i64>, sqlx::Error> {
+    let restante = sqlx::query_scalar::<_, Option<f64>>(
+        r#"
+        SELECT MAX(EXTRACT(EPOCH FROM (bloqueado_ate - NOW())))::float8
+        FROM bloqueios_login
+        WHERE chave = ANY($1) AND bloqueado_ate > NOW()
+        "#
+    )
+    .bind(chaves)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(restante.map(|s| s.ceil() as i64))
+}
+
+/// Contabiliza uma falha para cada chave e aplica o bloqueio com backoff exponencial
+/// quando o limite da chave é atingido.
+pub async fn registrar_falha(pool: &Pool<Postgres>, email: &str, ip: &str) -> Result<(), sqlx::Error> {
+    for (chave, limite) in [(chave_email(email), LIMITE_FALHAS_EMAIL), (chave_ip(ip), LIMITE_FALHAS_IP)] {
+        let mut transaction = pool.begin().await?;
+
+        // Garante a linha da chave e a bloqueia, para que falhas simultâneas não se percam
+        query("INSERT INTO bloqueios_login (chave, falhas, ultima_falha) VALUES ($1, 0, NOW()) ON CONFLICT (chave) DO NOTHING")
+            .bind(&chave)
+            .execute(&mut *transaction)
+            .await?;
+        let (falhas, ultima_falha, bloqueado_ate, agora) = sqlx::query_as::<_, (i32, DateTime<Utc>, Option<DateTime<Utc>>, DateTime<Utc>)>(
+            "SELECT falhas, ultima_falha, bloqueado_ate, NOW() FROM bloqueios_login WHERE chave = $1 FOR UPDATE"
+        )
+        .bind(&chave)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let contador = ContadorFalhas { falhas, ultima_falha, bloqueado_ate }.com_nova_falha(limite, agora);
+        query("UPDATE bloqueios_login SET falhas = $2, ultima_falha = $3, bloqueado_ate = $4 WHERE chave = $1")
+            .bind(&chave)
+            .bind(contador.falhas)
+            .bind(contador.ultima_falha)
+            .bind(contador.bloqueado_ate)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Estado do contador de falhas de uma chave em `bloqueios_login`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ContadorFalhas {
+    falhas: i32,
+    ultima_falha: DateTime<Utc>,
+    bloqueado_ate: Option<DateTime<Utc>>,
+}
+
+impl ContadorFalhas {
+    /// Contabiliza uma falha ocorrida em `agora`. As falhas antigas deixam de contar depois da janela,
+    /// medida a partir da última falha ou do fim do último bloqueio, o que vier depois: assim quem
+    /// espera o bloqueio acabar para tentar de novo continua no backoff, em vez de recomeçar a contagem.
+    fn com_nova_falha(self, limite: i32, agora: DateTime<Utc>) -> ContadorFalhas {
+        let referencia = self.bloqueado_ate.map_or(self.ultima_falha, |fim| fim.max(self.ultima_falha));
+        let falhas = if referencia < agora - Duration::minutes(JANELA_FALHAS_MINUTOS) { 1 } else { self.falhas + 1 };
+        let bloqueado_ate = if falhas >= limite {
+            Some(agora + Duration::seconds(duracao_bloqueio(falhas - limite)))
+        } else {
+            self.bloqueado_ate
+        };
+        ContadorFalhas { falhas, ultima_falha: agora, bloqueado_ate }
+    }
+}
+
+/// Zera o contador de falhas do e-mail após um login bem-sucedido.
+/// O contador por IP é mantido e expira pela janela de falhas.
+pub async fn registrar_sucesso(pool: &Pool<Postgres>, email: &str) -> Result<(), sqlx::Error> {
+    query("DELETE FROM bloqueios_login WHERE chave = $1")
+        .bind(chave_email(email))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Grava um evento de login para auditoria e revisão pelos administradores.
+pub async fn registrar_evento(
+    pool: &Pool<Postgres>,
+    email: &str,
+    ip: &str,
+    usuario_id: Option<i32>,
+    resultado: ResultadoLogin,
+) -> Result<(), sqlx::Error> {
+    query("INSERT INTO eventos_login (email, ip, usuario_id, resultado) VALUES ($1, $2, $3, $4)")
+        .bind(email.trim().to_lowercase())
+        .bind(ip)
+        .bind(usuario_id)
+        .bind(resultado.as_str())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Duração do bloqueio para a n-ésima falha além do limite: 1 min, 2 min, 4 min... até 1 hora.
+fn duracao_bloqueio(falhas_alem_do_limite: i32) -> i64 {
+    let expoente = falhas_alem_do_limite.clamp(0, 16) as u32;
+    (BLOQUEIO_INICIAL_SEGUNDOS * 2i64.pow(expoente)).min(BLOQUEIO_MAXIMO_SEGUNDOS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instante(segundos: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp(1_800_000_000 + segundos, 0).unwrap()
+    }
+
+    fn contador_novo(agora: DateTime<Utc>) -> ContadorFalhas {
+        ContadorFalhas { falhas: 0, ultima_falha: agora, bloqueado_ate: None }
+    }
+
+    #[test]
+    fn duracao_bloqueio_dobra_ate_o_maximo() {
+        assert_eq!(duracao_bloqueio(0), 60);
+        assert_eq!(duracao_bloqueio(1), 120);
+        assert_eq!(duracao_bloqueio(4), 960);
+        assert_eq!(duracao_bloqueio(5), 1920);
+        assert_eq!(duracao_bloqueio(6), BLOQUEIO_MAXIMO_SEGUNDOS);
+        assert_eq!(duracao_bloqueio(500), BLOQUEIO_MAXIMO_SEGUNDOS);
+        assert_eq!(duracao_bloqueio(-1), BLOQUEIO_INICIAL_SEGUNDOS);
+    }
+
+    #[test]
+    fn bloqueia_ao_atingir_o_limite() {
+        let mut contador = contador_novo(instante(0));
+        for i in 1..LIMITE_FALHAS_EMAIL {
+            contador = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, instante(i as i64));
+            assert_eq!(contador.bloqueado_ate, None);
+        }
+        contador = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, instante(10));
+        assert_eq!(contador.falhas, LIMITE_FALHAS_EMAIL);
+        assert_eq!(contador.bloqueado_ate, Some(instante(10 + BLOQUEIO_INICIAL_SEGUNDOS)));
+    }
+
+    #[test]
+    fn backoff_chega_ao_maximo_tentando_logo_apos_cada_bloqueio() {
+        let mut agora = instante(0);
+        let mut contador = contador_novo(agora);
+        for _ in 0..LIMITE_FALHAS_EMAIL {
+            contador = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, agora);
+        }
+
+        let mut duracoes = Vec::new();
+        for _ in 0..10 {
+            // O atacante espera o bloqueio acabar e erra de novo
+            agora = contador.bloqueado_ate.unwrap() + Duration::seconds(1);
+            let falhas_antes = contador.falhas;
+            contador = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, agora);
+            assert_eq!(contador.falhas, falhas_antes + 1, "a contagem não pode recomeçar entre bloqueios");
+            duracoes.push((contador.bloqueado_ate.unwrap() - agora).num_seconds());
+        }
+
+        assert_eq!(&duracoes[..6], &[120, 240, 480, 960, 1920, 3600]);
+        assert!(duracoes[6..].iter().all(|d| *d == BLOQUEIO_MAXIMO_SEGUNDOS));
+    }
+
+    #[test]
+    fn contagem_recomeca_depois_da_janela_sem_falhas() {
+        let contador = ContadorFalhas { falhas: 3, ultima_falha: instante(0), bloqueado_ate: None };
+        let dentro = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, instante(JANELA_FALHAS_MINUTOS * 60 - 1));
+        assert_eq!(dentro.falhas, 4);
+        let fora = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, instante(JANELA_FALHAS_MINUTOS * 60 + 1));
+        assert_eq!(fora.falhas, 1);
+    }
+
+    #[test]
+    fn janela_conta_a_partir_do_fim_do_bloqueio() {
+        let fim_bloqueio = instante(BLOQUEIO_MAXIMO_SEGUNDOS);
+        let contador = ContadorFalhas { falhas: 11, ultima_falha: instante(0), bloqueado_ate: Some(fim_bloqueio) };
+
+        let logo_depois = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, fim_bloqueio + Duration::minutes(JANELA_FALHAS_MINUTOS - 1));
+        assert_eq!(logo_depois.falhas, 12);
+        assert!(logo_depois.bloqueado_ate.unwrap() > fim_bloqueio);
+
+        let muito_depois = contador.com_nova_falha(LIMITE_FALHAS_EMAIL, fim_bloqueio + Duration::minutes(JANELA_FALHAS_MINUTOS + 1));
+        assert_eq!(muito_depois.falhas, 1);
+        assert_eq!(muito_depois.bloqueado_ate, Some(fim_bloqueio));
+    }
+}
@@ -0,0 +1,129 @@
+// src/usuarios/totp.rs
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+/// Emissor exibido nos aplicativos autenticadores.
+const EMISSOR: &str = "BellaVibe";
+/// Duração de cada passo do TOTP, em segundos.
+const PERIODO_SEGUNDOS: i64 = 30;
+/// Quantidade de dígitos do código.
+const DIGITOS: u32 = 6;
+/// Passos aceitos antes e depois do atual, para tolerar diferenças de relógio.
+const TOLERANCIA_PASSOS: i64 = 1;
+/// Alfabeto Base32 (RFC 4648), usado no segredo e nos códigos de recuperação.
+const ALFABETO_BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Gera um segredo TOTP de 160 bits codificado em Base32, como esperam os autenticadores.
+pub fn gerar_segredo() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+/// Monta a URI `otpauth://` usada para gerar o QR code de inscrição no autenticador.
+pub fn uri_provisionamento(segredo: &str, email: &str) -> String {
+    format!(
+        "otpauth://totp/{emissor}:{conta}?secret={segredo}&issuer={emissor}&algorithm=SHA1&digits={digitos}&period={periodo}",
+        emissor = EMISSOR,
+        conta = codificar_para_uri(email),
+        segredo = segredo,
+        digitos = DIGITOS,
+        periodo = PERIODO_SEGUNDOS,
+    )
+}
+
+/// Confere um código TOTP no instante `agora` (timestamp Unix).
+/// Retorna o passo em que o código foi aceito, para que o chamador impeça a reutilização
+/// do mesmo código (passos iguais ou anteriores ao último aceito devem ser recusados).
+pub fn verificar_codigo(segredo: &str, codigo: &str, agora: i64) -> Option<i64> {
+    let chave = base32_decode(segredo)?;
+    let codigo: u32 = codigo.trim().parse().ok()?;
+    let passo_atual = agora / PERIODO_SEGUNDOS;
+
+    (passo_atual - TOLERANCIA_PASSOS..=passo_atual + TOLERANCIA_PASSOS)
+        .find(|&passo| passo >= 0 && codigo_no_passo(&chave, passo as u64) == codigo)
+}
+
+/// Gera códigos de recuperação de uso único no formato `XXXXX-XXXXX`.
+pub fn gerar_codigos_recuperacao(quantidade: usize) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..quantidade)
+        .map(|_| {
+            let caracteres: String = (0..10)
+                .map(|_| ALFABETO_BASE32[(rng.next_u32() % 32) as usize] as char)
+                .collect();
+            format!("{}-{}", &caracteres[..5], &caracteres[5..])
+        })
+        .collect()
+}
+
+/// Normaliza um código de recuperação digitado pelo usuário (sem hífen, maiúsculo).
+pub fn normalizar_codigo_recuperacao(codigo: &str) -> String {
+    codigo.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_uppercase()
+}
+
+/// Calcula o código HOTP (RFC 4226) para um passo de tempo.
+fn codigo_no_passo(chave: &[u8], passo: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(chave).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(&passo.to_be_bytes());
+    let resultado = mac.finalize().into_bytes();
+
+    // Truncamento dinâmico: os 4 bits finais indicam o deslocamento dos 31 bits usados
+    let deslocamento = (resultado[resultado.len() - 1] & 0x0f) as usize;
+    let valor = u32::from_be_bytes([
+        resultado[deslocamento] & 0x7f,
+        resultado[deslocamento + 1],
+        resultado[deslocamento + 2],
+        resultado[deslocamento + 3],
+    ]);
+    valor % 10u32.pow(DIGITOS)
+}
+
+/// Codifica bytes em Base32 (RFC 4648) sem preenchimento.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut saida = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            saida.push(ALFABETO_BASE32[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        saida.push(ALFABETO_BASE32[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    saida
+}
+
+/// Decodifica Base32 (RFC 4648), ignorando preenchimento e espaços.
+fn base32_decode(texto: &str) -> Option<Vec<u8>> {
+    let mut saida = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in texto.chars().filter(|c| *c != '=' && !c.is_whitespace()) {
+        let valor = ALFABETO_BASE32.iter().position(|&a| a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | valor;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            saida.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+    Some(saida)
+}
+
+/// Codifica os caracteres reservados de um componente da URI `otpauth://`.
+fn codificar_para_uri(valor: &str) -> String {
+    valor
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -1,27 +1,52 @@
 // src/usuarios/usuario_router.rs
 
-use actix_web::{post, web, HttpResponse};
+use actix_web::{get, post, put, delete, http::header, web, HttpRequest, HttpResponse};
 use sqlx::{query, query_as, Row};
 use serde_json;
 use bcrypt::{hash, verify, DEFAULT_COST}; // Para hashing de senhas
-use jsonwebtoken::{encode, Header, EncodingKey}; // Para JWT
 use chrono::{Utc, Duration}; // Para gerenciar tempo (expiração do token)
 
 // Importa as structs do módulo de usuários
-use super::usuario_structs::{NovoUsuario, LoginRequest, AuthResponse, Usuario, Claims};
+use super::usuario_structs::{
+    NovoUsuario, LoginRequest, AuthResponse, Usuario, Claims,
+    PerfilUsuario, AtualizacaoPerfil, AlteracaoSenha, ExclusaoConta,
+    SolicitacaoRedefinicaoSenha, RedefinicaoSenha, VerificacaoEmail,
+    EventoLogin, FiltroEventosLogin, DesafioClaims, DesafioLoginResponse, AUDIENCIA_DESAFIO_2FA,
+};
+// Importa os extratores de autenticação e autorização
+use super::auth_middleware::{AuthenticatedUser, AdminUser};
+// Importa a proteção contra força bruta no login
+use super::protecao_login::{
+    chave_email, chave_ip, hash_ficticio, registrar_evento, registrar_falha, registrar_sucesso,
+    segundos_de_bloqueio, ResultadoLogin,
+};
 // Importa GenericResponse do módulo shared_structs
 use crate::shared::shared_structs::GenericResponse;
+// Importa as validações de CPF e telefone
+use crate::shared::validacoes::{cpf_valido, somente_digitos, telefone_valido};
+// Importa a geração e o hash de tokens de uso único
+use crate::shared::tokens::{gerar_token, hash_sha256, assinar, assinatura_valida};
+// Importa a mensagem de e-mail enviada pelo Mailer
+use crate::email::mailer::Email;
 // Importa o AppState do módulo raiz (main.rs)
 use crate::AppState;
 
+/// Tamanho mínimo exigido para novas senhas.
+const TAMANHO_MINIMO_SENHA: usize = 8;
+/// Validade, em minutos, do token de redefinição de senha.
+const VALIDADE_TOKEN_REDEFINICAO_MINUTOS: i64 = 30;
+/// Validade, em horas, do link de verificação de e-mail.
+const VALIDADE_LINK_VERIFICACAO_HORAS: i64 = 48;
+
 /// Rota para cadastrar um novo usuário.
+/// A conta nasce com o e-mail não verificado e um link assinado de verificação é enviado por e-mail.
 #[post("/usuarios/cadastro")]
 pub async fn cadastrar_usuario(
     data: web::Data<AppState>,
     novo_usuario: web::Json<NovoUsuario>,
 ) -> HttpResponse {
     // 1. Verificar se o e-mail já está em uso
-    let existing_user = query_as::<_, Usuario>("SELECT id, nome, email, senha_hash FROM usuarios WHERE email = $1")
+    let existing_user = query_as::<_, Usuario>("SELECT id, nome, email, senha_hash, perfil, totp_ativado_em IS NOT NULL AS totp_ativo FROM usuarios WHERE email = $1")
         .bind(&novo_usuario.email)
         .fetch_optional(&data.db_pool)
         .await;
@@ -71,11 +96,14 @@ pub async fn cadastrar_usuario(
     match result {
         Ok(row) => {
             match row.try_get::<i32, &str>("id") {
-                Ok(id) => HttpResponse::Ok().json(GenericResponse {
-                    status: "success".to_string(),
-                    message: format!("Usuário cadastrado com sucesso! ID: {}", id),
-                    body: Some(serde_json::json!({ "id": id })),
-                }),
+                Ok(id) => {
+                    enviar_email_verificacao(&data, id, &novo_usuario.email).await;
+                    HttpResponse::Ok().json(GenericResponse {
+                        status: "success".to_string(),
+                        message: format!("Usuário cadastrado com sucesso! ID: {}. Confirme o e-mail pelo link enviado.", id),
+                        body: Some(serde_json::json!({ "id": id })),
+                    })
+                },
                 Err(e) => {
                     eprintln!("Erro ao obter id do novo usuário: {:?}", e);
                     HttpResponse::InternalServerError().json(GenericResponse::<()>{
@@ -98,26 +126,56 @@ pub async fn cadastrar_usuario(
 }
 
 /// Rota para login de usuário.
+///
+/// Protegida contra força bruta: falhas são contadas por e-mail e por IP e, ao atingir o
+/// limite, novas tentativas recebem 429 com `Retry-After` durante um bloqueio que dobra
+/// a cada falha adicional. A senha é sempre conferida com bcrypt (contra um hash fictício
+/// quando o e-mail não existe), para que o tempo de resposta não revele contas cadastradas.
+/// Todas as tentativas são registradas em `eventos_login`. Contas com dois fatores ativos
+/// recebem um token de desafio, trocado pelo token de acesso em `/usuarios/login/2fa`.
 #[post("/usuarios/login")]
 pub async fn login_usuario(
     data: web::Data<AppState>,
     login_request: web::Json<LoginRequest>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    // 1. Buscar o usuário pelo e-mail
-    let user_result = query_as::<_, Usuario>("SELECT id, nome, email, senha_hash FROM usuarios WHERE email = $1")
-        .bind(&login_request.email)
-        .fetch_optional(&data.db_pool)
-        .await;
+    // Usa o endereço da conexão; cabeçalhos como X-Forwarded-For podem ser forjados pelo cliente.
+    let ip = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_else(|| "desconhecido".to_string());
+    let email = login_request.email.as_str();
 
-    let user = match user_result {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return HttpResponse::Unauthorized().json(GenericResponse::<()>{
+    // 1. Recusa a tentativa se o e-mail ou o IP estiverem bloqueados
+    match segundos_de_bloqueio(&data.db_pool, &[chave_email(email), chave_ip(&ip)]).await {
+        Ok(Some(segundos)) => {
+            if let Err(e) = registrar_evento(&data.db_pool, email, &ip, None, ResultadoLogin::Bloqueado).await {
+                eprintln!("Erro ao registrar evento de login: {:?}", e);
+            }
+            return HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, segundos.max(1).to_string()))
+                .json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: format!("Muitas tentativas de login. Tente novamente em {} segundos.", segundos.max(1)),
+                    body: None,
+                });
+        },
+        Ok(None) => {},
+        Err(e) => {
+            eprintln!("Erro ao verificar bloqueio de login: {:?}", e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: "Credenciais inválidas.".to_string(),
+                message: "Erro interno ao processar login.".to_string(),
                 body: None,
             });
-        },
+        }
+    }
+
+    // 2. Buscar o usuário pelo e-mail
+    let user_result = query_as::<_, Usuario>("SELECT id, nome, email, senha_hash, perfil, totp_ativado_em IS NOT NULL AS totp_ativo FROM usuarios WHERE email = $1 AND anonimizado_em IS NULL")
+        .bind(email)
+        .fetch_optional(&data.db_pool)
+        .await;
+
+    let user = match user_result {
+        Ok(u) => u,
         Err(e) => {
             eprintln!("Erro ao buscar usuário para login: {:?}", e);
             return HttpResponse::InternalServerError().json(GenericResponse::<()>{
@@ -128,9 +186,10 @@ pub async fn login_usuario(
         }
     };
 
-    // 2. Verificar a senha
-    let password_matches = match verify(&login_request.senha, &user.senha_hash) {
-        Ok(matches) => matches,
+    // 3. Verificar a senha (contra o hash fictício se o usuário não existir)
+    let senha_hash = user.as_ref().map(|u| u.senha_hash.as_str()).unwrap_or_else(|| hash_ficticio());
+    let password_matches = match verify(&login_request.senha, senha_hash) {
+        Ok(matches) => matches && user.is_some(),
         Err(e) => {
             eprintln!("Erro ao verificar senha: {:?}", e);
             return HttpResponse::InternalServerError().json(GenericResponse::<()>{
@@ -141,24 +200,86 @@ pub async fn login_usuario(
         }
     };
 
-    if !password_matches {
-        return HttpResponse::Unauthorized().json(GenericResponse::<()>{
-            status: "error".to_string(),
-            message: "Credenciais inválidas.".to_string(),
-            body: None,
-        });
+    let user = match user {
+        Some(u) if password_matches => u,
+        _ => {
+            if let Err(e) = registrar_falha(&data.db_pool, email, &ip).await {
+                eprintln!("Erro ao registrar falha de login: {:?}", e);
+            }
+            if let Err(e) = registrar_evento(&data.db_pool, email, &ip, user.as_ref().map(|u| u.id), ResultadoLogin::Falha).await {
+                eprintln!("Erro ao registrar evento de login: {:?}", e);
+            }
+            return HttpResponse::Unauthorized().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Credenciais inválidas.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    // 4. Com dois fatores ativos, emite apenas o token de desafio para o segundo passo.
+    // O contador de falhas só é zerado quando o segundo fator também for aceito; senão quem
+    // conhece a senha poderia zerá-lo entre as tentativas de adivinhar o código.
+    if user.totp_ativo {
+        if let Err(e) = registrar_evento(&data.db_pool, email, &ip, Some(user.id), ResultadoLogin::DoisFatoresPendente).await {
+            eprintln!("Erro ao registrar evento de login: {:?}", e);
+        }
+        return responder_desafio_2fa(&data, user.id);
+    }
+
+    if let Err(e) = registrar_sucesso(&data.db_pool, email).await {
+        eprintln!("Erro ao zerar falhas de login: {:?}", e);
     }
+    if let Err(e) = registrar_evento(&data.db_pool, email, &ip, Some(user.id), ResultadoLogin::Sucesso).await {
+        eprintln!("Erro ao registrar evento de login: {:?}", e);
+    }
+
+    // 5. Gerar token JWT real e retornar resposta de sucesso
+    responder_login(&data, user)
+}
 
-    // 3. Gerar token JWT real
+/// Validade, em minutos, do token de desafio do login com dois fatores.
+const VALIDADE_DESAFIO_2FA_MINUTOS: i64 = 5;
+
+/// Emite o token de desafio do login com dois fatores.
+/// A audiência própria (`aud`) impede que ele seja aceito como token de acesso.
+fn responder_desafio_2fa(data: &web::Data<AppState>, user_id: i32) -> HttpResponse {
+    let claims = DesafioClaims {
+        sub: user_id,
+        aud: AUDIENCIA_DESAFIO_2FA.to_string(),
+        exp: (Utc::now() + Duration::minutes(VALIDADE_DESAFIO_2FA_MINUTOS)).timestamp(),
+    };
+
+    match data.chaves_jwt.assinar(&claims) {
+        Ok(desafio_token) => HttpResponse::Ok().json(DesafioLoginResponse {
+            status: "2fa_pendente".to_string(),
+            message: "Informe o código do autenticador em /usuarios/login/2fa.".to_string(),
+            desafio_token,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao gerar token de desafio 2FA: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao gerar token de autenticação.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Gera o token JWT de acesso do usuário e monta a resposta de login bem-sucedido.
+/// Usada tanto pelo login com senha quanto pelo segundo passo do login com dois fatores.
+pub fn responder_login(data: &web::Data<AppState>, user: Usuario) -> HttpResponse {
     let expiration = Utc::now() + Duration::hours(24); // Token válido por 24 horas
     let claims = Claims {
         sub: user.id,
         name: user.nome.clone(),
         email: user.email.clone(),
+        perfil: user.perfil.clone(),
         exp: expiration.timestamp(),
     };
 
-    let token = match encode(&Header::default(), &claims, &EncodingKey::from_secret(data.jwt_secret.as_ref())) {
+    let token = match data.chaves_jwt.assinar(&claims) {
         Ok(t) => t,
         Err(e) => {
             eprintln!("Erro ao gerar JWT: {:?}", e);
@@ -170,7 +291,6 @@ pub async fn login_usuario(
         }
     };
 
-    // 4. Retornar resposta de sucesso
     HttpResponse::Ok().json(AuthResponse {
         status: "success".to_string(),
         message: "Login bem-sucedido!".to_string(),
@@ -180,3 +300,673 @@ pub async fn login_usuario(
         token, // Retorna o token JWT gerado
     })
 }
+
+/// Rota administrativa para revisar os eventos de login (sucessos, falhas e bloqueios).
+/// Aceita filtros opcionais por e-mail, IP e resultado, retornando os mais recentes primeiro.
+#[get("/usuarios/login/eventos")]
+pub async fn buscar_eventos_login(
+    data: web::Data<AppState>,
+    filtro: web::Query<FiltroEventosLogin>,
+    _admin: AdminUser,
+) -> HttpResponse {
+    let limite = filtro.limite.unwrap_or(100).clamp(1, 500);
+    let eventos_result = query_as::<_, EventoLogin>(
+        r#"
+        SELECT id, email, ip, usuario_id, resultado, criado_em
+        FROM eventos_login
+        WHERE ($1::text IS NULL OR email = lower($1))
+          AND ($2::text IS NULL OR ip = $2)
+          AND ($3::text IS NULL OR resultado = $3)
+        ORDER BY criado_em DESC, id DESC
+        LIMIT $4
+        "#
+    )
+    .bind(&filtro.email)
+    .bind(&filtro.ip)
+    .bind(&filtro.resultado)
+    .bind(limite)
+    .fetch_all(&data.db_pool)
+    .await;
+
+    match eventos_result {
+        Ok(eventos) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Eventos de login listados com sucesso!".to_string(),
+            body: Some(eventos),
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar eventos de login: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar eventos de login".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+// --- Rotas de perfil do usuário autenticado ---
+
+/// Rota para consultar o perfil do usuário autenticado.
+#[get("/usuarios/me")]
+pub async fn buscar_meu_perfil(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+) -> HttpResponse {
+    let perfil_result = query_as::<_, PerfilUsuario>(
+        "SELECT id, nome, email, telefone, cpf, data_nascimento FROM usuarios WHERE id = $1 AND anonimizado_em IS NULL"
+    )
+    .bind(auth_user.user_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    match perfil_result {
+        Ok(Some(perfil)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Perfil encontrado.".to_string(),
+            body: Some(perfil),
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Usuário não encontrado.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao buscar perfil do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao buscar perfil.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para atualizar nome, telefone, CPF e data de nascimento do usuário autenticado.
+/// CPF e telefone são validados e armazenados apenas com dígitos.
+#[put("/usuarios/me")]
+pub async fn atualizar_meu_perfil(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    item: web::Json<AtualizacaoPerfil>,
+) -> HttpResponse {
+    // 1. Validações
+    if item.nome.trim().is_empty() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "O nome é obrigatório.".to_string(),
+            body: None,
+        });
+    }
+
+    if let Some(cpf) = &item.cpf {
+        if !cpf_valido(cpf) {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "CPF inválido.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    if let Some(telefone) = &item.telefone {
+        if !telefone_valido(telefone) {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Telefone inválido. Informe o DDD e o número.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    if let Some(data_nascimento) = item.data_nascimento {
+        if data_nascimento >= Utc::now().date_naive() {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Data de nascimento inválida.".to_string(),
+                body: None,
+            });
+        }
+    }
+
+    // 2. Atualiza o perfil
+    let result = query_as::<_, PerfilUsuario>(
+        r#"
+        UPDATE usuarios SET nome = $1, telefone = $2, cpf = $3, data_nascimento = $4
+        WHERE id = $5 AND anonimizado_em IS NULL
+        RETURNING id, nome, email, telefone, cpf, data_nascimento
+        "#
+    )
+    .bind(item.nome.trim())
+    .bind(item.telefone.as_deref().map(somente_digitos))
+    .bind(item.cpf.as_deref().map(somente_digitos))
+    .bind(item.data_nascimento)
+    .bind(auth_user.user_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(Some(perfil)) => HttpResponse::Ok().json(GenericResponse {
+            status: "success".to_string(),
+            message: "Perfil atualizado com sucesso.".to_string(),
+            body: Some(perfil),
+        }),
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Usuário não encontrado.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao atualizar perfil do usuário {}: {:?}", auth_user.user_id, e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                    status: "error".to_string(),
+                    message: "CPF já cadastrado em outra conta.".to_string(),
+                    body: None,
+                });
+            }
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao atualizar perfil.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para o usuário autenticado trocar a própria senha.
+/// A senha atual é conferida com bcrypt antes de gravar o hash da nova senha.
+#[post("/usuarios/me/senha")]
+pub async fn alterar_minha_senha(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    item: web::Json<AlteracaoSenha>,
+) -> HttpResponse {
+    if item.nova_senha.chars().count() < TAMANHO_MINIMO_SENHA {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("A nova senha deve ter pelo menos {} caracteres.", TAMANHO_MINIMO_SENHA),
+            body: None,
+        });
+    }
+
+    // 1. Confere a senha atual
+    if let Err(resposta) = conferir_senha_atual(&data, auth_user.user_id, &item.senha_atual).await {
+        return resposta;
+    }
+
+    // 2. Hash da nova senha
+    let hashed_password = match hash(&item.nova_senha, DEFAULT_COST) {
+        Ok(h) => h,
+        Err(e) => {
+            eprintln!("Erro ao fazer hash da senha: {:?}", e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao processar senha.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    // 3. Grava o novo hash
+    let result = query("UPDATE usuarios SET senha_hash = $1 WHERE id = $2 AND anonimizado_em IS NULL")
+        .bind(&hashed_password)
+        .bind(auth_user.user_id)
+        .execute(&data.db_pool)
+        .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Senha alterada com sucesso.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao alterar senha do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao alterar senha.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para o usuário autenticado excluir a própria conta.
+///
+/// Em conformidade com a LGPD, os dados pessoais são anonimizados em vez de apagados:
+/// nome, e-mail, telefone, CPF e data de nascimento são substituídos ou removidos, a senha
+/// é invalidada e a linha é mantida para preservar os pedidos que a referenciam.
+/// Os endereços salvos, os favoritos e os avisos de estoque são apagados; os pedidos mantêm a cópia do endereço de entrega.
+/// Os tokens de redefinição de senha, os códigos de recuperação do 2FA e o contador de falhas de login
+/// do e-mail também são apagados, e os eventos de login perdem o e-mail e o IP.
+#[delete("/usuarios/me")]
+pub async fn excluir_minha_conta(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+    item: web::Json<ExclusaoConta>,
+) -> HttpResponse {
+    // 1. Confirma a exclusão com a senha atual
+    if let Err(resposta) = conferir_senha_atual(&data, auth_user.user_id, &item.senha).await {
+        return resposta;
+    }
+
+    // 2. Anonimiza os dados pessoais. O e-mail recebe um valor único e não roteável.
+    // As CTEs enxergam o e-mail de antes do UPDATE, usado nas tabelas indexadas por ele.
+    let result = query(
+        r#"
+        WITH atual AS (SELECT LOWER(email) AS email FROM usuarios WHERE id = $1),
+             enderecos_removidos AS (DELETE FROM enderecos WHERE usuario_id = $1),
+             favoritos_removidos AS (DELETE FROM favoritos WHERE usuario_id = $1),
+             avisos_removidos AS (DELETE FROM inscricoes_estoque WHERE usuario_id = $1),
+             tokens_removidos AS (DELETE FROM tokens_redefinicao_senha WHERE usuario_id = $1),
+             codigos_removidos AS (DELETE FROM codigos_recuperacao_2fa WHERE usuario_id = $1),
+             bloqueios_removidos AS (
+                 DELETE FROM bloqueios_login WHERE chave = (SELECT 'email:' || email FROM atual)
+             ),
+             eventos_anonimizados AS (
+                 UPDATE eventos_login SET email = 'removido-' || $1 || '@anonimizado.invalid', ip = 'anonimizado'
+                 WHERE usuario_id = $1 OR email = (SELECT email FROM atual)
+             )
+        UPDATE usuarios SET
+            nome = 'Usuário removido',
+            email = 'removido-' || id || '@anonimizado.invalid',
+            senha_hash = '',
+            telefone = NULL,
+            cpf = NULL,
+            data_nascimento = NULL,
+            totp_segredo = NULL,
+            totp_ativado_em = NULL,
+            anonimizado_em = NOW()
+        WHERE id = $1 AND anonimizado_em IS NULL
+        "#
+    )
+    .bind(auth_user.user_id)
+    .execute(&data.db_pool)
+    .await;
+
+    match result {
+        Ok(res) if res.rows_affected() > 0 => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Conta excluída e dados pessoais anonimizados.".to_string(),
+            body: None,
+        }),
+        Ok(_) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Usuário não encontrado.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao anonimizar conta do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao excluir conta.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+// --- Rotas de redefinição de senha ---
+
+/// Rota para solicitar a redefinição de senha.
+///
+/// Gera um token aleatório de uso único, armazena apenas o seu hash com validade de
+/// `VALIDADE_TOKEN_REDEFINICAO_MINUTOS` e envia o token por e-mail. Tokens anteriores
+/// ainda não usados são invalidados. A resposta é sempre a mesma, exista ou não
+/// uma conta com o e-mail informado, para não revelar quais e-mails estão cadastrados.
+/// A busca da conta, a gravação do token e o envio rodam fora da requisição, para que o
+/// tempo de resposta também não revele se a conta existe.
+#[post("/usuarios/senha/esqueci")]
+pub async fn esqueci_senha(
+    data: web::Data<AppState>,
+    item: web::Json<SolicitacaoRedefinicaoSenha>,
+) -> HttpResponse {
+    let email = item.into_inner().email;
+    actix_web::rt::spawn(async move {
+        if let Err(e) = enviar_token_redefinicao(&data, email).await {
+            eprintln!("Erro ao processar redefinição de senha: {:?}", e);
+        }
+    });
+
+    HttpResponse::Ok().json(GenericResponse::<()>{
+        status: "success".to_string(),
+        message: "Se o e-mail estiver cadastrado, enviaremos as instruções para redefinir a senha.".to_string(),
+        body: None,
+    })
+}
+
+/// Invalida os tokens pendentes da conta com o e-mail informado, grava o hash de um novo
+/// token e o envia por e-mail. Sem conta com esse e-mail, não faz nada.
+async fn enviar_token_redefinicao(data: &web::Data<AppState>, email: String) -> Result<(), sqlx::Error> {
+    // 1. Busca o usuário pelo e-mail
+    let usuario_id = sqlx::query_scalar::<_, i32>("SELECT id FROM usuarios WHERE email = $1 AND anonimizado_em IS NULL")
+        .bind(&email)
+        .fetch_optional(&data.db_pool)
+        .await?;
+    let usuario_id = match usuario_id {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    // 2. Invalida tokens pendentes e grava o hash do novo token
+    let token = gerar_token();
+    let expira_em = Utc::now() + Duration::minutes(VALIDADE_TOKEN_REDEFINICAO_MINUTOS);
+    let mut transaction = data.db_pool.begin().await?;
+    query("UPDATE tokens_redefinicao_senha SET usado_em = NOW() WHERE usuario_id = $1 AND usado_em IS NULL")
+        .bind(usuario_id)
+        .execute(&mut *transaction)
+        .await?;
+    query("INSERT INTO tokens_redefinicao_senha (usuario_id, token_hash, expira_em) VALUES ($1, $2, $3)")
+        .bind(usuario_id)
+        .bind(hash_sha256(&token))
+        .bind(expira_em)
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+
+    // 3. Envia o token por e-mail. Falhas de envio são registradas, mas não expostas ao cliente.
+    let email = Email {
+        para: email,
+        assunto: "BellaVibe - Redefinição de senha".to_string(),
+        corpo: format!(
+            "Recebemos um pedido para redefinir a sua senha.\n\n\
+             Use o código abaixo em {}/usuarios/senha/redefinir em até {} minutos:\n\n{}\n\n\
+             Se você não fez este pedido, ignore este e-mail.",
+            data.url_publica, VALIDADE_TOKEN_REDEFINICAO_MINUTOS, token
+        ),
+    };
+    if let Err(e) = data.mailer.enviar(email).await {
+        eprintln!("Erro ao enviar e-mail de redefinição de senha: {}", e);
+    }
+    Ok(())
+}
+
+/// Resultado da troca de senha por token.
+enum ResultadoRedefinicao {
+    Redefinida,
+    TokenInvalido,
+    FalhaHash(bcrypt::BcryptError), // A transação é desfeita e o token continua válido
+}
+
+/// Rota para redefinir a senha com o token recebido por e-mail.
+/// O token precisa existir, não ter sido usado e estar dentro da validade;
+/// após a troca ele é marcado como usado e não pode ser reaproveitado.
+#[post("/usuarios/senha/redefinir")]
+pub async fn redefinir_senha(
+    data: web::Data<AppState>,
+    item: web::Json<RedefinicaoSenha>,
+) -> HttpResponse {
+    if item.nova_senha.chars().count() < TAMANHO_MINIMO_SENHA {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("A nova senha deve ter pelo menos {} caracteres.", TAMANHO_MINIMO_SENHA),
+            body: None,
+        });
+    }
+
+    // Consome o token e grava a nova senha na mesma transação. O hash (caro) só é
+    // calculado depois que o token é encontrado e bloqueado.
+    let resultado: Result<ResultadoRedefinicao, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+        let token_row = query(
+            r#"
+            SELECT id, usuario_id FROM tokens_redefinicao_senha
+            WHERE token_hash = $1 AND usado_em IS NULL AND expira_em > NOW()
+            FOR UPDATE
+            "#
+        )
+        .bind(hash_sha256(item.token.trim()))
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        let (token_id, usuario_id): (i32, i32) = match token_row {
+            Some(row) => (row.try_get("id")?, row.try_get("usuario_id")?),
+            None => return Ok(ResultadoRedefinicao::TokenInvalido),
+        };
+
+        let hashed_password = match hash(&item.nova_senha, DEFAULT_COST) {
+            Ok(h) => h,
+            Err(e) => return Ok(ResultadoRedefinicao::FalhaHash(e)),
+        };
+
+        query("UPDATE usuarios SET senha_hash = $1 WHERE id = $2 AND anonimizado_em IS NULL")
+            .bind(&hashed_password)
+            .bind(usuario_id)
+            .execute(&mut *transaction)
+            .await?;
+        query("UPDATE tokens_redefinicao_senha SET usado_em = NOW() WHERE id = $1")
+            .bind(token_id)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(ResultadoRedefinicao::Redefinida)
+    }
+    .await;
+
+    match resultado {
+        Ok(ResultadoRedefinicao::Redefinida) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "Senha redefinida com sucesso.".to_string(),
+            body: None,
+        }),
+        Ok(ResultadoRedefinicao::TokenInvalido) => HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Token de redefinição inválido ou expirado.".to_string(),
+            body: None,
+        }),
+        Ok(ResultadoRedefinicao::FalhaHash(e)) => {
+            eprintln!("Erro ao fazer hash da senha: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao processar senha.".to_string(),
+                body: None,
+            })
+        },
+        Err(e) => {
+            eprintln!("Erro ao redefinir senha: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao redefinir senha.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+// --- Rotas de verificação de e-mail ---
+
+/// Rota acessada pelo link de verificação enviado por e-mail.
+/// Confere a assinatura (que inclui o e-mail atual, invalidando links de e-mails antigos)
+/// e a expiração, e então marca o e-mail da conta como verificado.
+#[get("/usuarios/verificar")]
+pub async fn verificar_email(
+    data: web::Data<AppState>,
+    params: web::Query<VerificacaoEmail>,
+) -> HttpResponse {
+    if params.expira < Utc::now().timestamp() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Link de verificação expirado. Solicite um novo link.".to_string(),
+            body: None,
+        });
+    }
+
+    // 1. Busca o e-mail atual do usuário para conferir a assinatura
+    let email_result = sqlx::query_scalar::<_, String>("SELECT email FROM usuarios WHERE id = $1 AND anonimizado_em IS NULL")
+        .bind(params.usuario)
+        .fetch_optional(&data.db_pool)
+        .await;
+
+    let email = match email_result {
+        Ok(Some(email)) => email,
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Link de verificação inválido.".to_string(),
+                body: None,
+            });
+        },
+        Err(e) => {
+            eprintln!("Erro ao buscar usuário para verificação de e-mail: {:?}", e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao verificar e-mail.".to_string(),
+                body: None,
+            });
+        }
+    };
+
+    let conteudo = conteudo_link_verificacao(params.usuario, &email, params.expira);
+    if !assinatura_valida(&data.chave_links, &conteudo, &params.assinatura) {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Link de verificação inválido.".to_string(),
+            body: None,
+        });
+    }
+
+    // 2. Marca o e-mail como verificado (idempotente: links repetidos não alteram a data)
+    let result = query("UPDATE usuarios SET email_verificado_em = COALESCE(email_verificado_em, NOW()) WHERE id = $1")
+        .bind(params.usuario)
+        .execute(&data.db_pool)
+        .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: "E-mail verificado com sucesso!".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao marcar e-mail como verificado: {:?}", e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao verificar e-mail.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Rota para o usuário autenticado pedir um novo link de verificação de e-mail.
+#[post("/usuarios/verificar/reenviar")]
+pub async fn reenviar_verificacao_email(
+    data: web::Data<AppState>,
+    auth_user: AuthenticatedUser,
+) -> HttpResponse {
+    let verificado_result = sqlx::query_scalar::<_, bool>(
+        "SELECT email_verificado_em IS NOT NULL FROM usuarios WHERE id = $1 AND anonimizado_em IS NULL"
+    )
+    .bind(auth_user.user_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    match verificado_result {
+        Ok(Some(true)) => HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "O e-mail desta conta já foi verificado.".to_string(),
+            body: None,
+        }),
+        Ok(Some(false)) => {
+            enviar_email_verificacao(&data, auth_user.user_id, &auth_user.user_email).await;
+            HttpResponse::Ok().json(GenericResponse::<()>{
+                status: "success".to_string(),
+                message: "Link de verificação reenviado.".to_string(),
+                body: None,
+            })
+        },
+        Ok(None) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Usuário não encontrado.".to_string(),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao consultar verificação de e-mail do usuário {}: {:?}", auth_user.user_id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao reenviar link de verificação.".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+/// Conteúdo assinado no link de verificação de e-mail.
+fn conteudo_link_verificacao(usuario_id: i32, email: &str, expira: i64) -> String {
+    format!("verificacao-email:{}:{}:{}", usuario_id, email, expira)
+}
+
+/// Monta o link assinado de verificação e o envia por e-mail.
+/// Falhas de envio são apenas registradas: o usuário pode pedir o reenvio depois.
+async fn enviar_email_verificacao(data: &web::Data<AppState>, usuario_id: i32, email: &str) {
+    let expira = (Utc::now() + Duration::hours(VALIDADE_LINK_VERIFICACAO_HORAS)).timestamp();
+    let assinatura = assinar(&data.chave_links, &conteudo_link_verificacao(usuario_id, email, expira));
+    let link = format!(
+        "{}/usuarios/verificar?usuario={}&expira={}&assinatura={}",
+        data.url_publica, usuario_id, expira, assinatura
+    );
+
+    let mensagem = Email {
+        para: email.to_string(),
+        assunto: "BellaVibe - Confirme o seu e-mail".to_string(),
+        corpo: format!(
+            "Bem-vindo(a) à BellaVibe!\n\nConfirme o seu e-mail acessando o link abaixo em até {} horas:\n\n{}",
+            VALIDADE_LINK_VERIFICACAO_HORAS, link
+        ),
+    };
+    if let Err(e) = data.mailer.enviar(mensagem).await {
+        eprintln!("Erro ao enviar e-mail de verificação para o usuário {}: {}", usuario_id, e);
+    }
+}
+
+/// Confere a senha informada com o hash armazenado do usuário.
+/// Retorna a resposta de erro (401, 404 ou 500) quando a senha não confere.
+pub async fn conferir_senha_atual(
+    data: &web::Data<AppState>,
+    user_id: i32,
+    senha: &str,
+) -> Result<(), HttpResponse> {
+    let senha_hash_result = sqlx::query_scalar::<_, String>(
+        "SELECT senha_hash FROM usuarios WHERE id = $1 AND anonimizado_em IS NULL"
+    )
+    .bind(user_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    let senha_hash = match senha_hash_result {
+        Ok(Some(h)) => h,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Usuário não encontrado.".to_string(),
+                body: None,
+            }));
+        },
+        Err(e) => {
+            eprintln!("Erro ao buscar senha do usuário {}: {:?}", user_id, e);
+            return Err(HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao verificar senha.".to_string(),
+                body: None,
+            }));
+        }
+    };
+
+    match verify(senha, &senha_hash) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Unauthorized().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Senha atual incorreta.".to_string(),
+            body: None,
+        })),
+        Err(e) => {
+            eprintln!("Erro ao verificar senha: {:?}", e);
+            Err(HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao verificar senha.".to_string(),
+                body: None,
+            }))
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use chrono::{DateTime, NaiveDate, Utc};
 
 /// Estrutura que representa um usuário no banco de dados.
 /// A senha será armazenada como um hash.
@@ -11,6 +12,8 @@ pub struct Usuario {
     pub nome: String,
     pub email: String,
     pub senha_hash: String, // Armazenará o hash da senha
+    pub perfil: String, // Perfil de acesso: "cliente" ou "admin"
+    pub totp_ativo: bool, // Indica se a autenticação em dois fatores está ativa
 }
 
 /// Estrutura para receber dados de um novo usuário na requisição de cadastro.
@@ -35,6 +38,8 @@ pub struct Claims {
     pub sub: i32, // Subject (ID do usuário)
     pub name: String, // Nome do usuário
     pub email: String, // Email do usuário
+    #[serde(default)] // Tokens emitidos antes da existência de perfis não trazem o campo
+    pub perfil: String, // Perfil de acesso do usuário
     pub exp: i64, // Expiration Time (timestamp Unix)
 }
 
@@ -49,3 +54,129 @@ pub struct AuthResponse {
     pub user_email: String,
     pub token: String, 
 }
+
+/// Estrutura com os dados de perfil do usuário autenticado (`/usuarios/me`).
+/// Não inclui o hash da senha.
+#[derive(Serialize, FromRow)]
+pub struct PerfilUsuario {
+    pub id: i32,
+    pub nome: String,
+    pub email: String,
+    pub telefone: Option<String>,
+    pub cpf: Option<String>,
+    pub data_nascimento: Option<NaiveDate>,
+}
+
+/// Estrutura para receber a atualização do perfil na requisição PUT.
+/// Campos opcionais enviados como `null` (ou ausentes) são apagados do perfil.
+#[derive(Deserialize)]
+pub struct AtualizacaoPerfil {
+    pub nome: String,
+    pub telefone: Option<String>,
+    pub cpf: Option<String>,
+    pub data_nascimento: Option<NaiveDate>, // Formato "AAAA-MM-DD"
+}
+
+/// Estrutura para receber a troca de senha do usuário autenticado.
+#[derive(Deserialize)]
+pub struct AlteracaoSenha {
+    pub senha_atual: String,
+    pub nova_senha: String, // Senha em texto claro (será hashed antes de salvar)
+}
+
+/// Estrutura para confirmar a exclusão da própria conta com a senha atual.
+#[derive(Deserialize)]
+pub struct ExclusaoConta {
+    pub senha: String,
+}
+
+/// Estrutura para solicitar o envio do token de redefinição de senha.
+#[derive(Deserialize)]
+pub struct SolicitacaoRedefinicaoSenha {
+    pub email: String,
+}
+
+/// Estrutura para redefinir a senha a partir do token recebido por e-mail.
+#[derive(Deserialize)]
+pub struct RedefinicaoSenha {
+    pub token: String,
+    pub nova_senha: String, // Senha em texto claro (será hashed antes de salvar)
+}
+
+/// Parâmetros do link de verificação de e-mail (`GET /usuarios/verificar`).
+/// A assinatura cobre o ID, o e-mail atual do usuário e a expiração.
+#[derive(Deserialize)]
+pub struct VerificacaoEmail {
+    pub usuario: i32,
+    pub expira: i64, // Timestamp Unix
+    pub assinatura: String,
+}
+
+/// Estrutura que representa um evento de login registrado para revisão.
+#[derive(Serialize, FromRow)]
+pub struct EventoLogin {
+    pub id: i32,
+    pub email: String,
+    pub ip: String,
+    pub usuario_id: Option<i32>,
+    pub resultado: String,
+    pub criado_em: DateTime<Utc>,
+}
+
+/// Filtros aceitos na query string de `GET /usuarios/login/eventos`.
+#[derive(Deserialize)]
+pub struct FiltroEventosLogin {
+    pub email: Option<String>,
+    pub ip: Option<String>,
+    pub resultado: Option<String>,
+    pub limite: Option<i64>,
+}
+
+/// Audiência (claim `aud`) do token de desafio do login com dois fatores.
+pub const AUDIENCIA_DESAFIO_2FA: &str = "bellavibe:login-2fa";
+
+/// Claims do token de desafio emitido no primeiro passo do login com dois fatores.
+/// É assinado com as mesmas chaves dos tokens de acesso, mas traz `aud`, então não serve para autenticar rotas.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DesafioClaims {
+    pub sub: i32, // ID do usuário que passou pela senha
+    pub aud: String, // Sempre AUDIENCIA_DESAFIO_2FA
+    pub exp: i64,
+}
+
+/// Resposta do primeiro passo do login quando a conta tem dois fatores ativos.
+#[derive(Serialize)]
+pub struct DesafioLoginResponse {
+    pub status: String,
+    pub message: String,
+    pub desafio_token: String,
+}
+
+/// Estrutura para o segundo passo do login: o token de desafio e um código TOTP
+/// ou, na falta do autenticador, um código de recuperação.
+#[derive(Deserialize)]
+pub struct Login2faRequest {
+    pub desafio_token: String,
+    pub codigo: Option<String>,
+    pub codigo_recuperacao: Option<String>,
+}
+
+/// Resposta da inscrição em dois fatores, com o segredo e a URI para o QR code.
+#[derive(Serialize)]
+pub struct Inscricao2faResponse {
+    pub segredo: String,
+    pub uri_provisionamento: String,
+}
+
+/// Estrutura para confirmar a ativação da autenticação em dois fatores.
+#[derive(Deserialize)]
+pub struct Ativacao2fa {
+    pub codigo: String,
+}
+
+/// Estrutura para desativar a autenticação em dois fatores.
+#[derive(Deserialize)]
+pub struct Desativacao2fa {
+    pub senha: String,
+    pub codigo: String,
+}
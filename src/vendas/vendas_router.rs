@@ -1,7 +1,10 @@
 // src/vendas/vendas_router.rs
 
-use actix_web::{get, post, web, HttpResponse};
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse};
 use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::RwLock;
 
 // Importa o AppState do módulo raiz (main.rs)
@@ -10,48 +13,133 @@ use crate::AppState;
 use crate::produtos::produtos_structs::Produto;
 // Importa GenericResponse do novo módulo shared_structs
 use crate::shared::shared_structs::GenericResponse;
+// Importa a execução única por chave de idempotência
+use crate::shared::idempotencia::{com_efeito_gravado, com_idempotencia};
+// Importa a identificação de conflitos de concorrência que podem ser repetidos
+use crate::shared::concorrencia::erro_transitorio;
 // Importa as structs de vendas (ItemVenda, VendaResponse, Carrinho)
-use super::vendas_structs::{ItemVenda, VendaResponse, Carrinho}; 
+use super::vendas_structs::{ItemVenda, VendaResponse, Carrinho, StatusVenda, AtualizarStatusVenda, FinalizacaoVenda, ErroItemVenda};
+// Importa os extratores de autenticação e autorização
+use crate::usuarios::auth_middleware::{AuthenticatedUser, AdminUser};
+// Importa a gravação da trilha de auditoria
+use crate::auditoria::auditoria_service::registrar_auditoria;
+use crate::auditoria::auditoria_structs::AcaoAuditoria;
+// Importa o cálculo do frete da venda
+use crate::frete::frete_service::{cotar_modalidade, montar_pedido_frete, ErroFrete};
+use crate::frete::frete_structs::OpcaoFrete;
+// Importa a cobrança do pedido no gateway de pagamento
+use crate::pagamentos::pagamentos_service::{cancelar_na_transacao, processar_pagamento, validade_reserva, FalhaPagamento};
+use crate::pagamentos::pagamentos_structs::{Cobranca, MetodoPagamento, SituacaoPagamento};
 
 
-/// Rota para realizar uma venda de produtos, consumindo itens da sacola.
+/// Rota para realizar uma venda de produtos, consumindo itens da sacola
+/// ou, no modo "comprar agora", os itens enviados em `itens`.
 ///
 /// Esta função orquestra o processo de venda, garantindo a atomicidade das operações
 /// de verificação de estoque, cálculo do total e atualização do estoque através de uma transação de banco de dados.
 ///
 /// Passos:
-/// 1. Obtém os itens da sacola e a limpa.
+/// 0. Exige que o usuário autenticado tenha verificado o e-mail e copia o endereço de entrega
+///    (o informado em `endereco_id` ou, na falta dele, o endereço padrão).
+/// 1. Usa os itens enviados no corpo ou, na falta deles, retira os itens da sacola (que fica vazia).
 /// 2. Inicia uma transação no banco de dados.
-/// 3. Para cada item na sacola:
-///    a. Busca o produto e o bloqueia para atualização (`FOR UPDATE`).
-///    b. Verifica a disponibilidade de estoque.
-///    c. Calcula o subtotal e adiciona ao total da compra.
-///    d. Decrementa o estoque do produto.
-/// 4. Se todas as operações forem bem-sucedidas, comita a transação.
-/// 5. Retorna o valor total da compra em caso de sucesso ou uma mensagem de erro.
+/// 3. Bloqueia todos os produtos da venda para atualização em um único `SELECT ... ORDER BY id FOR UPDATE`
+///    (a ordem fixa evita deadlocks entre vendas concorrentes) e, para cada item:
+///    a. Verifica a quantidade e a disponibilidade de estoque. Os problemas de todos os itens
+///       são reunidos e devolvidos juntos, e nesse caso a venda não é feita.
+///    b. Calcula o subtotal e adiciona ao total da compra.
+///    Em seguida decrementa o estoque de todos os produtos em um único comando (reserva até a
+///    confirmação do pagamento).
+/// 4. Cota o frete na modalidade escolhida e o soma ao total.
+/// 5. Registra o pedido como pendente, com a cópia do endereço, o frete e o prazo da reserva,
+///    e seus itens (com o preço praticado) para o usuário autenticado.
+/// 6. Se todas as operações forem bem-sucedidas, comita a transação. Se o banco desfizer a
+///    transação por deadlock ou falha de serialização, ela é repetida (até 3 tentativas).
+/// 7. Cobra o pedido no gateway: cartões são capturados na hora e confirmam o pedido; Pix e boleto
+///    aguardam o webhook. Pagamentos recusados cancelam o pedido e devolvem o estoque; reservas não
+///    pagas são liberadas quando expiram.
+/// 8. Retorna o ID do pedido, o valor total e os dados do pagamento, ou uma mensagem de erro.
+///
+/// Aceita o cabeçalho `Idempotency-Key`: um cliente que repete a requisição (ex.: após um timeout)
+/// recebe a resposta da primeira tentativa, sem criar outro pedido.
 #[post("/venda")]
 pub async fn realizar_venda(
     data: web::Data<AppState>,
     carrinho_data: web::Data<RwLock<Carrinho>>, // Acesso ao estado da sacola
+    auth_user: AuthenticatedUser,
+    finalizacao: web::Json<FinalizacaoVenda>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    // Pega os itens da sacola e limpa-a. Isso é feito dentro de um bloco para liberar o lock de escrita rapidamente.
-    let itens_venda = {
-        let mut carrinho = carrinho_data.write().unwrap();
-        if carrinho.itens.is_empty() {
-            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+    let venda = processar_venda(&data, &carrinho_data, &auth_user, &finalizacao);
+    com_idempotencia(&data.db_pool, &req, Some(auth_user.user_id), &*finalizacao, venda).await
+}
+
+/// Executa a venda descrita em `realizar_venda`.
+async fn processar_venda(
+    data: &web::Data<AppState>,
+    carrinho_data: &web::Data<RwLock<Carrinho>>,
+    auth_user: &AuthenticatedUser,
+    finalizacao: &FinalizacaoVenda,
+) -> HttpResponse {
+    // Bloqueia a compra enquanto o e-mail da conta não for verificado
+    let email_verificado = sqlx::query_scalar::<_, bool>(
+        "SELECT email_verificado_em IS NOT NULL FROM usuarios WHERE id = $1 AND anonimizado_em IS NULL"
+    )
+    .bind(auth_user.user_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    match email_verificado {
+        Ok(Some(true)) => {},
+        Ok(_) => {
+            return HttpResponse::Forbidden().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Confirme o seu e-mail antes de finalizar a compra.".to_string(),
+                body: None,
+            });
+        },
+        Err(e) => {
+            eprintln!("Erro ao verificar e-mail do usuário {}: {:?}", auth_user.user_id, e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: "A sacola está vazia. Adicione itens antes de realizar a venda.".to_string(),
+                message: "Erro interno ao processar venda".to_string(),
                 body: None,
             });
         }
-        std::mem::take(&mut carrinho.itens) // Pega os itens e deixa o vetor vazio
-    };
+    }
 
-    // Inicia uma transação no banco de dados para garantir atomicidade
-    let mut transaction = match data.db_pool.begin().await {
-        Ok(tx) => tx,
+    // Copia o endereço de entrega. A cópia é gravada no pedido, que não muda se o
+    // cliente alterar ou excluir o endereço depois. É feita antes de esvaziar a sacola.
+    let endereco_result = sqlx::query_scalar::<_, serde_json::Value>(
+        r#"
+        SELECT jsonb_build_object(
+            'cep', cep, 'logradouro', logradouro, 'numero', numero, 'complemento', complemento,
+            'bairro', bairro, 'cidade', cidade, 'uf', uf
+        )
+        FROM enderecos
+        WHERE usuario_id = $1 AND (($2::int IS NULL AND padrao) OR id = $2)
+        "#
+    )
+    .bind(auth_user.user_id)
+    .bind(finalizacao.endereco_id)
+    .fetch_optional(&data.db_pool)
+    .await;
+
+    let endereco_entrega = match endereco_result {
+        Ok(Some(endereco)) => endereco,
+        Ok(None) => {
+            let message = match finalizacao.endereco_id {
+                Some(id) => format!("Endereço com ID {} não encontrado.", id),
+                None => "Informe o endereço de entrega ou cadastre um endereço padrão.".to_string(),
+            };
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message,
+                body: None,
+            });
+        },
         Err(e) => {
-            eprintln!("Erro ao iniciar transação: {:?}", e);
+            eprintln!("Erro ao buscar endereço de entrega do usuário {}: {:?}", auth_user.user_id, e);
             return HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
                 message: "Erro interno ao processar venda".to_string(),
@@ -60,115 +148,516 @@ pub async fn realizar_venda(
         }
     };
 
-    let mut total_compra = BigDecimal::from(0); // Inicializa o total da compra com 0
+    if !data.calculadoras_frete.iter().any(|c| c.modalidade() == finalizacao.modalidade_frete) {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Modalidade de frete '{}' não existe. Consulte /frete/cotacao.", finalizacao.modalidade_frete),
+            body: None,
+        });
+    }
+    let cep_entrega = endereco_entrega["cep"].as_str().unwrap_or_default().to_string();
 
-    // Itera sobre cada item na sacola
-    for item in itens_venda.iter() {
-        // 1. Busca o produto no banco de dados para verificar estoque e preço
-        // FOR UPDATE bloqueia a linha para evitar race conditions em ambientes multi-usuário
-        let produto_result = sqlx::query_as::<_, Produto>(
-            "SELECT id, nome, descricao, preco, estoque FROM produtos WHERE id = $1 FOR UPDATE"
-        )
-        .bind(item.produto_id)
-        .fetch_optional(&mut *transaction) // Usa a transação para a consulta
-        .await;
-
-        let produto = match produto_result {
-            Ok(Some(p)) => p,
-            Ok(None) => {
-                eprintln!("Produto com ID {} não encontrado durante a venda.", item.produto_id);
-                let _ = transaction.rollback().await;
+    if finalizacao.metodo_pagamento == MetodoPagamento::Cartao && finalizacao.token_cartao.is_none() {
+        return HttpResponse::BadRequest().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: "Informe o token do cartão para pagar com cartão.".to_string(),
+            body: None,
+        });
+    }
+
+    // Usa os itens enviados no corpo ("comprar agora") ou, na falta deles, os da sacola
+    let (itens_venda, sacola_id) = match &finalizacao.itens {
+        Some(itens) if itens.is_empty() => {
+            return HttpResponse::BadRequest().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Informe ao menos um item para realizar a venda.".to_string(),
+                body: None,
+            });
+        },
+        Some(itens) => (itens.clone(), None),
+        // Pega os itens da sacola e limpa-a. Isso é feito dentro de um bloco para liberar o lock de escrita rapidamente.
+        None => {
+            let mut carrinho = carrinho_data.write().unwrap();
+            if carrinho.itens.is_empty() {
                 return HttpResponse::BadRequest().json(GenericResponse::<()>{
                     status: "error".to_string(),
-                    message: format!("Produto com ID {} não encontrado para venda.", item.produto_id),
-                    body: None,
-                });
-            },
-            Err(e) => {
-                eprintln!("Erro ao buscar produto {}: {:?}", item.produto_id, e);
-                let _ = transaction.rollback().await;
-                return HttpResponse::InternalServerError().json(GenericResponse::<()>{
-                    status: "error".to_string(),
-                    message: "Erro ao buscar produto para venda".to_string(),
+                    message: "A sacola está vazia. Adicione itens antes de realizar a venda.".to_string(),
                     body: None,
                 });
             }
-        };
+            // Pega os itens e deixa o vetor vazio; a próxima adição inicia uma nova sacola.
+            // Se a venda não for concluída, os itens são devolvidos com `Carrinho::devolver`.
+            (std::mem::take(&mut carrinho.itens), carrinho.sacola_id.take())
+        },
+    };
 
-        // 2. Verifica se há estoque suficiente
-        if produto.estoque < item.quantidade {
-            eprintln!("Estoque insuficiente para o produto {}. Disponível: {}, Solicitado: {}",
-                      produto.nome, produto.estoque, item.quantidade);
-            let _ = transaction.rollback().await;
+    let registro = registrar_venda_com_novas_tentativas(
+        data, auth_user.user_id, finalizacao, &endereco_entrega, &cep_entrega, &itens_venda, sacola_id,
+    ).await;
+
+    // Se a venda não foi gravada, os itens voltam para a sacola, com o mesmo registro de origem
+    let itens_da_sacola = finalizacao.itens.is_none().then_some(itens_venda);
+    if let (Err(_), Some(itens)) = (&registro, &itens_da_sacola) {
+        carrinho_data.write().unwrap().devolver(itens.clone(), sacola_id);
+    }
+
+    let registro = match registro {
+        Ok(registro) => registro,
+        Err(FalhaVenda::Itens(erros_itens)) => {
+            return HttpResponse::BadRequest().json(GenericResponse {
+                status: "error".to_string(),
+                message: format!("{} item(ns) da venda não pode(m) ser atendido(s).", erros_itens.len()),
+                body: Some(erros_itens),
+            });
+        },
+        Err(FalhaVenda::FreteIndisponivel) => {
             return HttpResponse::BadRequest().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: format!("Estoque insuficiente para o produto {}.", produto.nome),
+                message: format!("A modalidade de frete '{}' não atende este endereço.", finalizacao.modalidade_frete),
+                body: None,
+            });
+        },
+        Err(FalhaVenda::Frete(e)) => {
+            eprintln!("Erro ao calcular frete da venda: {}", e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao calcular frete".to_string(),
+                body: None,
+            });
+        },
+        Err(FalhaVenda::Banco(e)) => {
+            eprintln!("Erro ao registrar venda: {:?}", e);
+            return HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro interno ao processar venda".to_string(),
                 body: None,
             });
         }
+    };
 
-        // Calcula o subtotal para o item e adiciona ao total da compra
-        let quantidade_bigdecimal = BigDecimal::from(item.quantidade);
-        let subtotal = &produto.preco * &quantidade_bigdecimal;
-        total_compra += subtotal;
+    // O pedido já foi gravado: daqui em diante até as respostas de erro ficam guardadas pela
+    // idempotência, para que a repetição com a mesma chave não crie e cobre outro pedido
+    com_efeito_gravado(cobrar_venda(data, carrinho_data, finalizacao, registro, itens_da_sacola).await)
+}
 
-        // 3. Decrementa o estoque do produto
-        let novo_estoque = produto.estoque - item.quantidade;
-        let update_result = sqlx::query(
-            "UPDATE produtos SET estoque = $1 WHERE id = $2"
-        )
-        .bind(novo_estoque)
-        .bind(item.produto_id)
-        .execute(&mut *transaction)
-        .await;
-
-        if let Err(e) = update_result {
-            eprintln!("Erro ao atualizar estoque do produto {}: {:?}", produto.nome, e);
-            let _ = transaction.rollback().await;
+/// Cobra um pedido recém-gravado e monta a resposta da venda.
+/// Se o pedido for cancelado pela recusa do pagamento, os itens vindos da sacola voltam para ela.
+async fn cobrar_venda(
+    data: &web::Data<AppState>,
+    carrinho_data: &web::Data<RwLock<Carrinho>>,
+    finalizacao: &FinalizacaoVenda,
+    registro: VendaRegistrada,
+    itens_da_sacola: Option<Vec<ItemVenda>>,
+) -> HttpResponse {
+    let VendaRegistrada { venda_id, total_compra, frete, reserva_expira_em } = registro;
+
+    // Cobra o pedido no gateway de pagamento
+    let cobranca = Cobranca {
+        venda_id,
+        valor: total_compra.clone(),
+        metodo: finalizacao.metodo_pagamento,
+        token_cartao: finalizacao.token_cartao.clone(),
+        expira_em: reserva_expira_em,
+    };
+
+    let resultado = processar_pagamento(data, &cobranca).await;
+    // O registro de origem da sacola fica com o pedido cancelado; a próxima adição abre outro
+    if let (Err(FalhaPagamento::Recusado(_) | FalhaPagamento::Gateway(_)), Some(itens)) = (&resultado, itens_da_sacola) {
+        carrinho_data.write().unwrap().devolver(itens, None);
+    }
+
+    let pagamento = match resultado {
+        Ok(pagamento) => pagamento,
+        Err(FalhaPagamento::Recusado(motivo)) => {
+            return HttpResponse::PaymentRequired().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Pagamento recusado: {} O pedido {} foi cancelado.", motivo, venda_id),
+                body: None,
+            });
+        },
+        Err(FalhaPagamento::Gateway(e)) => {
+            eprintln!("Erro no gateway ao cobrar o pedido {}: {}", venda_id, e);
+            return HttpResponse::BadGateway().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Não foi possível processar o pagamento. O pedido {} foi cancelado.", venda_id),
+                body: None,
+            });
+        },
+        Err(FalhaPagamento::EmConferencia(e)) => {
+            eprintln!("Captura e anulação falharam no pedido {}; confira a cobrança no gateway: {}", venda_id, e);
+            return HttpResponse::BadGateway().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("Não foi possível confirmar o pagamento do pedido {}. Ele fica pendente até a conferência da cobrança; não repita a compra.", venda_id),
+                body: None,
+            });
+        },
+        Err(FalhaPagamento::Banco(e)) => {
+            eprintln!("Erro ao registrar pagamento do pedido {}: {:?}", venda_id, e);
             return HttpResponse::InternalServerError().json(GenericResponse::<()>{
                 status: "error".to_string(),
-                message: "Erro ao atualizar estoque durante a venda".to_string(),
+                message: format!("Erro interno ao processar o pagamento do pedido {}. Consulte o pedido antes de comprar de novo.", venda_id),
                 body: None,
             });
         }
-    }
+    };
 
-    // Se todas as operações foram bem-sucedidas, comita a transação
-    if let Err(e) = transaction.commit().await {
-        eprintln!("Erro ao comitar transação: {:?}", e);
-        return HttpResponse::InternalServerError().json(GenericResponse::<()>{
-            status: "error".to_string(),
-            message: "Erro interno ao finalizar venda".to_string(),
-            body: None,
-        });
-    }
+    let (status, mensagem) = if pagamento.situacao == SituacaoPagamento::Pago {
+        (StatusVenda::Confirmado, "Pagamento confirmado e sacola limpa.".to_string())
+    } else {
+        (StatusVenda::Pendente, "Pedido reservado; aguardando a confirmação do pagamento.".to_string())
+    };
 
-    // Retorna a resposta de sucesso com o total da compra
+    // Retorna a resposta de sucesso com o total da compra e os dados do pagamento
     HttpResponse::Ok().json(GenericResponse {
         status: "success".to_string(),
         message: "Venda realizada com sucesso!".to_string(),
         body: Some(VendaResponse {
+            venda_id,
             total_compra,
-            mensagem: "Venda processada e sacola limpa.".to_string(),
+            valor_frete: frete.valor,
+            prazo_entrega_dias: frete.prazo_dias,
+            status,
+            pagamento,
+            mensagem,
         }),
     })
 }
 
 
+/// Quantas vezes a transação da venda é tentada quando esbarra em deadlock ou falha de serialização.
+const TENTATIVAS_VENDA: u64 = 3;
+
+/// Pedido gravado pela transação da venda, ainda aguardando o pagamento.
+struct VendaRegistrada {
+    venda_id: i32,
+    total_compra: BigDecimal, // Itens mais frete
+    frete: OpcaoFrete,
+    reserva_expira_em: DateTime<Utc>,
+}
+
+/// Motivos para a transação da venda não ser concluída.
+enum FalhaVenda {
+    Itens(Vec<ErroItemVenda>),
+    FreteIndisponivel,
+    Frete(String),
+    Banco(sqlx::Error),
+}
+
+impl From<sqlx::Error> for FalhaVenda {
+    fn from(e: sqlx::Error) -> Self {
+        FalhaVenda::Banco(e)
+    }
+}
+
+/// Registra a venda. Em caso de deadlock ou falha de serialização, a transação inteira
+/// é desfeita pelo banco e repetida algumas vezes antes de desistir.
+async fn registrar_venda_com_novas_tentativas(
+    data: &web::Data<AppState>,
+    usuario_id: i32,
+    finalizacao: &FinalizacaoVenda,
+    endereco_entrega: &serde_json::Value,
+    cep_entrega: &str,
+    itens_venda: &[ItemVenda],
+    sacola_id: Option<i32>,
+) -> Result<VendaRegistrada, FalhaVenda> {
+    let mut tentativa = 1;
+    loop {
+        match registrar_venda(data, usuario_id, finalizacao, endereco_entrega, cep_entrega, itens_venda, sacola_id).await {
+            Err(FalhaVenda::Banco(e)) if tentativa < TENTATIVAS_VENDA && erro_transitorio(&e) => {
+                eprintln!("Conflito de concorrência ao registrar venda (tentativa {}): {:?}", tentativa, e);
+                actix_web::rt::time::sleep(std::time::Duration::from_millis(20 * tentativa)).await;
+                tentativa += 1;
+            },
+            resultado => return resultado,
+        }
+    }
+}
+
+/// Transação da venda: bloqueia os produtos, confere os itens, baixa o estoque, cota o frete
+/// e grava o pedido pendente com seus itens, ligado à sacola de origem (se houver).
+/// Qualquer falha desfaz tudo.
+///
+/// Os produtos são bloqueados em um único `SELECT ... ORDER BY id FOR UPDATE`: duas vendas com
+/// os mesmos produtos em ordens diferentes esperam uma pela outra em vez de entrar em deadlock.
+async fn registrar_venda(
+    data: &web::Data<AppState>,
+    usuario_id: i32,
+    finalizacao: &FinalizacaoVenda,
+    endereco_entrega: &serde_json::Value,
+    cep_entrega: &str,
+    itens_venda: &[ItemVenda],
+    sacola_id: Option<i32>,
+) -> Result<VendaRegistrada, FalhaVenda> {
+    let mut transaction = data.db_pool.begin().await?;
+
+    // 1. Bloqueia todos os produtos da venda de uma vez, sempre em ordem de ID
+    let ids: Vec<i32> = itens_venda.iter().map(|item| item.produto_id).collect();
+    let produtos: HashMap<i32, Produto> = sqlx::query_as::<_, Produto>(
+        r#"
+        SELECT id, nome, descricao, preco, estoque, categoria_id FROM produtos
+        WHERE id = ANY($1) AND deleted_at IS NULL
+        ORDER BY id
+        FOR UPDATE
+        "#
+    )
+    .bind(&ids)
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|produto| (produto.id, produto))
+    .collect();
+
+    // 2. Confere todos os itens. O mesmo produto pode aparecer em mais de um item,
+    //    então o estoque é comparado com a quantidade total pedida dele.
+    let mut quantidades: BTreeMap<i32, i32> = BTreeMap::new();
+    for item in itens_venda.iter().filter(|item| item.quantidade > 0) {
+        let total = quantidades.entry(item.produto_id).or_insert(0);
+        *total = total.saturating_add(item.quantidade);
+    }
+
+    let mut total_compra = BigDecimal::from(0); // Inicializa o total da compra com 0
+    let mut erros_itens: Vec<ErroItemVenda> = Vec::new(); // Problemas de todos os itens, devolvidos juntos
+    for (indice, item) in itens_venda.iter().enumerate() {
+        let motivo = match produtos.get(&item.produto_id) {
+            _ if item.quantidade <= 0 => "A quantidade deve ser maior que zero.".to_string(),
+            None => format!("Produto com ID {} não encontrado para venda.", item.produto_id),
+            Some(produto) if produto.estoque < quantidades[&item.produto_id] => {
+                eprintln!("Estoque insuficiente para o produto {}. Disponível: {}, Solicitado: {}",
+                          produto.nome, produto.estoque, quantidades[&item.produto_id]);
+                format!("Estoque insuficiente para o produto {}.", produto.nome)
+            },
+            Some(produto) => {
+                total_compra += &produto.preco * BigDecimal::from(item.quantidade);
+                continue;
+            },
+        };
+        erros_itens.push(ErroItemVenda { indice, produto_id: item.produto_id, motivo });
+    }
+    if !erros_itens.is_empty() {
+        return Err(FalhaVenda::Itens(erros_itens)); // A transação é desfeita ao ser descartada
+    }
+
+    // 3. Baixa o estoque de todos os produtos em um único comando.
+    //    A baixa de estoque também invalida edições concorrentes (versao).
+    let (ids_baixa, quantidades_baixa): (Vec<i32>, Vec<i32>) = quantidades.into_iter().unzip();
+    sqlx::query(
+        r#"
+        UPDATE produtos p SET estoque = p.estoque - b.quantidade, versao = p.versao + 1
+        FROM UNNEST($1::int[], $2::int[]) AS b(id, quantidade)
+        WHERE p.id = b.id
+        "#
+    )
+    .bind(&ids_baixa)
+    .bind(&quantidades_baixa)
+    .execute(&mut *transaction)
+    .await?;
+
+    // 4. Cota o frete na modalidade escolhida, com os preços e medidas lidos na transação
+    let frete = match montar_pedido_frete(&mut *transaction, cep_entrega, itens_venda).await {
+        Ok(pedido_frete) => cotar_modalidade(&data.calculadoras_frete, &finalizacao.modalidade_frete, &pedido_frete).await,
+        Err(ErroFrete::ProdutoNaoEncontrado(id)) => Err(format!("produto {} não encontrado", id)),
+        Err(ErroFrete::Banco(e)) => return Err(FalhaVenda::Banco(e)),
+    };
+    let frete = match frete {
+        Ok(Some(opcao)) => opcao,
+        Ok(None) => return Err(FalhaVenda::FreteIndisponivel),
+        Err(e) => return Err(FalhaVenda::Frete(e)),
+    };
+    total_compra += &frete.valor;
+
+    // 5. Registra o pedido como pendente: o estoque fica reservado até o pagamento ou a expiração
+    let reserva_expira_em = Utc::now() + validade_reserva(finalizacao.metodo_pagamento);
+    let venda_id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO vendas (usuario_id, total, status, endereco_entrega, frete_modalidade, frete_valor, frete_prazo_dias,
+                            metodo_pagamento, reserva_expira_em)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id
+        "#
+    )
+    .bind(usuario_id)
+    .bind(&total_compra)
+    .bind(StatusVenda::Pendente.as_str())
+    .bind(endereco_entrega)
+    .bind(&frete.modalidade)
+    .bind(&frete.valor)
+    .bind(frete.prazo_dias)
+    .bind(finalizacao.metodo_pagamento.as_str())
+    .bind(reserva_expira_em)
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    // 6. Registra os itens do pedido com o preço praticado, lido das linhas bloqueadas
+    let produtos_itens: Vec<i32> = itens_venda.iter().map(|item| item.produto_id).collect();
+    let quantidades_itens: Vec<i32> = itens_venda.iter().map(|item| item.quantidade).collect();
+    sqlx::query(
+        r#"
+        INSERT INTO vendas_itens (venda_id, produto_id, quantidade, preco_unitario)
+        SELECT $1, i.produto_id, i.quantidade, p.preco
+        FROM UNNEST($2::int[], $3::int[]) WITH ORDINALITY AS i(produto_id, quantidade, ordem)
+        JOIN produtos p ON p.id = i.produto_id
+        ORDER BY i.ordem
+        "#
+    )
+    .bind(venda_id)
+    .bind(&produtos_itens)
+    .bind(&quantidades_itens)
+    .execute(&mut *transaction)
+    .await?;
+
+    // 7. Marca a sacola de origem como convertida em pedido
+    if let Some(sacola_id) = sacola_id {
+        sqlx::query("UPDATE sacolas SET venda_id = $1 WHERE id = $2")
+            .bind(venda_id)
+            .bind(sacola_id)
+            .execute(&mut *transaction)
+            .await?;
+    }
+
+    transaction.commit().await?;
+    Ok(VendaRegistrada { venda_id, total_compra, frete, reserva_expira_em })
+}
+
+
+/// Resultado da mudança de situação de um pedido.
+enum ResultadoStatusVenda {
+    Alterado,
+    NaoEncontrado,
+    NaoPermitido(String), // Situação atual do pedido
+    PagamentoEmProcessamento, // Captura ou estorno em andamento no gateway
+}
+
+/// Rota administrativa para alterar a situação de um pedido.
+/// Só aceita as mudanças previstas em `StatusVenda::pode_mudar_para`; as demais recebem 409.
+/// Cancelar um pedido pendente devolve o estoque reservado, como na expiração da reserva.
+/// A alteração é registrada na auditoria com os snapshots anterior e posterior.
+#[put("/vendas/{id}/status")]
+pub async fn atualizar_status_venda(
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    item: web::Json<AtualizarStatusVenda>,
+    admin: AdminUser,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let novo = item.status;
+
+    let result: Result<ResultadoStatusVenda, sqlx::Error> = async {
+        let mut transaction = data.db_pool.begin().await?;
+
+        let row = sqlx::query("SELECT status, to_jsonb(v) AS antes FROM vendas v WHERE v.id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(ResultadoStatusVenda::NaoEncontrado),
+        };
+        let atual: String = row.try_get("status")?;
+        let antes: serde_json::Value = row.try_get("antes")?;
+
+        match StatusVenda::de_texto(&atual) {
+            Some(status) if status.pode_mudar_para(novo) => {},
+            _ => return Ok(ResultadoStatusVenda::NaoPermitido(atual)),
+        }
+
+        // Enquanto o gateway não responde a uma captura ou a um estorno, o pedido não muda
+        let em_processamento = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM pagamentos WHERE venda_id = $1 AND situacao IN ($2, $3))"
+        )
+        .bind(id)
+        .bind(SituacaoPagamento::Capturando.as_str())
+        .bind(SituacaoPagamento::Estornando.as_str())
+        .fetch_one(&mut *transaction)
+        .await?;
+        if em_processamento {
+            return Ok(ResultadoStatusVenda::PagamentoEmProcessamento);
+        }
+
+        if novo == StatusVenda::Cancelado {
+            cancelar_na_transacao(&mut transaction, id, SituacaoPagamento::Cancelado).await?;
+        } else {
+            sqlx::query("UPDATE vendas SET status = $1 WHERE id = $2")
+                .bind(novo.as_str())
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+        let depois = sqlx::query_scalar::<_, serde_json::Value>("SELECT to_jsonb(v) FROM vendas v WHERE v.id = $1")
+            .bind(id)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        registrar_auditoria(&mut transaction, &admin.0, "venda", id, AcaoAuditoria::AlteracaoStatus, Some(antes), Some(depois)).await?;
+        transaction.commit().await?;
+        Ok(ResultadoStatusVenda::Alterado)
+    }
+    .await;
+
+    match result {
+        Ok(ResultadoStatusVenda::Alterado) => HttpResponse::Ok().json(GenericResponse::<()>{
+            status: "success".to_string(),
+            message: format!("Status do pedido {} alterado para '{}'.", id, novo.as_str()),
+            body: None,
+        }),
+        Ok(ResultadoStatusVenda::NaoEncontrado) => HttpResponse::NotFound().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("Pedido com ID {} não encontrado.", id),
+            body: None,
+        }),
+        Ok(ResultadoStatusVenda::NaoPermitido(atual)) => {
+            let dica = if novo == StatusVenda::Cancelado && atual != StatusVenda::Cancelado.as_str() {
+                " Para cancelar um pedido pago, use o estorno (POST /vendas/{id}/estorno)."
+            } else {
+                ""
+            };
+            HttpResponse::Conflict().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: format!("O pedido {} não pode passar de '{}' para '{}'.{}", id, atual, novo.as_str(), dica),
+                body: None,
+            })
+        },
+        Ok(ResultadoStatusVenda::PagamentoEmProcessamento) => HttpResponse::Conflict().json(GenericResponse::<()>{
+            status: "error".to_string(),
+            message: format!("O pagamento do pedido {} está em captura ou estorno no gateway. Confira a cobrança antes de alterar o pedido.", id),
+            body: None,
+        }),
+        Err(e) => {
+            eprintln!("Erro ao alterar status do pedido {}: {:?}", id, e);
+            HttpResponse::InternalServerError().json(GenericResponse::<()>{
+                status: "error".to_string(),
+                message: "Erro ao alterar status do pedido".to_string(),
+                body: None,
+            })
+        }
+    }
+}
+
+
 
 // --- Rotas para a funcionalidade de Sacola (Movidas para o módulo de Vendas) ---
 
 /// Rota para adicionar um item à sacola de compras.
 /// Recebe um ItemVenda no corpo da requisição.
+/// Com o cabeçalho `Idempotency-Key`, uma repetição não soma a quantidade de novo.
 #[post("/sacola/adicionar")]
 pub async fn adicionar_item_sacola(
     carrinho_data: web::Data<RwLock<Carrinho>>, // Acesso ao estado da sacola
     item_venda: web::Json<ItemVenda>,
     data: web::Data<AppState>, // Necessário para verificar o produto no DB
+    req: HttpRequest,
+) -> HttpResponse {
+    let adicao = processar_adicao_sacola(&carrinho_data, &item_venda, &data);
+    com_idempotencia(&data.db_pool, &req, None, &*item_venda, adicao).await
+}
+
+/// Adiciona o item à sacola, somando a quantidade se o produto já estiver nela.
+pub(crate) async fn processar_adicao_sacola(
+    carrinho_data: &web::Data<RwLock<Carrinho>>,
+    item_venda: &ItemVenda,
+    data: &web::Data<AppState>,
 ) -> HttpResponse {
     // Verifica se o produto existe no banco de dados
     let produto_exists = sqlx::query_as::<_, Produto>(
-        "SELECT id, nome, descricao, preco, estoque, categoria_id FROM produtos WHERE id = $1" 
+        "SELECT id, nome, descricao, preco, estoque, categoria_id FROM produtos WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(item_venda.produto_id)
     .fetch_optional(&data.db_pool)
@@ -176,7 +665,17 @@ pub async fn adicionar_item_sacola(
 
     match produto_exists {
         Ok(Some(_)) => {
+            // A primeira adição registra o início da sacola, usado na taxa de conversão dos relatórios
+            let nova_sacola = if carrinho_data.read().unwrap().sacola_id.is_none() {
+                registrar_sacola(&data.db_pool).await
+            } else {
+                None
+            };
+
             let mut carrinho = carrinho_data.write().unwrap(); // Obtém um lock de escrita
+            if carrinho.sacola_id.is_none() {
+                carrinho.sacola_id = nova_sacola;
+            }
 
             // Verifica se o produto já existe na sacola
             let mut found = false;
@@ -190,7 +689,7 @@ pub async fn adicionar_item_sacola(
 
             if !found {
                 // Se o produto não foi encontrado, adiciona como um novo item
-                carrinho.itens.push(item_venda.into_inner());
+                carrinho.itens.push(item_venda.clone());
             }
 
             HttpResponse::Ok().json(GenericResponse::<()>{
@@ -217,6 +716,17 @@ pub async fn adicionar_item_sacola(
     }
 }
 
+/// Registra o início de uma sacola. Uma falha aqui não impede a compra, só deixa a sacola fora dos relatórios.
+async fn registrar_sacola(pool: &sqlx::Pool<sqlx::Postgres>) -> Option<i32> {
+    match sqlx::query_scalar::<_, i32>("INSERT INTO sacolas DEFAULT VALUES RETURNING id").fetch_one(pool).await {
+        Ok(id) => Some(id),
+        Err(e) => {
+            eprintln!("Erro ao registrar sacola: {:?}", e);
+            None
+        }
+    }
+}
+
 /// Rota para visualizar o conteúdo atual da sacola de compras.
 #[get("/sacola")]
 pub async fn ver_sacola(carrinho_data: web::Data<RwLock<Carrinho>>) -> HttpResponse {
@@ -229,3 +739,103 @@ pub async fn ver_sacola(carrinho_data: web::Data<RwLock<Carrinho>>) -> HttpRespo
     })
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::{join, join_all};
+    use std::cell::Cell;
+
+    use crate::shared::testes::{criar_produto, criar_usuario, estado_de_teste, gateway_de_teste};
+
+    /// Estoque inicial de cada produto do teste de concorrência.
+    const ESTOQUE_INICIAL: i32 = 15;
+
+    /// Vendas simultâneas com os mesmos produtos em ordens diferentes (inclusive invertida) não podem
+    /// entrar em deadlock, vender além do estoque nem deixar o estoque negativo em nenhum momento.
+    /// Cada venda termina registrada ou recusada por falta de estoque, nunca com erro de banco.
+    #[actix_web::test]
+    async fn vendas_simultaneas_nao_entram_em_deadlock_nem_vendem_alem_do_estoque() {
+        let data = match estado_de_teste(gateway_de_teste()).await {
+            Some(data) => data,
+            None => return,
+        };
+        let usuario_id = criar_usuario(&data).await;
+        let mut produtos = Vec::new();
+        for _ in 0..4 {
+            produtos.push(criar_produto(&data, 10, ESTOQUE_INICIAL).await);
+        }
+        let [a, b, c, d] = [produtos[0], produtos[1], produtos[2], produtos[3]];
+        // Procura maior que o estoque em todos os produtos, para que parte das vendas seja recusada
+        let combinacoes = [vec![a, b, c], vec![c, b, a], vec![b, c, d], vec![d, c, b, a]];
+
+        let finalizacao = FinalizacaoVenda {
+            itens: None,
+            endereco_id: None,
+            modalidade_frete: "retirada".to_string(),
+            metodo_pagamento: MetodoPagamento::Pix,
+            token_cartao: None,
+        };
+        let endereco = serde_json::json!({ "cep": "01310-100" });
+        let vendas = (0..40).map(|i| {
+            let itens: Vec<ItemVenda> = combinacoes[i % 4].iter().map(|&produto_id| ItemVenda { produto_id, quantidade: 1 }).collect();
+            let (data, finalizacao, endereco) = (&data, &finalizacao, &endereco);
+            // Mesmo caminho da rota, com as novas tentativas em caso de conflito de concorrência
+            async move { registrar_venda_com_novas_tentativas(data, usuario_id, finalizacao, endereco, "01310-100", &itens, None).await }
+        });
+
+        // Enquanto as vendas correm, confere o menor estoque dos produtos
+        let terminou = Cell::new(false);
+        let menor_estoque = Cell::new(i32::MAX);
+        let amostragem = async {
+            while !terminou.get() {
+                let menor = sqlx::query_scalar::<_, i32>("SELECT MIN(estoque) FROM produtos WHERE id = ANY($1)")
+                    .bind(&produtos)
+                    .fetch_one(&data.db_pool)
+                    .await
+                    .unwrap();
+                menor_estoque.set(menor_estoque.get().min(menor));
+                actix_web::rt::time::sleep(std::time::Duration::from_millis(1)).await;
+            }
+        };
+        let execucao = async {
+            let resultados = join_all(vendas).await;
+            terminou.set(true);
+            resultados
+        };
+        let (resultados, ()) = join(execucao, amostragem).await;
+
+        let mut vendidas = Vec::new();
+        let mut recusadas = 0;
+        for resultado in resultados {
+            match resultado {
+                Ok(registro) => vendidas.push(registro.venda_id),
+                Err(FalhaVenda::Itens(_)) => recusadas += 1,
+                // Inclui deadlocks que persistiram depois de todas as tentativas
+                Err(FalhaVenda::Banco(e)) => panic!("Erro de banco em venda simultânea: {:?}", e),
+                Err(FalhaVenda::Frete(e)) => panic!("Erro de frete em venda simultânea: {}", e),
+                Err(FalhaVenda::FreteIndisponivel) => panic!("Frete indisponível em venda simultânea"),
+            }
+        }
+        assert!(!vendidas.is_empty() && recusadas > 0, "vendidas: {}, recusadas: {}", vendidas.len(), recusadas);
+        assert!(menor_estoque.get() >= 0, "estoque negativo durante as vendas: {}", menor_estoque.get());
+
+        for produto_id in produtos {
+            let estoque = sqlx::query_scalar::<_, i32>("SELECT estoque FROM produtos WHERE id = $1")
+                .bind(produto_id)
+                .fetch_one(&data.db_pool)
+                .await
+                .unwrap();
+            let vendido = sqlx::query_scalar::<_, i64>(
+                "SELECT COALESCE(SUM(quantidade), 0) FROM vendas_itens WHERE produto_id = $1 AND venda_id = ANY($2)"
+            )
+            .bind(produto_id)
+            .bind(&vendidas)
+            .fetch_one(&data.db_pool)
+            .await
+            .unwrap();
+            assert!(estoque >= 0);
+            assert_eq!(estoque as i64, ESTOQUE_INICIAL as i64 - vendido, "estoque do produto {}", produto_id);
+        }
+    }
+}
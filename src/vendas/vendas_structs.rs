@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use bigdecimal::BigDecimal;
 
+use crate::pagamentos::pagamentos_structs::{MetodoPagamento, PagamentoResponse};
+
 /// Estrutura para representar um item individual dentro de uma venda ou sacola.
 /// É usada tanto para adicionar itens à sacola quanto para processar a venda.
 #[derive(Deserialize, Serialize, Clone)]
@@ -11,11 +13,38 @@ pub struct ItemVenda {
     pub quantidade: i32,
 }
 
+/// Estrutura para receber os dados de entrega ao finalizar a venda.
+/// Sem `endereco_id`, é usado o endereço padrão do cliente.
+/// Com `itens`, a venda é feita com esses itens ("comprar agora", PDV da loja) e a sacola não é usada.
+#[derive(Deserialize, Serialize)]
+pub struct FinalizacaoVenda {
+    #[serde(default)]
+    pub itens: Option<Vec<ItemVenda>>,
+    pub endereco_id: Option<i32>,
+    pub modalidade_frete: String, // Código retornado por `/frete/cotacao`
+    pub metodo_pagamento: MetodoPagamento,
+    pub token_cartao: Option<String>, // Obrigatório para cartão; gerado pelo gateway no navegador
+}
+
+/// Problema encontrado em um item da venda.
+/// Todos os itens são conferidos, e os problemas são devolvidos juntos.
+#[derive(Serialize)]
+pub struct ErroItemVenda {
+    pub indice: usize, // Posição do item na lista (a partir de 0)
+    pub produto_id: i32,
+    pub motivo: String,
+}
+
 /// Estrutura para a resposta de sucesso da venda.
 /// Contém o valor total da compra e uma mensagem de confirmação.
 #[derive(Serialize)]
 pub struct VendaResponse {
-    pub total_compra: BigDecimal,
+    pub venda_id: i32,
+    pub total_compra: BigDecimal, // Itens mais frete
+    pub valor_frete: BigDecimal,
+    pub prazo_entrega_dias: i32,
+    pub status: StatusVenda,
+    pub pagamento: PagamentoResponse,
     pub mensagem: String,
 }
 
@@ -23,4 +52,136 @@ pub struct VendaResponse {
 #[derive(Default)] // Permite criar uma instância padrão (com vetor vazio)
 pub struct Carrinho {
     pub itens: Vec<ItemVenda>,
+    pub sacola_id: Option<i32>, // Registro da sacola em `sacolas`, usado na taxa de conversão
+}
+
+impl Carrinho {
+    /// Devolve à sacola os itens retirados para uma venda que não foi concluída. Itens adicionados
+    /// nesse meio-tempo são mantidos, somando as quantidades quando o produto se repete.
+    pub fn devolver(&mut self, itens: Vec<ItemVenda>, sacola_id: Option<i32>) {
+        let adicionados = std::mem::replace(&mut self.itens, itens);
+        for item in adicionados {
+            match self.itens.iter_mut().find(|existente| existente.produto_id == item.produto_id) {
+                Some(existente) => existente.quantidade += item.quantidade,
+                None => self.itens.push(item),
+            }
+        }
+        if self.sacola_id.is_none() {
+            self.sacola_id = sacola_id;
+        }
+    }
+}
+
+/// Situações possíveis de um pedido (venda) ao longo do seu ciclo de vida.
+/// Persistidas como texto na coluna `vendas.status`.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusVenda {
+    Pendente,
+    Confirmado,
+    Enviado,
+    Entregue,
+    Cancelado,
+}
+
+impl StatusVenda {
+    /// Valor gravado na coluna `vendas.status`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusVenda::Pendente => "pendente",
+            StatusVenda::Confirmado => "confirmado",
+            StatusVenda::Enviado => "enviado",
+            StatusVenda::Entregue => "entregue",
+            StatusVenda::Cancelado => "cancelado",
+        }
+    }
+
+    /// Interpreta o valor gravado na coluna `vendas.status`.
+    pub fn de_texto(texto: &str) -> Option<StatusVenda> {
+        [StatusVenda::Pendente, StatusVenda::Confirmado, StatusVenda::Enviado, StatusVenda::Entregue, StatusVenda::Cancelado]
+            .into_iter()
+            .find(|status| status.as_str() == texto)
+    }
+
+    /// Mudanças de situação aceitas na rota administrativa `PUT /vendas/{id}/status`.
+    /// A confirmação só vem do pagamento, e um pedido pago só é cancelado pelo estorno
+    /// (`POST /vendas/{id}/estorno`), que devolve o dinheiro ao cliente.
+    pub fn pode_mudar_para(self, novo: StatusVenda) -> bool {
+        matches!(
+            (self, novo),
+            (StatusVenda::Pendente, StatusVenda::Cancelado)
+                | (StatusVenda::Confirmado, StatusVenda::Enviado)
+                | (StatusVenda::Enviado, StatusVenda::Entregue)
+        )
+    }
+
+    /// Pedidos que o estorno (`POST /vendas/{id}/estorno`) cancela: pagos e ainda não entregues.
+    /// Um pedido já cancelado que recebeu um pagamento tardio tem só o pagamento estornado.
+    pub fn cancelavel_por_estorno(self) -> bool {
+        matches!(self, StatusVenda::Confirmado | StatusVenda::Enviado)
+    }
+}
+
+/// Estrutura para receber a nova situação de um pedido na requisição PUT.
+#[derive(Deserialize)]
+pub struct AtualizarStatusVenda {
+    pub status: StatusVenda,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusVenda::*;
+
+    #[test]
+    fn mudancas_de_status_permitidas() {
+        assert!(Pendente.pode_mudar_para(Cancelado));
+        assert!(Confirmado.pode_mudar_para(Enviado));
+        assert!(Enviado.pode_mudar_para(Entregue));
+
+        // Confirmação só pelo pagamento; cancelamento de pedido pago só pelo estorno
+        assert!(!Pendente.pode_mudar_para(Confirmado));
+        assert!(!Confirmado.pode_mudar_para(Cancelado));
+        assert!(!Enviado.pode_mudar_para(Cancelado));
+        // Pedidos cancelados ou entregues não voltam atrás
+        for novo in [Pendente, Confirmado, Enviado, Entregue, Cancelado] {
+            assert!(!Cancelado.pode_mudar_para(novo));
+            assert!(!Entregue.pode_mudar_para(novo));
+        }
+    }
+
+    #[test]
+    fn estorno_cancela_apenas_pedidos_pagos_nao_entregues() {
+        assert!(Confirmado.cancelavel_por_estorno());
+        assert!(Enviado.cancelavel_por_estorno());
+        for status in [Pendente, Entregue, Cancelado] {
+            assert!(!status.cancelavel_por_estorno());
+        }
+    }
+
+    #[test]
+    fn status_lido_do_banco() {
+        for status in [Pendente, Confirmado, Enviado, Entregue, Cancelado] {
+            assert_eq!(super::StatusVenda::de_texto(status.as_str()), Some(status));
+        }
+        assert_eq!(super::StatusVenda::de_texto("pago"), None);
+    }
+
+    #[test]
+    fn itens_devolvidos_voltam_para_a_sacola_sem_perder_os_novos() {
+        use super::{Carrinho, ItemVenda};
+        let item = |produto_id, quantidade| ItemVenda { produto_id, quantidade };
+
+        // Durante a venda, o cliente adicionou outro produto e mais uma unidade de um dos retirados
+        let mut carrinho = Carrinho { itens: vec![item(3, 1), item(1, 1)], sacola_id: None };
+        carrinho.devolver(vec![item(1, 2), item(2, 1)], Some(7));
+
+        let itens: Vec<(i32, i32)> = carrinho.itens.iter().map(|i| (i.produto_id, i.quantidade)).collect();
+        assert_eq!(itens, [(1, 3), (2, 1), (3, 1)]);
+        assert_eq!(carrinho.sacola_id, Some(7));
+
+        // Uma sacola aberta nesse meio-tempo mantém o seu registro
+        let mut carrinho = Carrinho { itens: vec![item(3, 1)], sacola_id: Some(9) };
+        carrinho.devolver(vec![item(1, 2)], Some(7));
+        assert_eq!(carrinho.sacola_id, Some(9));
+    }
 }